@@ -0,0 +1,127 @@
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::SystemTime;
+
+/// Mapping of gpu timestamps to the cpu's wall clock, see [`crate::GpuProfiler::calibrate`].
+///
+/// Accuracy is limited by the time it takes to submit the calibration timestamp and read it back,
+/// which is reported as [`ClockCalibration::uncertainty_seconds`].
+/// Additionally, gpu and cpu clocks may drift apart over time, so calibrating periodically is advisable.
+/// Some devices reset their timestamp counter, e.g. on power state changes, invalidating a calibration entirely.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockCalibration {
+    /// Gpu time in seconds at calibration, in the same time base as [`crate::GpuTimerQueryResult::time`].
+    pub gpu_seconds: f64,
+
+    /// Wall clock time in seconds since the unix epoch that corresponds to [`ClockCalibration::gpu_seconds`].
+    ///
+    /// This is the midpoint between submitting the calibration timestamp and reading it back.
+    pub unix_seconds: f64,
+
+    /// Maximum error of [`ClockCalibration::unix_seconds`], i.e. half the time between submission and read back.
+    pub uncertainty_seconds: f64,
+
+    /// Relative rate at which the cpu clock advances faster than the gpu clock.
+    ///
+    /// Measured against the previous calibration, zero for the first calibration of a profiler.
+    pub drift: f64,
+}
+
+impl ClockCalibration {
+    /// Converts a gpu time in seconds, e.g. from [`crate::GpuTimerQueryResult::time`], to seconds since the unix epoch.
+    pub fn to_unix_seconds(&self, gpu_seconds: f64) -> f64 {
+        self.unix_seconds + (gpu_seconds - self.gpu_seconds) * (1.0 + self.drift)
+    }
+
+    /// Converts a gpu time in seconds, e.g. from [`crate::GpuTimerQueryResult::time`], to nanoseconds since the unix epoch.
+    pub fn to_unix_nanos(&self, gpu_seconds: f64) -> u64 {
+        (self.to_unix_seconds(gpu_seconds) * 1000.0 * 1000.0 * 1000.0) as u64
+    }
+
+    /// Measures a new calibration, taking drift relative to `previous` into account.
+    ///
+    /// Blocks until the calibration timestamp has been read back.
+    /// Returns `None` if reading back the timestamp failed.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn measure(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        previous: Option<&ClockCalibration>,
+    ) -> Option<Self> {
+        let submit_time = unix_seconds_now();
+        let timestamp = read_gpu_timestamp(device, queue)?;
+        let read_back_time = unix_seconds_now();
+
+        let gpu_seconds =
+            timestamp as f64 * queue.get_timestamp_period() as f64 / 1000.0 / 1000.0 / 1000.0;
+        let unix_seconds = (submit_time + read_back_time) / 2.0;
+        let drift = previous
+            .filter(|previous| gpu_seconds > previous.gpu_seconds)
+            .map_or(0.0, |previous| {
+                (unix_seconds - previous.unix_seconds) / (gpu_seconds - previous.gpu_seconds) - 1.0
+            });
+
+        Some(ClockCalibration {
+            gpu_seconds,
+            unix_seconds,
+            uncertainty_seconds: (read_back_time - submit_time) / 2.0,
+            drift,
+        })
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn unix_seconds_now() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+/// Writes a single timestamp, submits it and reads it back.
+///
+/// Blocks until the timestamp has been read back.
+/// Returns `None` if mapping the read back buffer failed,
+/// or if it didn't complete after polling, as is the case on WebGPU where polling can't block.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn read_gpu_timestamp(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<u64> {
+    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu sync query_set"),
+        ty: wgpu::QueryType::Timestamp,
+        count: 1,
+    });
+
+    let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu resolve buffer"),
+        size: wgpu::QUERY_SIZE as _,
+        usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let map_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu map buffer"),
+        size: wgpu::QUERY_SIZE as _,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu sync cmd_buf"),
+    });
+    encoder.write_timestamp(&query_set, 0);
+    encoder.resolve_query_set(&query_set, 0..1, &resolve_buffer, 0);
+    encoder.copy_buffer_to_buffer(&resolve_buffer, 0, &map_buffer, 0, wgpu::QUERY_SIZE as _);
+    queue.submit(Some(encoder.finish()));
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    map_buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |mapping_result| {
+            // The receiver is already gone if mapping didn't complete while polling.
+            let _ = sender.send(mapping_result);
+        });
+    device.poll(wgpu::Maintain::Wait);
+
+    receiver.try_recv().ok()?.ok()?;
+
+    let view = map_buffer.slice(..).get_mapped_range();
+    Some(u64::from_le_bytes((*view).try_into().ok()?))
+}
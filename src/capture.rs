@@ -0,0 +1,473 @@
+//! Compact binary capture format for storing many frames of profiling results.
+//!
+//! A capture starts with [`MAGIC`] and the format [`VERSION`], followed by any number of length prefixed frames.
+//! Strings like labels are interned, i.e. every distinct string is only stored once per capture
+//! and referred to by index afterwards.
+//! Times are stored as variable length integers in nanoseconds, start times of nested scopes relative to their
+//! parent's start time. Times read back are therefore rounded to whole nanoseconds.
+//! [`GpuTimerQueryResult::tid`] isn't stored, read results use the id of the reading thread instead.
+//!
+//! Frames read from a capture can be passed on to any of the other exporters,
+//! e.g. [`crate::chrometrace::ChromeTraceBuilder`] or [`crate::perfetto::write_trace`].
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{Read, Write},
+    ops::Range,
+};
+
+use crate::{GpuTimerQueryResult, PipelineStats, QueryId, TraceColor};
+
+/// Bytes every capture starts with.
+pub const MAGIC: [u8; 8] = *b"WGPUPROF";
+
+/// Version of the format written by [`Writer`]. [`Reader`] accepts captures of this or any older version.
+///
+/// Version 2 added [`GpuTimerQueryResult::category`].
+pub const VERSION: u32 = 2;
+
+const FLAG_TIME: u8 = 1 << 0;
+const FLAG_CPU_TIME: u8 = 1 << 1;
+const FLAG_TRACK_NAME: u8 = 1 << 2;
+const FLAG_COLOR: u8 = 1 << 3;
+const FLAG_PIPELINE_STATS: u8 = 1 << 4;
+const FLAG_CATEGORY: u8 = 1 << 5;
+
+/// All colors, stored by their index in this list.
+const COLORS: [TraceColor; 13] = [
+    TraceColor::Good,
+    TraceColor::Bad,
+    TraceColor::Terrible,
+    TraceColor::Yellow,
+    TraceColor::Olive,
+    TraceColor::Grey,
+    TraceColor::White,
+    TraceColor::Black,
+    TraceColor::RailResponse,
+    TraceColor::RailAnimation,
+    TraceColor::RailIdle,
+    TraceColor::RailLoad,
+    TraceColor::GenericWork,
+];
+
+/// Writes frames of profiling results to a capture.
+pub struct Writer<W: Write> {
+    writer: W,
+    strings: HashMap<String, u64>,
+    frame: Vec<u8>,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the capture header to `writer`.
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        let mut header = MAGIC.to_vec();
+        write_varint(&mut header, VERSION as u64);
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            writer,
+            strings: HashMap::new(),
+            frame: Vec::new(),
+        })
+    }
+
+    /// Appends a frame of profiling results, as returned by [`crate::GpuProfiler::process_finished_frame`].
+    pub fn write_frame(&mut self, profile_data: &[GpuTimerQueryResult]) -> std::io::Result<()> {
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.clear();
+        self.write_scopes(&mut frame, profile_data, 0);
+
+        let mut len = Vec::new();
+        write_varint(&mut len, frame.len() as u64);
+        let result = self
+            .writer
+            .write_all(&len)
+            .and_then(|()| self.writer.write_all(&frame));
+        self.frame = frame;
+        result
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_scopes(
+        &mut self,
+        buffer: &mut Vec<u8>,
+        scopes: &[GpuTimerQueryResult],
+        time_base_ns: i64,
+    ) {
+        write_varint(buffer, scopes.len() as u64);
+
+        for scope in scopes {
+            let mut flags = 0;
+            if scope.time.is_some() {
+                flags |= FLAG_TIME;
+            }
+            if scope.cpu_time.is_some() {
+                flags |= FLAG_CPU_TIME;
+            }
+            if scope.track_name.is_some() {
+                flags |= FLAG_TRACK_NAME;
+            }
+            if scope.color.is_some() {
+                flags |= FLAG_COLOR;
+            }
+            if scope.pipeline_stats.is_some() {
+                flags |= FLAG_PIPELINE_STATS;
+            }
+            if scope.category.is_some() {
+                flags |= FLAG_CATEGORY;
+            }
+            buffer.push(flags);
+
+            self.write_string(buffer, &scope.label);
+            write_varint(buffer, scope.id.0 as u64);
+            write_varint(buffer, scope.pid as u64);
+            write_varint(buffer, scope.timeline as u64);
+
+            let mut nested_time_base_ns = time_base_ns;
+            if let Some(time) = &scope.time {
+                nested_time_base_ns = write_time(buffer, time, time_base_ns);
+            }
+            if let Some(cpu_time) = &scope.cpu_time {
+                write_time(buffer, cpu_time, 0);
+            }
+            if let Some(track_name) = &scope.track_name {
+                self.write_string(buffer, track_name);
+            }
+            if let Some(color) = scope.color {
+                let index = COLORS.iter().position(|c| *c == color).unwrap();
+                buffer.push(index as u8);
+            }
+            if let Some(pipeline_stats) = &scope.pipeline_stats {
+                let values = pipeline_stats_values(pipeline_stats);
+                let mask = values
+                    .iter()
+                    .enumerate()
+                    .fold(0, |mask, (i, value)| mask | ((value.is_some() as u8) << i));
+                buffer.push(mask);
+                for value in values.into_iter().flatten() {
+                    write_varint(buffer, value);
+                }
+            }
+            if let Some(category) = &scope.category {
+                self.write_string(buffer, category);
+            }
+
+            write_varint(buffer, scope.metadata.len() as u64);
+            for (key, value) in &scope.metadata {
+                self.write_string(buffer, key);
+                write_bytes(buffer, value.as_bytes());
+            }
+
+            self.write_scopes(buffer, &scope.nested_queries, nested_time_base_ns);
+        }
+    }
+
+    /// Writes the index of an interned string, followed by the string itself if it wasn't written before.
+    fn write_string(&mut self, buffer: &mut Vec<u8>, string: &str) {
+        if let Some(&index) = self.strings.get(string) {
+            write_varint(buffer, index);
+            return;
+        }
+
+        let index = self.strings.len() as u64;
+        write_varint(buffer, index);
+        write_bytes(buffer, string.as_bytes());
+        self.strings.insert(string.to_owned(), index);
+    }
+}
+
+/// Reads frames of profiling results from a capture written by [`Writer`].
+///
+/// Also iterates over all remaining frames.
+pub struct Reader<R: Read> {
+    reader: R,
+    strings: Vec<String>,
+    frame: Vec<u8>,
+}
+
+impl<R: Read> Reader<R> {
+    /// Reads & validates the capture header from `reader`.
+    pub fn new(mut reader: R) -> std::io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("Not a wgpu-profiler capture"));
+        }
+        let version = read_varint(&mut reader)?;
+        if !(1..=VERSION as u64).contains(&version) {
+            return Err(invalid_data(format!(
+                "Unsupported capture version {version}, expected at most {VERSION}"
+            )));
+        }
+
+        Ok(Self {
+            reader,
+            strings: Vec::new(),
+            frame: Vec::new(),
+        })
+    }
+
+    /// Reads the next frame of profiling results.
+    ///
+    /// Returns `None` if the end of the capture was reached.
+    pub fn read_frame(&mut self) -> std::io::Result<Option<Vec<GpuTimerQueryResult>>> {
+        let mut first_byte = [0];
+        loop {
+            match self.reader.read(&mut first_byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let len = read_varint(&mut first_byte.as_slice().chain(&mut self.reader))?;
+
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.clear();
+        let result = self.read_frame_data(&mut frame, len);
+        self.frame = frame;
+
+        result.map(Some)
+    }
+
+    fn read_frame_data(
+        &mut self,
+        frame: &mut Vec<u8>,
+        len: u64,
+    ) -> std::io::Result<Vec<GpuTimerQueryResult>> {
+        if self.reader.by_ref().take(len).read_to_end(frame)? as u64 != len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut cursor = frame.as_slice();
+        let scopes = self.read_scopes(&mut cursor, 0, std::thread::current().id())?;
+        if !cursor.is_empty() {
+            return Err(invalid_data("Trailing bytes after frame"));
+        }
+        Ok(scopes)
+    }
+
+    fn read_scopes(
+        &mut self,
+        cursor: &mut &[u8],
+        time_base_ns: i64,
+        tid: std::thread::ThreadId,
+    ) -> std::io::Result<Vec<GpuTimerQueryResult>> {
+        let num_scopes = read_varint(cursor)?;
+
+        let mut scopes = Vec::new();
+        for _ in 0..num_scopes {
+            let flags = read_byte(cursor)?;
+
+            let label = self.read_string(cursor)?;
+            let id = QueryId(read_u32(cursor)?);
+            let pid = read_u32(cursor)?;
+            let timeline = read_u32(cursor)?;
+
+            let mut nested_time_base_ns = time_base_ns;
+            let time = if flags & FLAG_TIME != 0 {
+                let (time, start_ns) = read_time(cursor, time_base_ns)?;
+                nested_time_base_ns = start_ns;
+                Some(time)
+            } else {
+                None
+            };
+            let cpu_time = if flags & FLAG_CPU_TIME != 0 {
+                Some(read_time(cursor, 0)?.0)
+            } else {
+                None
+            };
+            let track_name = if flags & FLAG_TRACK_NAME != 0 {
+                Some(Cow::Owned(self.read_string(cursor)?))
+            } else {
+                None
+            };
+            let color = if flags & FLAG_COLOR != 0 {
+                let index = read_byte(cursor)?;
+                Some(
+                    *COLORS
+                        .get(index as usize)
+                        .ok_or_else(|| invalid_data("Invalid color"))?,
+                )
+            } else {
+                None
+            };
+            let pipeline_stats = if flags & FLAG_PIPELINE_STATS != 0 {
+                let mask = read_byte(cursor)?;
+                let mut values = [None; 5];
+                for (i, value) in values.iter_mut().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        *value = Some(read_varint(cursor)?);
+                    }
+                }
+                let [vertex_shader_invocations, clipper_invocations, clipper_primitives_out, fragment_shader_invocations, compute_shader_invocations] =
+                    values;
+                Some(PipelineStats {
+                    vertex_shader_invocations,
+                    clipper_invocations,
+                    clipper_primitives_out,
+                    fragment_shader_invocations,
+                    compute_shader_invocations,
+                })
+            } else {
+                None
+            };
+            let category = if flags & FLAG_CATEGORY != 0 {
+                Some(Cow::Owned(self.read_string(cursor)?))
+            } else {
+                None
+            };
+
+            let num_metadata = read_varint(cursor)?;
+            let mut metadata = Vec::new();
+            for _ in 0..num_metadata {
+                let key = self.read_string(cursor)?;
+                let value = read_utf8(cursor)?;
+                metadata.push((key, value));
+            }
+
+            let nested_queries = self.read_scopes(cursor, nested_time_base_ns, tid)?;
+
+            scopes.push(GpuTimerQueryResult {
+                label: Cow::Owned(label),
+                id,
+                pid,
+                tid,
+                time,
+                cpu_time,
+                timeline,
+                track_name,
+                metadata,
+                color,
+                category,
+                pipeline_stats,
+                nested_queries,
+            });
+        }
+
+        Ok(scopes)
+    }
+
+    /// Reads an interned string, see [`Writer::write_string`].
+    fn read_string(&mut self, cursor: &mut &[u8]) -> std::io::Result<String> {
+        let index = read_varint(cursor)?;
+        if index == self.strings.len() as u64 {
+            self.strings.push(read_utf8(cursor)?);
+        }
+        self.strings
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| invalid_data("Invalid string index"))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = std::io::Result<Vec<GpuTimerQueryResult>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+fn pipeline_stats_values(pipeline_stats: &PipelineStats) -> [Option<u64>; 5] {
+    [
+        pipeline_stats.vertex_shader_invocations,
+        pipeline_stats.clipper_invocations,
+        pipeline_stats.clipper_primitives_out,
+        pipeline_stats.fragment_shader_invocations,
+        pipeline_stats.compute_shader_invocations,
+    ]
+}
+
+fn seconds_to_ns(seconds: f64) -> i64 {
+    (seconds * 1_000_000_000.0).round() as i64
+}
+
+fn ns_to_seconds(ns: i64) -> f64 {
+    ns as f64 / 1_000_000_000.0
+}
+
+/// Writes start relative to `time_base_ns` and duration, returns the start in nanoseconds.
+fn write_time(buffer: &mut Vec<u8>, time: &Range<f64>, time_base_ns: i64) -> i64 {
+    let start_ns = seconds_to_ns(time.start);
+    write_signed_varint(buffer, start_ns.wrapping_sub(time_base_ns));
+    write_signed_varint(buffer, seconds_to_ns(time.end).wrapping_sub(start_ns));
+    start_ns
+}
+
+/// Reads a time written by [`write_time`] along with its start in nanoseconds.
+fn read_time(cursor: &mut &[u8], time_base_ns: i64) -> std::io::Result<(Range<f64>, i64)> {
+    let start_ns = time_base_ns.wrapping_add(read_signed_varint(cursor)?);
+    let end_ns = start_ns.wrapping_add(read_signed_varint(cursor)?);
+    Ok((ns_to_seconds(start_ns)..ns_to_seconds(end_ns), start_ns))
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Zigzag encodes the value, so that small negative values stay small.
+fn write_signed_varint(buffer: &mut Vec<u8>, value: i64) {
+    write_varint(buffer, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn read_byte(reader: &mut impl Read) -> std::io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_varint(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(reader)?;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("Varint is too long"))
+}
+
+fn read_signed_varint(reader: &mut impl Read) -> std::io::Result<i64> {
+    let value = read_varint(reader)?;
+    Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    u32::try_from(read_varint(reader)?).map_err(|_| invalid_data("Value is out of range"))
+}
+
+fn read_utf8(cursor: &mut &[u8]) -> std::io::Result<String> {
+    let len = read_varint(cursor)?;
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= cursor.len())
+        .ok_or_else(|| invalid_data("String exceeds frame"))?;
+    let (bytes, rest) = cursor.split_at(len);
+    *cursor = rest;
+    String::from_utf8(bytes.to_vec()).map_err(|_| invalid_data("String is not valid UTF-8"))
+}
+
+fn invalid_data(message: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
@@ -1,75 +1,426 @@
-use std::{fs::File, io::Write, path::Path};
+use std::{borrow::Cow, collections::HashSet, fs::File, io::Write, ops::Range, path::Path};
 
-use crate::GpuTimerQueryResult;
+use crate::{flatten, GpuTimerQueryResult};
 
 /// Writes a .json trace file that can be viewed as a flame graph in Chrome or Edge via <chrome://tracing>
+///
+/// Scopes of different [`GpuTimerQueryResult::timeline`]s are placed on separate tracks.
+/// Tracks of scopes with a [`GpuTimerQueryResult::track_name`] are named accordingly.
+/// Scopes with a [`GpuTimerQueryResult::color`] are colored accordingly.
 pub fn write_chrometrace(
     target: &Path,
     profile_data: &[GpuTimerQueryResult],
 ) -> std::io::Result<()> {
-    let mut file = File::create(target)?;
+    write_chrometrace_file(target, profile_data, None)
+}
+
+/// Like [`write_chrometrace`], but additionally writes the [`GpuTimerQueryResult::cpu_time`] of every scope.
+///
+/// Cpu timings are placed on a separate track next to the gpu track of the same thread & timeline,
+/// named after the scope's [`GpuTimerQueryResult::track_name`] with a ` (cpu)` suffix, or just `cpu`.
+/// This allows comparing when work was recorded with when it executed.
+/// Since cpu and gpu timings don't share a common origin, only their durations & relative placement
+/// within their own track are meaningful.
+/// Scopes with only one of the two timings are written to the respective track only.
+pub fn write_chrometrace_with_cpu_timings(
+    target: &Path,
+    profile_data: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    write_chrometrace_file(target, profile_data, Some(0.0))
+}
+
+fn write_chrometrace_file(
+    target: &Path,
+    profile_data: &[GpuTimerQueryResult],
+    cpu_time_offset: Option<f64>,
+) -> std::io::Result<()> {
+    let mut events = Vec::new();
+    let mut has_events = false;
+    write_results_recursive(
+        &mut events,
+        profile_data,
+        0.0,
+        cpu_time_offset,
+        None,
+        &mut HashSet::new(),
+        &mut has_events,
+    )?;
+
+    write_trace(File::create(target)?, &events, has_events)
+}
+
+/// Accumulates several frames of profiling results into a single chrome trace.
+///
+/// Frames are laid out one after another on the timeline:
+/// if a frame starts before the previous frame ended, all its timestamps are shifted
+/// such that it starts right at the end of the previous frame.
+/// Every event is tagged with the index of its frame via the event's `args`.
+/// Like for [`write_chrometrace`], scopes of different timelines are placed on separate tracks.
+/// Cpu timings are only written if enabled via [`ChromeTraceBuilder::with_cpu_timings`],
+/// in which case they are laid out the same way, independently of the gpu timings.
+#[derive(Default)]
+pub struct ChromeTraceBuilder {
+    events: Vec<u8>,
+    frames: FrameWriter,
+}
+
+impl ChromeTraceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to write cpu timings on separate tracks as well, see [`write_chrometrace_with_cpu_timings`].
+    ///
+    /// Disabled by default.
+    #[must_use]
+    pub fn with_cpu_timings(mut self, include_cpu_timings: bool) -> Self {
+        self.frames.include_cpu_timings = include_cpu_timings;
+        self
+    }
+
+    /// Adds a frame of profiling results to the trace.
+    pub fn add_frame(&mut self, frame_index: u64, profile_data: &[GpuTimerQueryResult]) {
+        self.frames
+            .write_frame(&mut self.events, frame_index, profile_data)
+            .expect("Writing to a Vec can't fail");
+    }
+
+    /// Writes out the trace containing all previously added frames.
+    pub fn finish<W: Write>(self, writer: W) -> std::io::Result<()> {
+        write_trace(writer, &self.events, self.frames.has_events)
+    }
+}
+
+/// Like [`ChromeTraceBuilder`], but writes every frame right away instead of keeping all frames in memory.
+///
+/// This bounds memory usage during long captures.
+/// The beginning of the trace is written on creation, the end by [`StreamingChromeTrace::finish`].
+/// If the trace is dropped without calling `finish`, the end is written on drop, ignoring any errors,
+/// so the written trace is valid json either way.
+pub struct StreamingChromeTrace<W: Write> {
+    /// `None` once the trace was finished.
+    writer: Option<W>,
+    frames: FrameWriter,
+}
+
+impl<W: Write> StreamingChromeTrace<W> {
+    /// Starts a trace, writing its beginning to `writer`.
+    ///
+    /// Consider passing a [`std::io::BufWriter`], since events are written piece by piece.
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        write_trace_start(&mut writer)?;
+        Ok(Self {
+            writer: Some(writer),
+            frames: FrameWriter::default(),
+        })
+    }
+
+    /// Whether to write cpu timings on separate tracks as well, see [`write_chrometrace_with_cpu_timings`].
+    ///
+    /// Disabled by default.
+    #[must_use]
+    pub fn with_cpu_timings(mut self, include_cpu_timings: bool) -> Self {
+        self.frames.include_cpu_timings = include_cpu_timings;
+        self
+    }
+
+    /// Writes a frame of profiling results to the trace and flushes the writer.
+    ///
+    /// Frames are laid out like for [`ChromeTraceBuilder::add_frame`].
+    pub fn add_frame(
+        &mut self,
+        frame_index: u64,
+        profile_data: &[GpuTimerQueryResult],
+    ) -> std::io::Result<()> {
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("Writer is only taken when finishing");
+        self.frames.write_frame(writer, frame_index, profile_data)?;
+        writer.flush()
+    }
+
+    /// Writes the end of the trace and returns the writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let mut writer = self
+            .writer
+            .take()
+            .expect("Writer is only taken when finishing");
+        write_trace_end(&mut writer, self.frames.has_events)?;
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Drop for StreamingChromeTrace<W> {
+    fn drop(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            let _ = write_trace_end(writer, self.frames.has_events);
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// Lays out several frames one after another, see [`ChromeTraceBuilder`].
+#[derive(Default)]
+struct FrameWriter {
+    has_events: bool,
+    previous_frame_end: Option<f64>,
+    named_tracks: HashSet<(u32, u64)>,
+    include_cpu_timings: bool,
+    previous_cpu_frame_end: Option<f64>,
+}
+
+impl FrameWriter {
+    fn write_frame<W: Write>(
+        &mut self,
+        writer: &mut W,
+        frame_index: u64,
+        profile_data: &[GpuTimerQueryResult],
+    ) -> std::io::Result<()> {
+        let time_offset = frame_time_offset(&mut self.previous_frame_end, profile_data, |scope| {
+            scope.time.as_ref()
+        });
+        let cpu_time_offset = if self.include_cpu_timings {
+            frame_time_offset(&mut self.previous_cpu_frame_end, profile_data, |scope| {
+                scope.cpu_time.as_ref()
+            })
+        } else {
+            None
+        };
+        if time_offset.is_none() && cpu_time_offset.is_none() {
+            return Ok(());
+        }
+
+        write_results_recursive(
+            writer,
+            profile_data,
+            time_offset.unwrap_or(0.0),
+            cpu_time_offset,
+            Some(frame_index),
+            &mut self.named_tracks,
+            &mut self.has_events,
+        )
+    }
+}
+
+/// Offset to shift a frame by such that it starts no earlier than the previous frame ended,
+/// updating `previous_frame_end` to the end of the shifted frame.
+///
+/// Returns `None` if no scope of the frame has a time range.
+fn frame_time_offset(
+    previous_frame_end: &mut Option<f64>,
+    profile_data: &[GpuTimerQueryResult],
+    time: impl Fn(&GpuTimerQueryResult) -> Option<&Range<f64>>,
+) -> Option<f64> {
+    let (frame_start, frame_end) = flatten(profile_data)
+        .filter_map(|(_, scope)| time(scope))
+        .fold(None, |range: Option<(f64, f64)>, time| {
+            Some(match range {
+                Some((start, end)) => (start.min(time.start), end.max(time.end)),
+                None => (time.start, time.end),
+            })
+        })?;
+
+    let time_offset = match *previous_frame_end {
+        Some(previous_frame_end) if frame_start < previous_frame_end => {
+            previous_frame_end - frame_start
+        }
+        _ => 0.0,
+    };
+    *previous_frame_end = Some(frame_end + time_offset);
+    Some(time_offset)
+}
 
-    writeln!(file, "{{")?;
-    writeln!(file, "\"traceEvents\": [")?;
+fn write_trace<W: Write>(mut writer: W, events: &[u8], has_events: bool) -> std::io::Result<()> {
+    write_trace_start(&mut writer)?;
+    writer.write_all(events)?;
+    write_trace_end(&mut writer, has_events)
+}
+
+fn write_trace_start<W: Write>(writer: &mut W) -> std::io::Result<()> {
+    writeln!(writer, "{{")?;
+    writeln!(writer, "\"traceEvents\": [")
+}
+
+fn write_trace_end<W: Write>(writer: &mut W, has_events: bool) -> std::io::Result<()> {
+    if has_events {
+        writeln!(writer)?;
+    }
+    writeln!(writer, "]")?;
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+// note: ThreadIds are under the control of Rust’s standard library
+// and there may not be any relationship between ThreadId and the underlying platform’s notion of a thread identifier
+//
+// There's a proposal for stabilization of ThreadId::as_u64, which
+// would eliminate the need for this hack: https://github.com/rust-lang/rust/pull/110738
+//
+// for now, we use this hack to convert to integer
+fn tid_to_int(tid: std::thread::ThreadId) -> u64 {
+    format!("{:?}", tid)
+        .replace("ThreadId(", "")
+        .replace(')', "")
+        .parse::<u64>()
+        .unwrap_or(std::u64::MAX)
+}
 
-    if !profile_data.is_empty() {
-        for child in profile_data.iter().take(profile_data.len() - 1) {
-            write_results_recursive(&mut file, child, false)?;
+/// Quotes and escapes a string for use in json.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
         }
-        write_results_recursive(&mut file, profile_data.last().unwrap(), true)?;
     }
+    quoted.push('"');
+    quoted
+}
+
+/// Chrome trace thread id of the track a result is placed on.
+///
+/// Identical to the id of the thread that opened the scope for the default timeline,
+/// every other timeline gets its own range of ids.
+fn track_id(result: &GpuTimerQueryResult) -> u64 {
+    tid_to_int(result.tid).wrapping_add((result.timeline as u64) << 32)
+}
 
-    writeln!(file, "]")?;
-    writeln!(file, "}}")?;
+/// Chrome trace thread id of the track a result's cpu timing is placed on.
+///
+/// Same as [`track_id`] but with the highest bit flipped, which no gpu track uses in practice.
+fn cpu_track_id(result: &GpuTimerQueryResult) -> u64 {
+    track_id(result) ^ (1 << 63)
+}
+
+/// Writes all passed results as events, each but the first preceded by a separator.
+///
+/// Results without timing information are skipped, their nested results are still written.
+/// If `cpu_time_offset` is set, cpu timings are written as well, on the tracks given by [`cpu_track_id`].
+/// The first result on a track with a [`GpuTimerQueryResult::track_name`] and every cpu track is preceded
+/// by a metadata event naming the track, `named_tracks` keeps track of all (pid, track id) pairs that were
+/// already named.
+fn write_results_recursive<W: Write>(
+    writer: &mut W,
+    results: &[GpuTimerQueryResult],
+    time_offset: f64,
+    cpu_time_offset: Option<f64>,
+    frame_index: Option<u64>,
+    named_tracks: &mut HashSet<(u32, u64)>,
+    has_events: &mut bool,
+) -> std::io::Result<()> {
+    for result in results {
+        if let Some(time) = &result.time {
+            let track_name = result.track_name.as_deref().map(Cow::Borrowed);
+            write_event(
+                writer,
+                result,
+                track_id(result),
+                track_name,
+                time,
+                time_offset,
+                frame_index,
+                named_tracks,
+                has_events,
+            )?;
+        }
+        if let (Some(cpu_time), Some(cpu_time_offset)) = (&result.cpu_time, cpu_time_offset) {
+            let track_name = match &result.track_name {
+                Some(track_name) => format!("{track_name} (cpu)").into(),
+                None => "cpu".into(),
+            };
+            write_event(
+                writer,
+                result,
+                cpu_track_id(result),
+                Some(track_name),
+                cpu_time,
+                cpu_time_offset,
+                frame_index,
+                named_tracks,
+                has_events,
+            )?;
+        }
+
+        write_results_recursive(
+            writer,
+            &result.nested_queries,
+            time_offset,
+            cpu_time_offset,
+            frame_index,
+            named_tracks,
+            has_events,
+        )?;
+    }
 
     Ok(())
 }
 
-fn write_results_recursive(
-    file: &mut File,
+/// Writes a single complete event for `result` spanning `time`, preceded by a separator if needed.
+#[allow(clippy::too_many_arguments)]
+fn write_event<W: Write>(
+    writer: &mut W,
     result: &GpuTimerQueryResult,
-    last: bool,
+    track_id: u64,
+    track_name: Option<Cow<'_, str>>,
+    time: &Range<f64>,
+    time_offset: f64,
+    frame_index: Option<u64>,
+    named_tracks: &mut HashSet<(u32, u64)>,
+    has_events: &mut bool,
 ) -> std::io::Result<()> {
-    // note: ThreadIds are under the control of Rust’s standard library
-    // and there may not be any relationship between ThreadId and the underlying platform’s notion of a thread identifier
-    //
-    // There's a proposal for stabilization of ThreadId::as_u64, which
-    // would eliminate the need for this hack: https://github.com/rust-lang/rust/pull/110738
-    //
-    // for now, we use this hack to convert to integer
-    let tid_to_int = |tid| {
-        format!("{:?}", tid)
-            .replace("ThreadId(", "")
-            .replace(')', "")
-            .parse::<u64>()
-            .unwrap_or(std::u64::MAX)
-    };
+    if *has_events {
+        writeln!(writer, ",")?;
+    }
+    *has_events = true;
+
+    if let Some(track_name) = track_name {
+        if named_tracks.insert((result.pid, track_id)) {
+            writeln!(
+                writer,
+                r#"{{ "pid":{}, "tid":{}, "ph":"M", "name":"thread_name", "args":{{ "name":{} }} }},"#,
+                result.pid,
+                track_id,
+                json_string(&track_name),
+            )?;
+        }
+    }
+
     write!(
-        file,
-        r#"{{ "pid":{}, "tid":{}, "ts":{}, "dur":{}, "ph":"X", "name":"{}" }}{}"#,
+        writer,
+        r#"{{ "pid":{}, "tid":{}, "ts":{}, "dur":{}, "ph":"X", "name":"{}""#,
         result.pid,
-        tid_to_int(result.tid),
-        result.time.start * 1000.0 * 1000.0,
-        (result.time.end - result.time.start) * 1000.0 * 1000.0,
+        track_id,
+        (time.start + time_offset) * 1000.0 * 1000.0,
+        (time.end - time.start) * 1000.0 * 1000.0,
         result.label,
-        if last && result.nested_queries.is_empty() {
-            "\n"
-        } else {
-            ",\n"
-        }
     )?;
-    if result.nested_queries.is_empty() {
-        return Ok(());
+    if let Some(color) = result.color {
+        write!(writer, r#", "cname":"{}""#, color.chrome_name())?;
     }
-
-    for child in result
-        .nested_queries
-        .iter()
-        .take(result.nested_queries.len() - 1)
-    {
-        write_results_recursive(file, child, false)?;
+    if frame_index.is_some() || !result.metadata.is_empty() {
+        write!(writer, r#", "args":{{ "#)?;
+        let mut has_args = false;
+        if let Some(frame_index) = frame_index {
+            write!(writer, r#""frame":{}"#, frame_index)?;
+            has_args = true;
+        }
+        for (key, value) in &result.metadata {
+            if has_args {
+                write!(writer, ", ")?;
+            }
+            has_args = true;
+            write!(writer, "{}:{}", json_string(key), json_string(value))?;
+        }
+        write!(writer, " }}")?;
     }
-    write_results_recursive(file, result.nested_queries.last().unwrap(), last)?;
+    write!(writer, " }}")?;
 
     Ok(())
     // { "pid":1, "tid":1, "ts":546867, "dur":121564, "ph":"X", "name":"DoThings"
@@ -0,0 +1,61 @@
+//! CSV export of profiling results.
+
+use std::{borrow::Cow, io::Write};
+
+use crate::GpuTimerQueryResult;
+
+/// Writes profiling results as CSV with one row per scope.
+///
+/// Columns are `depth,label,start_ms,end_ms,duration_ms,path`,
+/// where `path` is the dot separated list of all parent labels followed by the scope's own label.
+/// Nested scopes are flattened depth-first, i.e. every scope is directly followed by its nested scopes.
+/// For scopes without timing information, `start_ms`, `end_ms` and `duration_ms` are left empty.
+pub fn write_csv<W: Write>(
+    mut writer: W,
+    profile_data: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    writeln!(writer, "depth,label,start_ms,end_ms,duration_ms,path")?;
+    write_rows_recursive(&mut writer, profile_data, 0, "")
+}
+
+fn write_rows_recursive<W: Write>(
+    writer: &mut W,
+    results: &[GpuTimerQueryResult],
+    depth: u32,
+    parent_path: &str,
+) -> std::io::Result<()> {
+    for scope in results {
+        let path = if parent_path.is_empty() {
+            scope.label.to_string()
+        } else {
+            format!("{parent_path}.{}", scope.label)
+        };
+
+        write!(writer, "{},{},", depth, escape(&scope.label))?;
+        if let Some(time) = &scope.time {
+            write!(
+                writer,
+                "{},{},{},",
+                time.start * 1000.0,
+                time.end * 1000.0,
+                (time.end - time.start) * 1000.0,
+            )?;
+        } else {
+            write!(writer, ",,,")?;
+        }
+        writeln!(writer, "{}", escape(&path))?;
+
+        write_rows_recursive(writer, &scope.nested_queries, depth + 1, &path)?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a field if necessary, as described by RFC 4180.
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use crate::{flatten_with_paths, GpuTimerQueryResult};
+
+/// How a scope changed between two frames, see [`diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScopeChange {
+    /// The scope took longer than in the baseline.
+    Slower,
+    /// The scope took less time than in the baseline.
+    Faster,
+    /// The scope is only present in the current frame.
+    Added,
+    /// The scope is only present in the baseline.
+    Removed,
+}
+
+/// A scope whose duration changed between two frames, see [`diff`].
+///
+/// All durations are in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeDelta {
+    /// Labels of all parent scopes and the scope itself, separated by `/`.
+    pub path: String,
+    pub change: ScopeChange,
+    /// `None` if the scope isn't present in the baseline or has no timing information there.
+    pub baseline_ms: Option<f64>,
+    /// `None` if the scope isn't present in the current frame or has no timing information there.
+    pub current_ms: Option<f64>,
+}
+
+impl ScopeDelta {
+    /// Current minus baseline duration, `None` unless both durations are known.
+    pub fn delta_ms(&self) -> Option<f64> {
+        Some(self.current_ms? - self.baseline_ms?)
+    }
+}
+
+/// Compares the scope durations of a frame against a baseline frame.
+///
+/// Scopes are matched by their path (see [`flatten_with_paths`]), so the trees may differ in structure.
+/// Like for [`crate::GpuProfilerStatistics`], durations of a path that occurs several times in a frame are summed up.
+/// Scopes that got slower or faster by more than `threshold_ms` are reported,
+/// as well as all scopes that were added or removed regardless of their duration.
+/// Scopes without timing information in either frame can only be reported as added or removed.
+///
+/// Deltas are ordered by the first occurrence of their path in the baseline, followed by added scopes.
+pub fn diff(
+    baseline: &[GpuTimerQueryResult],
+    current: &[GpuTimerQueryResult],
+    threshold_ms: f64,
+) -> Vec<ScopeDelta> {
+    let baseline = durations_by_path(baseline);
+    let current = durations_by_path(current);
+    let current_indices: HashMap<&str, usize> = current
+        .iter()
+        .enumerate()
+        .map(|(index, (path, _))| (path.as_str(), index))
+        .collect();
+    let mut is_matched = vec![false; current.len()];
+
+    let mut deltas = Vec::new();
+    for (path, baseline_ms) in &baseline {
+        let Some(&index) = current_indices.get(path.as_str()) else {
+            deltas.push(ScopeDelta {
+                path: path.clone(),
+                change: ScopeChange::Removed,
+                baseline_ms: *baseline_ms,
+                current_ms: None,
+            });
+            continue;
+        };
+        is_matched[index] = true;
+
+        let current_ms = current[index].1;
+        let (Some(baseline_duration), Some(current_duration)) = (*baseline_ms, current_ms) else {
+            continue;
+        };
+        let change = if current_duration - baseline_duration > threshold_ms {
+            ScopeChange::Slower
+        } else if baseline_duration - current_duration > threshold_ms {
+            ScopeChange::Faster
+        } else {
+            continue;
+        };
+        deltas.push(ScopeDelta {
+            path: path.clone(),
+            change,
+            baseline_ms: *baseline_ms,
+            current_ms,
+        });
+    }
+
+    deltas.extend(
+        current
+            .into_iter()
+            .zip(is_matched)
+            .filter(|(_, is_matched)| !is_matched)
+            .map(|((path, current_ms), _)| ScopeDelta {
+                path,
+                change: ScopeChange::Added,
+                baseline_ms: None,
+                current_ms,
+            }),
+    );
+
+    deltas
+}
+
+/// Summed up durations in milliseconds of all paths, in order of their first occurrence.
+fn durations_by_path(results: &[GpuTimerQueryResult]) -> Vec<(String, Option<f64>)> {
+    let mut durations: Vec<(String, Option<f64>)> = Vec::new();
+    let mut indices = HashMap::new();
+    for (path, _, result) in flatten_with_paths(results) {
+        let duration_ms = result
+            .time
+            .as_ref()
+            .map(|time| (time.end - time.start) * 1000.0);
+        let index = *indices.entry(path.clone()).or_insert_with(|| {
+            durations.push((path, None));
+            durations.len() - 1
+        });
+        if let Some(duration_ms) = duration_ms {
+            *durations[index].1.get_or_insert(0.0) += duration_ms;
+        }
+    }
+    durations
+}
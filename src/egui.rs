@@ -0,0 +1,92 @@
+//! Displays profiling results in [egui](https://github.com/emilk/egui).
+//!
+//! Requires the `egui` feature.
+
+use crate::GpuTimerQueryResult;
+
+/// Width of the bar drawn in front of every scope, in points.
+const BAR_WIDTH: f32 = 100.0;
+
+/// Shows a frame of profiling results as a collapsible tree.
+///
+/// Every scope is shown with its label and duration in milliseconds, preceded by a bar that indicates
+/// when the scope ran relative to the whole frame, i.e. the range from the earliest start to the latest end
+/// of all timed scopes. Hovering a bar shows the scope's start & end relative to the frame start.
+/// Scopes with nested scopes can be collapsed and are open by default.
+/// Scopes without timing information are shown without duration and with an empty bar.
+///
+/// This is a read-only display, pass in the results of [`crate::GpuProfiler::process_finished_frame`],
+/// e.g. the most recent frame that was processed.
+pub fn profiler_ui(ui: &mut ::egui::Ui, profile_data: &[GpuTimerQueryResult]) {
+    let frame_range = crate::flatten(profile_data)
+        .filter_map(|(_, scope)| scope.time.as_ref())
+        .fold(None, |range: Option<(f64, f64)>, time| {
+            Some(match range {
+                Some((start, end)) => (start.min(time.start), end.max(time.end)),
+                None => (time.start, time.end),
+            })
+        });
+
+    let id = ui.make_persistent_id("wgpu_profiler_scopes");
+    scopes_ui(ui, id, profile_data, frame_range);
+}
+
+fn scopes_ui(
+    ui: &mut ::egui::Ui,
+    parent_id: ::egui::Id,
+    scopes: &[GpuTimerQueryResult],
+    frame_range: Option<(f64, f64)>,
+) {
+    for (index, scope) in scopes.iter().enumerate() {
+        let id = parent_id.with(index);
+        if scope.nested_queries.is_empty() {
+            ui.horizontal(|ui| {
+                ui.add_space(ui.spacing().indent);
+                scope_row_ui(ui, scope, frame_range);
+            });
+        } else {
+            ::egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| scope_row_ui(ui, scope, frame_range))
+                .body(|ui| scopes_ui(ui, id, &scope.nested_queries, frame_range));
+        }
+    }
+}
+
+fn scope_row_ui(ui: &mut ::egui::Ui, scope: &GpuTimerQueryResult, frame_range: Option<(f64, f64)>) {
+    let (rect, response) = ui.allocate_exact_size(
+        ::egui::vec2(BAR_WIDTH, ui.spacing().interact_size.y * 0.6),
+        ::egui::Sense::hover(),
+    );
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+    if let (Some(time), Some((frame_start, frame_end))) = (&scope.time, frame_range) {
+        let frame_duration = frame_end - frame_start;
+        let (start, end) = if frame_duration > 0.0 {
+            (
+                ((time.start - frame_start) / frame_duration) as f32,
+                ((time.end - frame_start) / frame_duration) as f32,
+            )
+        } else {
+            (0.0, 1.0)
+        };
+        let bar = ::egui::Rect::from_x_y_ranges(
+            rect.left() + start * rect.width()..=rect.left() + end * rect.width(),
+            rect.y_range(),
+        );
+        painter.rect_filled(bar, 0.0, ui.visuals().selection.bg_fill);
+        response.on_hover_text(format!(
+            "{:.3} ms - {:.3} ms",
+            (time.start - frame_start) * 1000.0,
+            (time.end - frame_start) * 1000.0,
+        ));
+
+        ui.label(format!(
+            "{}: {:.3} ms",
+            scope.label,
+            (time.end - time.start) * 1000.0
+        ));
+    } else {
+        ui.label(scope.label.as_ref());
+    }
+}
@@ -5,10 +5,17 @@ pub enum CreationError {
     #[error(transparent)]
     InvalidSettings(#[from] SettingsError),
 
+    #[error(transparent)]
+    InvalidQueryPool(#[from] QueryPoolError),
+
     #[cfg(feature = "tracy")]
     #[error("Tracy client doesn't run yet.")]
     TracyClientNotRunning,
 
+    #[cfg(feature = "tracy")]
+    #[error("Failed to read back the gpu timestamp for Tracy's calibration.")]
+    TracyCalibrationFailed,
+
     #[cfg(feature = "tracy")]
     #[error("Failed to create Tracy GPU context: {0}")]
     TracyGpuContextCreationError(#[from] tracy_client::GpuContextCreationError),
@@ -22,9 +29,16 @@ impl PartialEq for CreationError {
                 CreationError::InvalidSettings(right) => left == right,
                 _ => false,
             },
+            CreationError::InvalidQueryPool(left) => match other {
+                CreationError::InvalidQueryPool(right) => left == right,
+                _ => false,
+            },
             CreationError::TracyClientNotRunning => {
                 matches!(other, CreationError::TracyClientNotRunning)
             }
+            CreationError::TracyCalibrationFailed => {
+                matches!(other, CreationError::TracyCalibrationFailed)
+            }
             CreationError::TracyGpuContextCreationError(left) => match left {
                 tracy_client::GpuContextCreationError::TooManyContextsCreated => matches!(
                     other,
@@ -42,15 +56,83 @@ impl Eq for CreationError {}
 /// Errors that can occur during settings validation and change.
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum SettingsError {
-    #[error("GpuProfilerSettings::max_num_pending_frames must be at least 1.")]
+    #[error("GpuProfilerSettings::max_num_pending_frames must be at least 1. Use 1 to read back every frame synchronously.")]
     InvalidMaxNumPendingFrames,
+
+    #[error("GpuProfilerSettings::max_query_depth must be at least 1.")]
+    InvalidMaxQueryDepth,
+
+    #[error("GpuProfilerSettings::sample_interval must be at least 1.")]
+    InvalidSampleInterval,
+
+    #[error("The valid bits of TimestampWrapPolicy::Unwrap must be within 1..=64.")]
+    InvalidTimestampValidBits,
+}
+
+/// Reasons why query pool resources passed to [`crate::GpuProfiler::new_with_query_pools`] can't be used.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum QueryPoolError {
+    #[error("Query pool capacity {capacity} must be even, at least 2 and at most wgpu::QUERY_SET_MAX_QUERIES.")]
+    InvalidCapacity { capacity: u32 },
+
+    #[error("Query pool capacity {capacity} is smaller than the {required} queries required by GpuProfilerSettings::max_num_queries_per_frame.")]
+    CapacityTooSmall { capacity: u32, required: u32 },
+
+    #[error("The query resolve buffer needs a size of at least {required_size} bytes and QUERY_RESOLVE | COPY_SRC usage.")]
+    InvalidResolveBuffer { required_size: u64 },
+
+    #[error("The query read buffer needs a size of at least {required_size} bytes and COPY_DST | MAP_READ usage.")]
+    InvalidReadBuffer { required_size: u64 },
+}
+
+/// Reasons why [`crate::GpuProfiler::try_begin_query`] didn't open a query.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum BeginQueryError {
+    #[error("The profiler is disabled, see `GpuProfiler::set_enabled`.")]
+    ProfilerDisabled,
+
+    #[error("Timer queries are disabled via `GpuProfilerSettings::enable_timer_queries`.")]
+    TimerQueriesDisabled,
+
+    #[error("Timestamps can't be written to the encoder or pass, either because the device lacks the required features or because the recorder doesn't support timestamps at all.")]
+    TimestampWritesNotSupported,
+
+    #[error("The maximum number of queries per frame was reached, see `GpuProfilerSettings::max_num_queries_per_frame`.")]
+    MaxNumQueriesPerFrameExceeded,
+
+    #[error("The query was rejected by `GpuProfilerSettings::query_filter`.")]
+    FilteredOut,
+}
+
+/// Reasons why [`crate::GpuProfiler::try_process_finished_frame`] didn't return a frame.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ProcessFrameError {
+    #[error("There is no pending frame, see `GpuProfiler::end_frame`.")]
+    NoPendingFrame,
+
+    #[error("The results of the oldest pending frame aren't available yet, the device needs to finish its work and be polled.")]
+    NotReady,
+}
+
+/// Errors that can occur during [`crate::merge`].
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    #[error("Results of timeline {first} and {second} can't be merged since their timestamps aren't comparable.")]
+    DifferentTimelines { first: u32, second: u32 },
 }
 
 /// Errors that can occur during [`crate::GpuProfiler::end_frame`].
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum EndFrameError {
-    #[error("All profiling queries need to be closed before ending a frame. There were still {0} open queries.")]
-    UnclosedQueries(u32),
+    #[error("All profiling queries need to be closed before ending a frame. There were still {num_open_queries} open queries: {open_query_labels:?}")]
+    UnclosedQueries {
+        num_open_queries: u32,
+
+        /// Labels of the queries that are still open, ordered by creation.
+        ///
+        /// Only available if [`crate::GpuProfilerSettings::track_open_queries`] is enabled, empty otherwise.
+        open_query_labels: Vec<String>,
+    },
 
     #[error(
         "Not all queries were resolved before ending a frame.\n
@@ -0,0 +1,91 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{flatten, FlattenedQueryResults, GpuTimerQueryResult};
+
+/// Results of a whole profiler frame, returned by [`crate::GpuProfiler::take_finished_frame`].
+///
+/// Dereferences to the list of top-level results, so it can be used just like the result of
+/// [`crate::GpuProfiler::process_finished_frame`], and converts into it via [`FinishedFrame::into_vec`].
+#[derive(Debug, Clone, Default)]
+pub struct FinishedFrame {
+    queries: Vec<GpuTimerQueryResult>,
+}
+
+impl FinishedFrame {
+    /// Time in seconds between the earliest start and the latest end of all timed results, including nested ones.
+    ///
+    /// Returns `None` if no result has timing information.
+    pub fn total_duration(&self) -> Option<f64> {
+        let (start, end) = self
+            .iter_recursive()
+            .filter_map(|(_, result)| result.time.as_ref())
+            .fold(None, |range: Option<(f64, f64)>, time| {
+                Some(match range {
+                    Some((start, end)) => (start.min(time.start), end.max(time.end)),
+                    None => (time.start, time.end),
+                })
+            })?;
+        Some(end - start)
+    }
+
+    /// Finds the first result with the given label, searching depth-first through all nested results.
+    pub fn find(&self, label: &str) -> Option<&GpuTimerQueryResult> {
+        self.iter_recursive()
+            .map(|(_, result)| result)
+            .find(|result| result.label == label)
+    }
+
+    /// Iterates depth-first over all results and their nested results, see [`flatten`].
+    pub fn iter_recursive(&self) -> FlattenedQueryResults<'_> {
+        flatten(&self.queries)
+    }
+
+    /// Returns the list of top-level results.
+    pub fn into_vec(self) -> Vec<GpuTimerQueryResult> {
+        self.queries
+    }
+}
+
+impl From<Vec<GpuTimerQueryResult>> for FinishedFrame {
+    fn from(queries: Vec<GpuTimerQueryResult>) -> Self {
+        Self { queries }
+    }
+}
+
+impl From<FinishedFrame> for Vec<GpuTimerQueryResult> {
+    fn from(frame: FinishedFrame) -> Self {
+        frame.queries
+    }
+}
+
+impl Deref for FinishedFrame {
+    type Target = Vec<GpuTimerQueryResult>;
+
+    fn deref(&self) -> &Vec<GpuTimerQueryResult> {
+        &self.queries
+    }
+}
+
+impl DerefMut for FinishedFrame {
+    fn deref_mut(&mut self) -> &mut Vec<GpuTimerQueryResult> {
+        &mut self.queries
+    }
+}
+
+impl IntoIterator for FinishedFrame {
+    type Item = GpuTimerQueryResult;
+    type IntoIter = std::vec::IntoIter<GpuTimerQueryResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.queries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FinishedFrame {
+    type Item = &'a GpuTimerQueryResult;
+    type IntoIter = std::slice::Iter<'a, GpuTimerQueryResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.queries.iter()
+    }
+}
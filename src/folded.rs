@@ -0,0 +1,55 @@
+//! Folded stack export of profiling results, as consumed by
+//! [flamegraph.pl](https://github.com/brendangregg/FlameGraph) and [inferno](https://github.com/jonhoo/inferno).
+
+use std::{borrow::Cow, io::Write};
+
+use crate::GpuTimerQueryResult;
+
+/// Writes profiling results in the folded stack format with one line per scope.
+///
+/// Every line consists of the `;` separated labels of all parents and the scope itself,
+/// followed by the scope's [`GpuTimerQueryResult::self_time`] in nanoseconds (rounded).
+/// This way, the widths in the resulting flamegraph are proportional to the scopes' durations.
+///
+/// Scopes without timing information or without self time don't produce a line, their nested scopes still do.
+/// Since `;` and line breaks have special meaning in the format, they are replaced with `_` in labels.
+pub fn write_folded<W: Write>(
+    mut writer: W,
+    profile_data: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    write_lines_recursive(&mut writer, profile_data, "")
+}
+
+fn write_lines_recursive<W: Write>(
+    writer: &mut W,
+    results: &[GpuTimerQueryResult],
+    parent_stack: &str,
+) -> std::io::Result<()> {
+    for scope in results {
+        let label = escape(&scope.label);
+        let stack = if parent_stack.is_empty() {
+            label.into_owned()
+        } else {
+            format!("{parent_stack};{label}")
+        };
+
+        if let Some(self_time) = scope.self_time() {
+            let self_time_ns = (self_time * 1000.0 * 1000.0 * 1000.0).round() as u64;
+            if self_time_ns > 0 {
+                writeln!(writer, "{stack} {self_time_ns}")?;
+            }
+        }
+
+        write_lines_recursive(writer, &scope.nested_queries, &stack)?;
+    }
+
+    Ok(())
+}
+
+fn escape(label: &str) -> Cow<'_, str> {
+    if label.contains([';', '\n', '\r']) {
+        Cow::Owned(label.replace([';', '\n', '\r'], "_"))
+    } else {
+        Cow::Borrowed(label)
+    }
+}
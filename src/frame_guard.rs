@@ -0,0 +1,51 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{EndFrameError, GpuProfiler};
+
+/// Guard for a whole profiler frame, created by [`GpuProfiler::begin_frame`].
+///
+/// Dereferences to the [`GpuProfiler`], so scopes can be opened and queries resolved through it as usual.
+/// Calls [`GpuProfiler::end_frame`] on drop, discarding any error.
+/// Use [`FrameGuard::finish`] instead to handle the error.
+#[derive(Debug)]
+pub struct FrameGuard<'a> {
+    profiler: &'a mut GpuProfiler,
+}
+
+impl<'a> FrameGuard<'a> {
+    pub(crate) fn new(profiler: &'a mut GpuProfiler) -> Self {
+        Self { profiler }
+    }
+
+    /// Ends the frame, returning the result of [`GpuProfiler::end_frame`].
+    pub fn finish(self) -> Result<(), EndFrameError> {
+        let result = self.profiler.end_frame();
+        // Only holds a reference, so there is nothing to leak.
+        std::mem::forget(self);
+        result
+    }
+}
+
+impl<'a> Deref for FrameGuard<'a> {
+    type Target = GpuProfiler;
+
+    #[inline]
+    fn deref(&self) -> &GpuProfiler {
+        self.profiler
+    }
+}
+
+impl<'a> DerefMut for FrameGuard<'a> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut GpuProfiler {
+        self.profiler
+    }
+}
+
+impl<'a> Drop for FrameGuard<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        // There's no way to report the error from here. `finish` exists for this reason.
+        let _ = self.profiler.end_frame();
+    }
+}
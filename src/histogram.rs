@@ -0,0 +1,103 @@
+use std::{fmt::Write, ops::Range};
+
+use crate::{flatten_with_paths, GpuTimerQueryResult};
+
+/// Distribution of the per-frame duration of a single scope, e.g. to spot bimodal stalls.
+///
+/// The scope is identified by its path like for [`crate::GpuProfilerStatistics`], i.e. the labels of all
+/// parent scopes and the scope itself, separated by `/`.
+/// If the path occurs several times in a frame, its durations are summed up.
+/// Frames in which the scope is absent or has no timing information don't add a sample.
+///
+/// Buckets have a fixed width in milliseconds, the last bucket additionally holds all longer durations.
+#[derive(Debug, Clone)]
+pub struct DurationHistogram {
+    path: String,
+    bucket_width_ms: f64,
+    counts: Vec<u64>,
+}
+
+impl DurationHistogram {
+    /// Creates an empty histogram for the scope with the given path.
+    ///
+    /// `num_buckets` is at least one, `bucket_width_ms` needs to be positive.
+    pub fn new(path: impl Into<String>, bucket_width_ms: f64, num_buckets: usize) -> Self {
+        Self {
+            path: path.into(),
+            bucket_width_ms,
+            counts: vec![0; num_buckets.max(1)],
+        }
+    }
+
+    /// Path of the scope whose durations are accumulated.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Adds the duration of the scope in a frame of profiling results.
+    pub fn add_frame(&mut self, profile_data: &[GpuTimerQueryResult]) {
+        let mut duration_ms = None;
+        for (path, _, scope) in flatten_with_paths(profile_data) {
+            if path != self.path {
+                continue;
+            }
+            if let Some(time) = &scope.time {
+                *duration_ms.get_or_insert(0.0) += (time.end - time.start) * 1000.0;
+            }
+        }
+
+        if let Some(duration_ms) = duration_ms {
+            let last_bucket = self.counts.len() - 1;
+            let bucket = ((duration_ms / self.bucket_width_ms).max(0.0) as usize).min(last_bucket);
+            self.counts[bucket] += 1;
+        }
+    }
+
+    /// Number of samples in every bucket.
+    pub fn bucket_counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Range of durations in milliseconds covered by the given bucket.
+    ///
+    /// The range of the last bucket ends at infinity.
+    pub fn bucket_range_ms(&self, bucket: usize) -> Range<f64> {
+        let start = bucket as f64 * self.bucket_width_ms;
+        if bucket + 1 >= self.counts.len() {
+            start..f64::INFINITY
+        } else {
+            start..(start + self.bucket_width_ms)
+        }
+    }
+
+    /// Total number of samples.
+    pub fn num_samples(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Removes all samples.
+    pub fn reset(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+    }
+
+    /// Renders the histogram as text, one line per bucket.
+    ///
+    /// Bars are scaled such that the fullest bucket has `max_bar_width` characters.
+    pub fn to_ascii(&self, max_bar_width: usize) -> String {
+        let max_count = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut text = format!("{}\n", self.path);
+        for (bucket, &count) in self.counts.iter().enumerate() {
+            let range = self.bucket_range_ms(bucket);
+            let bar_width =
+                (count as f64 / max_count as f64 * max_bar_width as f64).round() as usize;
+            let range = if range.end.is_finite() {
+                format!("{:8.3} - {:8.3} ms", range.start, range.end)
+            } else {
+                format!("{:8.3} - {:>8} ms", range.start, "inf")
+            };
+            writeln!(text, "{range} | {} {count}", "#".repeat(bar_width))
+                .expect("Writing to a String can't fail");
+        }
+        text
+    }
+}
@@ -72,6 +72,9 @@ profiler.resolve_queries(&mut encoder);
 profiler.end_frame().unwrap();
 
 // Retrieving the oldest available frame and writing it out to a chrome trace file.
+// (requires the default `exporters` feature)
+# #[cfg(feature = "exporters")]
+# {
 if let Some(profiling_data) = profiler.process_finished_frame(queue.get_timestamp_period()) {
     # let button_pressed = false;
     // You usually want to write to disk only under some condition, e.g. press of a key.
@@ -80,9 +83,22 @@ if let Some(profiling_data) = profiler.process_finished_frame(queue.get_timestam
             std::path::Path::new("mytrace.json"), &profiling_data);
     }
 }
+# }
 ```
 Check also the [Example](https://github.com/Wumpf/wgpu-profiler/blob/main/examples/demo.rs) where everything can be seen in action.
 
+To reduce profiling overhead in e.g. shipping builds to a minimum, disable the default `profiling-enabled` feature.
+All profiler methods and scopes can still be used as before, but don't do anything:
+no queries are opened, no debug groups are pushed, no locks are taken and
+[`GpuProfiler::process_finished_frame`] never returns results.
+Scope objects still exist and are just as large as before, they merely don't track anything.
+
+The exporters in [`chrometrace`], [`csv`], [`perfetto`] and [`folded`] as well as everything else writing to
+[`std::io::Write`] are part of the default `exporters` feature.
+Without it, results are still collected and can be forwarded elsewhere, e.g. via the `serde` feature.
+This only removes the dependency on [`std::io`]: there is no `no_std` or `alloc`-only core,
+timing collection and result trees still require `std`, like wgpu itself.
+
 # Internals
 
 For every frame that hasn't completely finished processing yet
@@ -98,19 +114,75 @@ On [`GpuProfiler::end_frame`], we memorize the total size of all `QueryPool`s in
 `QueryPool` from finished frames are re-used, unless they are deemed too small.
 */
 
+mod calibration;
+#[cfg(feature = "exporters")]
+pub mod capture;
+#[cfg(feature = "exporters")]
 pub mod chrometrace;
+#[cfg(feature = "exporters")]
+pub mod csv;
+mod diff;
+#[cfg(feature = "egui")]
+pub mod egui;
 mod errors;
+mod finished_frame;
+#[cfg(feature = "exporters")]
+pub mod folded;
+mod frame_guard;
+mod histogram;
+#[cfg(feature = "test-util")]
+mod mock_recorder;
+#[cfg(feature = "exporters")]
+pub mod perfetto;
+#[cfg(feature = "exporters")]
+mod print_tree;
+mod profiled_render_bundle;
 mod profiler;
 mod profiler_command_recorder;
 mod profiler_query;
 mod profiler_settings;
+#[cfg(feature = "puffin")]
+pub mod puffin;
 mod scope;
+mod scope_label;
+mod smoothed;
+mod statistics;
+#[cfg(feature = "tracing")]
+pub mod tracing;
 #[cfg(feature = "tracy")]
 mod tracy;
 
-pub use errors::{CreationError, EndFrameError, SettingsError};
-pub use profiler::GpuProfiler;
+pub use calibration::ClockCalibration;
+pub use diff::{diff, ScopeChange, ScopeDelta};
+pub use errors::{
+    BeginQueryError, CreationError, EndFrameError, MergeError, ProcessFrameError, QueryPoolError,
+    SettingsError,
+};
+pub use finished_frame::FinishedFrame;
+pub use frame_guard::FrameGuard;
+pub use histogram::DurationHistogram;
+#[cfg(feature = "test-util")]
+pub use mock_recorder::{MockCommand, MockRecorder};
+#[cfg(feature = "exporters")]
+pub use print_tree::{print_tree, print_tree_with_indent};
+pub use profiled_render_bundle::ProfiledRenderBundle;
+pub use profiler::{
+    GpuProfiler, MemoryReport, ProfilerCapabilities, QueryPoolResources, RegisteredScopeId,
+};
 pub use profiler_command_recorder::ProfilerCommandRecorder;
-pub use profiler_query::{GpuProfilerQuery, GpuTimerQueryResult};
-pub use profiler_settings::GpuProfilerSettings;
-pub use scope::{ManualOwningScope, OwningScope, Scope};
+pub use profiler_query::{
+    build_query_tree, decode_timestamps, find_overlapping_scopes, flatten, flatten_with_paths,
+    merge, results_from_raw, results_from_raw_with_wrap_policy, FlattenedQueryResults,
+    GpuProfilerQuery, GpuTimerQueryRawResult, GpuTimerQueryRecord, GpuTimerQueryResult,
+    PipelineStats, QueryId, ScopeOverlap, TimestampAnomaly, TraceColor,
+};
+pub use profiler_settings::{
+    Clock, GpuProfilerSettings, QueryFilter, QueryOverflowPolicy, TimestampWrapPolicy,
+};
+pub use scope::{CpuScope, ManualOwningScope, OwningScope, PassTimestampMethod, Scope};
+pub use scope_label::ScopeLabel;
+pub use smoothed::SmoothedProfiler;
+pub use statistics::{category_durations, GpuProfilerStatistics, ScopeStatistics};
+
+#[cfg(feature = "macros")]
+pub use wgpu_profiler_macros::gpu_profile;
@@ -0,0 +1,88 @@
+use crate::ProfilerCommandRecorder;
+
+/// A command recorded by a [`MockRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockCommand {
+    /// [`ProfilerCommandRecorder::write_timestamp`] with the index of the written query.
+    WriteTimestamp { query_index: u32 },
+    /// [`ProfilerCommandRecorder::push_debug_group`] with the label of the pushed group.
+    PushDebugGroup(String),
+    /// [`ProfilerCommandRecorder::pop_debug_group`].
+    PopDebugGroup,
+    /// [`ProfilerCommandRecorder::begin_pipeline_statistics_query`] with the index of the query.
+    BeginPipelineStatisticsQuery { query_index: u32 },
+    /// [`ProfilerCommandRecorder::end_pipeline_statistics_query`].
+    EndPipelineStatisticsQuery,
+}
+
+/// A [`ProfilerCommandRecorder`] that doesn't record any gpu commands but stores the calls made by the profiler.
+///
+/// Allows testing which timestamp writes & debug groups the profiler emits for a given scope nesting,
+/// without creating & submitting command encoders.
+/// Note that opening queries still requires a [`wgpu::Device`]: timestamps are only written if it supports
+/// [`wgpu::Features::TIMESTAMP_QUERY`] (or [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] if the mock acts
+/// as a pass), since the profiler allocates its query sets on it.
+///
+/// Requires the `test-util` feature.
+#[derive(Debug, Clone, Default)]
+pub struct MockRecorder {
+    is_pass: bool,
+
+    /// All calls made to this recorder, in order.
+    pub commands: Vec<MockCommand>,
+}
+
+impl MockRecorder {
+    /// Creates a mock that acts as a [`wgpu::CommandEncoder`].
+    pub fn encoder() -> Self {
+        Self::default()
+    }
+
+    /// Creates a mock that acts as a [`wgpu::RenderPass`] or [`wgpu::ComputePass`].
+    pub fn pass() -> Self {
+        Self {
+            is_pass: true,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Indices of all queries timestamps were written to, in order.
+    pub fn timestamp_writes(&self) -> Vec<u32> {
+        self.commands
+            .iter()
+            .filter_map(|command| match command {
+                MockCommand::WriteTimestamp { query_index } => Some(*query_index),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl ProfilerCommandRecorder for MockRecorder {
+    fn is_pass(&self) -> bool {
+        self.is_pass
+    }
+
+    fn write_timestamp(&mut self, _query_set: &wgpu::QuerySet, query_index: u32) {
+        self.commands
+            .push(MockCommand::WriteTimestamp { query_index });
+    }
+
+    fn push_debug_group(&mut self, label: &str) {
+        self.commands
+            .push(MockCommand::PushDebugGroup(label.to_owned()));
+    }
+
+    fn pop_debug_group(&mut self) {
+        self.commands.push(MockCommand::PopDebugGroup);
+    }
+
+    fn begin_pipeline_statistics_query(&mut self, _query_set: &wgpu::QuerySet, query_index: u32) {
+        self.commands
+            .push(MockCommand::BeginPipelineStatisticsQuery { query_index });
+    }
+
+    fn end_pipeline_statistics_query(&mut self) {
+        self.commands.push(MockCommand::EndPipelineStatisticsQuery);
+    }
+}
@@ -0,0 +1,202 @@
+//! Export of profiling results to [Perfetto](https://perfetto.dev/)'s protobuf trace format.
+//!
+//! Resulting files can be opened with the [Perfetto UI](https://ui.perfetto.dev/).
+//! The few protobuf messages needed are encoded by hand, see
+//! [Perfetto's TrackEvent documentation](https://perfetto.dev/docs/instrumentation/track-events)
+//! for details on the format.
+
+use std::{collections::BTreeMap, io::Write};
+
+use crate::GpuTimerQueryResult;
+
+/// Name of the track scopes of the default timeline are placed on, unless [`GpuTimerQueryResult::track_name`] is set.
+const TRACK_NAME: &str = "GPU";
+/// Arbitrary, but unique within the trace. Other timelines use consecutive uuids.
+const TRACK_UUID: u64 = 0x7767_7075;
+/// Arbitrary, all packets are written by the same "writer".
+const TRUSTED_PACKET_SEQUENCE_ID: u64 = 1;
+
+// Protobuf field numbers of the messages we use.
+/// `Trace.packet`
+const FIELD_TRACE_PACKET: u32 = 1;
+/// `TracePacket.timestamp`
+const FIELD_PACKET_TIMESTAMP: u32 = 8;
+/// `TracePacket.trusted_packet_sequence_id`
+const FIELD_PACKET_TRUSTED_PACKET_SEQUENCE_ID: u32 = 10;
+/// `TracePacket.track_event`
+const FIELD_PACKET_TRACK_EVENT: u32 = 11;
+/// `TracePacket.track_descriptor`
+const FIELD_PACKET_TRACK_DESCRIPTOR: u32 = 60;
+/// `TrackDescriptor.uuid`
+const FIELD_TRACK_DESCRIPTOR_UUID: u32 = 1;
+/// `TrackDescriptor.name`
+const FIELD_TRACK_DESCRIPTOR_NAME: u32 = 2;
+/// `TrackEvent.type`
+const FIELD_TRACK_EVENT_TYPE: u32 = 9;
+/// `TrackEvent.track_uuid`
+const FIELD_TRACK_EVENT_TRACK_UUID: u32 = 11;
+/// `TrackEvent.name`
+const FIELD_TRACK_EVENT_NAME: u32 = 23;
+
+/// `TrackEvent.Type.TYPE_SLICE_BEGIN`
+const TRACK_EVENT_TYPE_SLICE_BEGIN: u64 = 1;
+/// `TrackEvent.Type.TYPE_SLICE_END`
+const TRACK_EVENT_TYPE_SLICE_END: u64 = 2;
+
+const WIRE_TYPE_VARINT: u32 = 0;
+const WIRE_TYPE_LENGTH_DELIMITED: u32 = 2;
+
+/// Writes a Perfetto protobuf trace containing all passed frames.
+///
+/// Every scope becomes a slice on a gpu track, nested scopes become nested slices.
+/// Every [`GpuTimerQueryResult::timeline`] gets its own track, named after the
+/// [`GpuTimerQueryResult::track_name`] of its scopes if set.
+/// Scopes without timing information are skipped, their nested scopes are still written.
+/// Frames are expected to be passed in the order they were recorded.
+pub fn write_trace<W: Write>(
+    mut writer: W,
+    frames: &[Vec<GpuTimerQueryResult>],
+) -> std::io::Result<()> {
+    let mut timelines = BTreeMap::from([(0, None)]);
+    for scope in frames.iter().flatten() {
+        let track_name = timelines.entry(scope.timeline).or_insert(None);
+        if track_name.is_none() {
+            *track_name = scope.track_name.as_deref();
+        }
+    }
+    for (timeline, track_name) in timelines {
+        let track_name = match track_name {
+            Some(track_name) => track_name.to_owned(),
+            None if timeline == 0 => TRACK_NAME.to_owned(),
+            None => format!("{TRACK_NAME} timeline {timeline}"),
+        };
+
+        let mut track_descriptor = Vec::new();
+        write_varint_field(
+            &mut track_descriptor,
+            FIELD_TRACK_DESCRIPTOR_UUID,
+            track_uuid(timeline),
+        );
+        write_bytes_field(
+            &mut track_descriptor,
+            FIELD_TRACK_DESCRIPTOR_NAME,
+            track_name.as_bytes(),
+        );
+
+        let mut packet = Vec::new();
+        write_varint_field(
+            &mut packet,
+            FIELD_PACKET_TRUSTED_PACKET_SEQUENCE_ID,
+            TRUSTED_PACKET_SEQUENCE_ID,
+        );
+        write_bytes_field(
+            &mut packet,
+            FIELD_PACKET_TRACK_DESCRIPTOR,
+            &track_descriptor,
+        );
+        write_packet(&mut writer, &packet)?;
+    }
+
+    for frame in frames {
+        write_slices_recursive(&mut writer, frame)?;
+    }
+
+    Ok(())
+}
+
+fn write_slices_recursive<W: Write>(
+    writer: &mut W,
+    results: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    for scope in results {
+        let Some(time) = &scope.time else {
+            write_slices_recursive(writer, &scope.nested_queries)?;
+            continue;
+        };
+
+        write_track_event(
+            writer,
+            track_uuid(scope.timeline),
+            seconds_to_ns(time.start),
+            TRACK_EVENT_TYPE_SLICE_BEGIN,
+            Some(&scope.label),
+        )?;
+        write_slices_recursive(writer, &scope.nested_queries)?;
+        write_track_event(
+            writer,
+            track_uuid(scope.timeline),
+            seconds_to_ns(time.end),
+            TRACK_EVENT_TYPE_SLICE_END,
+            None,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn seconds_to_ns(seconds: f64) -> u64 {
+    (seconds * 1000.0 * 1000.0 * 1000.0) as u64
+}
+
+fn track_uuid(timeline: u32) -> u64 {
+    TRACK_UUID + timeline as u64
+}
+
+fn write_track_event<W: Write>(
+    writer: &mut W,
+    track_uuid: u64,
+    timestamp_ns: u64,
+    event_type: u64,
+    name: Option<&str>,
+) -> std::io::Result<()> {
+    let mut track_event = Vec::new();
+    write_varint_field(&mut track_event, FIELD_TRACK_EVENT_TYPE, event_type);
+    write_varint_field(&mut track_event, FIELD_TRACK_EVENT_TRACK_UUID, track_uuid);
+    if let Some(name) = name {
+        write_bytes_field(&mut track_event, FIELD_TRACK_EVENT_NAME, name.as_bytes());
+    }
+
+    let mut packet = Vec::new();
+    write_varint_field(&mut packet, FIELD_PACKET_TIMESTAMP, timestamp_ns);
+    write_varint_field(
+        &mut packet,
+        FIELD_PACKET_TRUSTED_PACKET_SEQUENCE_ID,
+        TRUSTED_PACKET_SEQUENCE_ID,
+    );
+    write_bytes_field(&mut packet, FIELD_PACKET_TRACK_EVENT, &track_event);
+    write_packet(writer, &packet)
+}
+
+/// Writes a `TracePacket` as an element of the top level `Trace.packet` field.
+///
+/// Since a protobuf message is just a sequence of fields, a stream of packets written like this forms a valid `Trace`.
+fn write_packet<W: Write>(writer: &mut W, packet: &[u8]) -> std::io::Result<()> {
+    let mut header = Vec::with_capacity(6);
+    write_tag(&mut header, FIELD_TRACE_PACKET, WIRE_TYPE_LENGTH_DELIMITED);
+    write_varint(&mut header, packet.len() as u64);
+    writer.write_all(&header)?;
+    writer.write_all(packet)
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn write_tag(buffer: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(buffer, ((field << 3) | wire_type) as u64);
+}
+
+fn write_varint_field(buffer: &mut Vec<u8>, field: u32, value: u64) {
+    write_tag(buffer, field, WIRE_TYPE_VARINT);
+    write_varint(buffer, value);
+}
+
+fn write_bytes_field(buffer: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_tag(buffer, field, WIRE_TYPE_LENGTH_DELIMITED);
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
@@ -0,0 +1,64 @@
+use std::io::Write;
+
+use crate::{flatten, GpuTimerQueryResult};
+
+/// Writes profiling results as indented text with one line per scope, indenting nested scopes by two spaces.
+///
+/// Every line consists of the scope's label followed by its duration and,
+/// in parentheses, its [`GpuTimerQueryResult::self_time`], both in milliseconds:
+/// ```text
+/// frame:    4.50 ms (0.25 ms self)
+///   shadow: 1.23 ms (1.23 ms self)
+///   main:   3.02 ms (3.02 ms self)
+/// ```
+/// Durations are right-aligned. Scopes without timing information only show their label.
+pub fn print_tree<W: Write>(
+    writer: W,
+    profile_data: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    print_tree_with_indent(writer, profile_data, 2)
+}
+
+/// Like [`print_tree`], but indents nested scopes by `indent` spaces per nesting level.
+pub fn print_tree_with_indent<W: Write>(
+    mut writer: W,
+    profile_data: &[GpuTimerQueryResult],
+    indent: usize,
+) -> std::io::Result<()> {
+    let lines = flatten(profile_data)
+        .map(|(depth, scope)| {
+            let label = format!("{:width$}{}:", "", scope.label, width = depth * indent);
+            let durations = scope.time.as_ref().map(|time| {
+                (
+                    format!("{:.2}", (time.end - time.start) * 1000.0),
+                    format!("{:.2}", scope.self_time().unwrap_or_default() * 1000.0),
+                )
+            });
+            (label, durations)
+        })
+        .collect::<Vec<_>>();
+
+    let label_width = lines
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or_default();
+    let duration_width = lines
+        .iter()
+        .filter_map(|(_, durations)| durations.as_ref())
+        .map(|(duration, _)| duration.len())
+        .max()
+        .unwrap_or_default();
+
+    for (label, durations) in lines {
+        match durations {
+            Some((duration, self_time)) => writeln!(
+                writer,
+                "{label:<label_width$} {duration:>duration_width$} ms ({self_time} ms self)"
+            )?,
+            None => writeln!(writer, "{label}")?,
+        }
+    }
+
+    Ok(())
+}
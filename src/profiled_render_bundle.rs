@@ -0,0 +1,29 @@
+use std::borrow::Cow;
+
+/// A [`wgpu::RenderBundle`] along with the label of the scope its executions are attributed to.
+///
+/// Scopes opened on a [`wgpu::RenderBundleEncoder`] are never timed and show up in the frame the bundle was
+/// recorded in, no matter how often or in which frame the bundle is executed later on.
+/// Executing a bundle via [`crate::GpuProfiler::execute_bundle`] instead times every execution
+/// as a separate scope with this label, reported in the frame the execution was recorded in.
+pub struct ProfiledRenderBundle {
+    pub bundle: wgpu::RenderBundle,
+    pub label: Cow<'static, str>,
+}
+
+impl ProfiledRenderBundle {
+    pub fn new(label: impl Into<Cow<'static, str>>, bundle: wgpu::RenderBundle) -> Self {
+        Self {
+            bundle,
+            label: label.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ProfiledRenderBundle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProfiledRenderBundle")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     sync::{
         atomic::{AtomicU32, Ordering},
@@ -9,10 +10,73 @@ use std::{
 use parking_lot::{Mutex, RwLock};
 
 use crate::{
-    CreationError, EndFrameError, GpuProfilerQuery, GpuProfilerSettings, GpuTimerQueryResult,
-    ManualOwningScope, OwningScope, ProfilerCommandRecorder, Scope, SettingsError,
+    profiler_query::{decode_timestamps, result_from_raw, OverlapDetector, ResultTreeBuilder},
+    BeginQueryError, ClockCalibration, CpuScope, CreationError, EndFrameError, FinishedFrame,
+    FrameGuard, GpuProfilerQuery, GpuProfilerSettings, GpuTimerQueryRawResult, GpuTimerQueryResult,
+    ManualOwningScope, OwningScope, PassTimestampMethod, PipelineStats, ProcessFrameError,
+    ProfiledRenderBundle, ProfilerCommandRecorder, QueryId, QueryOverflowPolicy, QueryPoolError,
+    Scope, ScopeOverlap, SettingsError, TimestampAnomaly, TraceColor,
 };
 
+/// What a [`GpuProfiler`] is able to measure with a given set of device features.
+///
+/// Retrieved via [`GpuProfiler::features_supported`].
+/// Regardless of capabilities, all queries push debug groups (if enabled) and show up in the results,
+/// only their [`GpuTimerQueryResult::time`] is `None` if they can't be timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfilerCapabilities {
+    /// Queries on command encoders and queries for render/compute pass timestamp writes are timed.
+    ///
+    /// Requires [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub encoder_timer_queries: bool,
+
+    /// Queries inside of render/compute passes are timed.
+    ///
+    /// Requires [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] in addition to [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub pass_timer_queries: bool,
+}
+
+impl ProfilerCapabilities {
+    /// True if no query can be timed, i.e. the profiler only provides labels & debug groups.
+    pub fn is_labels_only(&self) -> bool {
+        !self.encoder_timer_queries && !self.pass_timer_queries
+    }
+}
+
+/// Gpu memory currently allocated by a [`GpuProfiler`], retrieved via [`GpuProfiler::memory_report`].
+///
+/// Covers the query pools of the active frame, of all pending frames and those kept around for reuse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryReport {
+    /// Number of query pools, each consisting of a query set along with a resolve & a read buffer.
+    pub num_query_pools: u32,
+
+    /// Bytes needed to store the results of all query sets, i.e. [`wgpu::QUERY_SIZE`] per query.
+    ///
+    /// How much memory a query set actually occupies is up to the driver.
+    pub query_set_bytes: u64,
+
+    /// Bytes of all buffers that queries are resolved into.
+    pub resolve_buffer_bytes: u64,
+
+    /// Bytes of all mappable buffers that resolved queries are read back from.
+    pub read_buffer_bytes: u64,
+}
+
+impl MemoryReport {
+    /// Sum of all query set & buffer bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.query_set_bytes + self.resolve_buffer_bytes + self.read_buffer_bytes
+    }
+
+    fn add_pool(&mut self, pool: &QueryPool) {
+        self.num_query_pools += 1;
+        self.query_set_bytes += pool.capacity as u64 * pool.query_size();
+        self.resolve_buffer_bytes += pool.resolve_buffer.size();
+        self.read_buffer_bytes += pool.read_buffer.size();
+    }
+}
+
 /// Profiler instance.
 ///
 /// You can have an arbitrary number of independent profiler instances per application/adapter.
@@ -20,6 +84,11 @@ use crate::{
 ///
 /// Any query creation method may allocate a new [`wgpu::QuerySet`] and [`wgpu::Buffer`] internally if necessary.
 ///
+/// All methods for opening & closing queries take `&self`, so a single profiler can be shared across threads
+/// that record command buffers in parallel.
+/// Only per-frame operations like [`GpuProfiler::resolve_queries`], [`GpuProfiler::end_frame`] and
+/// [`GpuProfiler::process_finished_frame`] require exclusive access.
+///
 /// After the first call that passes [`wgpu::Device`], the same device must be used with all subsequent
 /// calls to [`GpuProfiler`] and all passed references to wgpu objects must originate from that device.
 pub struct GpuProfiler {
@@ -30,18 +99,110 @@ pub struct GpuProfiler {
 
     num_open_queries: AtomicU32,
     next_query_handle: AtomicU32,
+    /// Incremented whenever the queries of the active frame are discarded, see [`GpuProfilerQuery::generation`].
+    query_generation: u32,
+
+    /// Labels of all open queries, only maintained if [`GpuProfilerSettings::track_open_queries`] is enabled.
+    open_queries: Mutex<HashMap<GpuTimerQueryTreeHandle, Cow<'static, str>>>,
+
+    /// Number of timer queries requested in the active frame, including dropped ones.
+    num_requested_timer_queries: AtomicU32,
+    /// Number of timer queries dropped in the active frame due to [`QueryOverflowPolicy::Drop`].
+    num_dropped_queries: AtomicU32,
+    /// Number of timer queries dropped in the last ended frame.
+    num_dropped_queries_last_frame: u32,
+
+    /// Number of queries in the active frame nested deeper than [`GpuProfilerSettings::max_query_depth`].
+    num_queries_exceeding_max_depth: AtomicU32,
+    /// Label of the first query in the active frame nested deeper than [`GpuProfilerSettings::max_query_depth`].
+    first_query_exceeding_max_depth: Mutex<Option<String>>,
+    /// Number of queries in the last ended frame nested deeper than [`GpuProfilerSettings::max_query_depth`].
+    num_queries_exceeding_max_depth_last_frame: u32,
+    /// Label of the first query in the last ended frame nested deeper than [`GpuProfilerSettings::max_query_depth`].
+    first_query_exceeding_max_depth_last_frame: Option<String>,
 
     size_for_new_query_pools: u32,
 
+    /// Timestamp period (in nanoseconds per tick) that was used most recently.
+    timestamp_period: Option<f32>,
+
+    /// Anomalies of the frame that was most recently returned by [`GpuProfiler::process_finished_frame`].
+    timestamp_anomalies: Vec<TimestampAnomaly>,
+
+    /// Overlaps of the frame that was most recently returned by [`GpuProfiler::process_finished_frame`].
+    overlapping_scopes: Vec<ScopeOverlap>,
+
+    /// Cleared nested result vectors to reuse, see [`GpuProfilerSettings::recycle_result_buffers`].
+    recycled_result_vecs: Vec<Vec<GpuTimerQueryResult>>,
+
+    /// Total gpu time of the frame that was most recently returned by [`GpuProfiler::process_finished_frame`].
+    last_frame_total: Option<f64>,
+
+    /// Resolved timestamps of the frame that was most recently processed, see [`GpuProfiler::last_frame_raw_timestamps`].
+    last_frame_raw_timestamps: Option<Vec<u64>>,
+
+    /// Most recent result of [`GpuProfiler::calibrate`].
+    clock_calibration: Option<ClockCalibration>,
+
+    /// Origin of all cpu timings, set lazily on the first cpu timing.
+    cpu_time_origin: std::sync::OnceLock<std::time::Instant>,
+
+    /// Prefix for the labels of all queries, see [`GpuProfiler::push_namespace`].
+    namespace: RwLock<Namespace>,
+
+    /// Scopes registered via [`GpuProfiler::register_scope`], indexed by [`RegisteredScopeId`].
+    registered_scopes: Vec<RegisteredScope>,
+
     settings: GpuProfilerSettings,
 
+    enabled: bool,
+    enabled_next_frame: bool,
+
+    /// Index of the active frame, see [`GpuProfiler::frame_index`].
+    frame_index: u64,
+
+    /// Number of frames ended since the last frame that was sampled, see [`GpuProfilerSettings::sample_interval`].
+    frames_since_sample: u32,
+    /// Whether the current frame is sampled, see [`GpuProfilerSettings::sample_interval`].
+    is_sampled_frame: bool,
+
+    /// Set by [`GpuProfiler::on_device_lost`] until [`GpuProfiler::change_device`] is called.
+    needs_device: bool,
+
     #[cfg(feature = "tracy")]
     tracy_context: Option<tracy_client::GpuContext>,
 }
 
+impl std::fmt::Debug for GpuProfiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuProfiler")
+            .field("enabled", &self.enabled)
+            .field("num_pending_frames", &self.num_pending_frames())
+            .field(
+                "num_open_queries",
+                &self.num_open_queries.load(Ordering::Relaxed),
+            )
+            .field(
+                "num_queries_in_active_frame",
+                &self.num_queries_in_active_frame(),
+            )
+            .field("settings", &self.settings)
+            .finish_non_exhaustive()
+    }
+}
+
 // Public interface
 impl GpuProfiler {
     /// Combination of all timer query features [`GpuProfiler`] can leverage.
+    ///
+    /// * [`wgpu::Features::TIMESTAMP_QUERY`] is required for any timing at all:
+    ///   it allows timing scopes on command encoders and whole passes via their timestamp writes
+    ///   (`scoped_render_pass`/`scoped_compute_pass`).
+    /// * [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] additionally allows timing scopes within passes,
+    ///   i.e. scopes opened on a `wgpu::RenderPass`/`wgpu::ComputePass` and [`crate::PassTimestampMethod::InsidePass`].
+    ///
+    /// Request only those features the adapter supports, e.g. by intersecting with [`wgpu::Adapter::features`].
+    /// See also [`GpuProfiler::required_features`].
     pub const ALL_WGPU_TIMER_FEATURES: wgpu::Features =
         wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES);
 
@@ -49,6 +210,49 @@ impl GpuProfiler {
     #[deprecated(since = "0.9.0", note = "Use ALL_WGPU_TIMER_FEATURES instead")]
     pub const REQUIRED_WGPU_FEATURES: wgpu::Features = GpuProfiler::ALL_WGPU_TIMER_FEATURES;
 
+    /// Maximum number of nested result vectors kept for reuse, see [`GpuProfilerSettings::recycle_result_buffers`].
+    pub const MAX_NUM_RECYCLED_RESULT_VECS: usize = 1024;
+
+    /// Maximum capacity of nested result vectors kept for reuse, see [`GpuProfilerSettings::recycle_result_buffers`].
+    pub const MAX_RECYCLED_RESULT_VEC_CAPACITY: usize = 256;
+
+    /// Features a device needs for the profiler to measure everything the given settings ask for,
+    /// except for scopes within passes.
+    ///
+    /// This is [`wgpu::Features::TIMESTAMP_QUERY`] if [`GpuProfilerSettings::enable_timer_queries`] is set,
+    /// plus [`wgpu::Features::PIPELINE_STATISTICS_QUERY`] if [`GpuProfilerSettings::pipeline_statistics`] isn't empty.
+    /// For timing scopes within passes, additionally request [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`],
+    /// see [`GpuProfiler::ALL_WGPU_TIMER_FEATURES`].
+    /// Empty if the `profiling-enabled` feature is disabled.
+    ///
+    /// Missing features never make the profiler fail, they only leave scopes untimed, see [`GpuProfiler::features_supported`].
+    pub fn required_features(settings: &GpuProfilerSettings) -> wgpu::Features {
+        let mut features = wgpu::Features::empty();
+        if !PROFILING_ENABLED {
+            return features;
+        }
+        if settings.enable_timer_queries {
+            features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
+        if !settings.pipeline_statistics.is_empty() {
+            features |= wgpu::Features::PIPELINE_STATISTICS_QUERY;
+        }
+        features
+    }
+
+    /// Determines what the profiler can measure on a device with the given features.
+    ///
+    /// Creating a profiler never fails because of missing features,
+    /// queries that can't be timed simply don't have a [`GpuTimerQueryResult::time`].
+    pub fn features_supported(features: wgpu::Features) -> ProfilerCapabilities {
+        let encoder_timer_queries = features.contains(wgpu::Features::TIMESTAMP_QUERY);
+        ProfilerCapabilities {
+            encoder_timer_queries,
+            pass_timer_queries: encoder_timer_queries
+                && features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES),
+        }
+    }
+
     /// Creates a new Profiler object.
     ///
     /// There is nothing preventing the use of several independent profiler objects.
@@ -69,17 +273,126 @@ impl GpuProfiler {
 
             num_open_queries: AtomicU32::new(0),
             next_query_handle: AtomicU32::new(0),
+            query_generation: 0,
+            open_queries: Mutex::new(if settings.track_open_queries {
+                HashMap::with_capacity(settings.expected_queries_per_frame as usize)
+            } else {
+                HashMap::new()
+            }),
 
-            size_for_new_query_pools: QueryPool::MIN_CAPACITY,
+            num_requested_timer_queries: AtomicU32::new(0),
+            num_dropped_queries: AtomicU32::new(0),
+            num_dropped_queries_last_frame: 0,
+
+            num_queries_exceeding_max_depth: AtomicU32::new(0),
+            first_query_exceeding_max_depth: Mutex::new(None),
+            num_queries_exceeding_max_depth_last_frame: 0,
+            first_query_exceeding_max_depth_last_frame: None,
+
+            size_for_new_query_pools: QueryPool::capacity_for_queries(
+                settings.expected_queries_per_frame,
+            ),
+
+            timestamp_period: None,
+            timestamp_anomalies: Vec::new(),
+            overlapping_scopes: Vec::new(),
+            recycled_result_vecs: Vec::new(),
+            last_frame_total: None,
+            last_frame_raw_timestamps: None,
+            clock_calibration: None,
+            cpu_time_origin: std::sync::OnceLock::new(),
+            namespace: RwLock::new(Namespace::default()),
+            registered_scopes: Vec::new(),
 
             settings,
 
+            enabled: PROFILING_ENABLED,
+            enabled_next_frame: PROFILING_ENABLED,
+
+            frame_index: 0,
+
+            frames_since_sample: 0,
+            is_sampled_frame: true,
+
+            needs_device: false,
+
             #[cfg(feature = "tracy")]
             tracy_context: None,
         })
     }
 
+    /// Creates a new Profiler object and initializes [`GpuProfiler::timestamp_period`] from the given queue.
+    ///
+    /// Use this together with [`GpuProfiler::process_finished_frame_with_queue`] to never pass the timestamp period manually.
+    pub fn new_with_queue(
+        settings: GpuProfilerSettings,
+        queue: &wgpu::Queue,
+    ) -> Result<Self, CreationError> {
+        let mut profiler = Self::new(settings)?;
+        profiler.timestamp_period = Some(queue.get_timestamp_period());
+        Ok(profiler)
+    }
+
+    /// Creates a new Profiler object with [`GpuProfilerSettings::default`], taking the timestamp period from the given queue.
+    ///
+    /// Shorthand for [`GpuProfiler::new_with_queue`], convenient for quick experiments.
+    pub fn new_default(queue: &wgpu::Queue) -> Self {
+        Self::new_with_queue(GpuProfilerSettings::default(), queue)
+            .expect("Default settings are always valid")
+    }
+
+    /// Creates a new Profiler object that uses the passed query sets & buffers before allocating any of its own.
+    ///
+    /// This allows all gpu resources to be allocated by the application.
+    /// The profiler only allocates additional query sets & buffers if the passed pools are exhausted:
+    /// With [`QueryOverflowPolicy::Drop`], one pool per pending frame plus one for the active frame,
+    /// i.e. [`GpuProfilerSettings::max_num_pending_frames`] + 1 pools, are sufficient to never allocate.
+    ///
+    /// Fails if a pool's buffers are too small or have the wrong usages, or if its capacity can't hold
+    /// [`GpuProfilerSettings::max_num_queries_per_frame`] with [`QueryOverflowPolicy::Drop`].
+    /// Note that the type of the query set can't be validated, it has to be [`wgpu::QueryType::Timestamp`].
+    pub fn new_with_query_pools(
+        settings: GpuProfilerSettings,
+        query_pools: impl IntoIterator<Item = QueryPoolResources>,
+    ) -> Result<Self, CreationError> {
+        let mut profiler = Self::new(settings)?;
+        let required_capacity = match profiler.settings.query_overflow_policy {
+            QueryOverflowPolicy::Drop => profiler
+                .settings
+                .max_num_queries_per_frame
+                .saturating_mul(2),
+            QueryOverflowPolicy::Grow => 0,
+        };
+        for resources in query_pools {
+            let pool = QueryPool::from_resources(resources)?;
+            if pool.capacity < required_capacity {
+                return Err(QueryPoolError::CapacityTooSmall {
+                    capacity: pool.capacity,
+                    required: required_capacity,
+                }
+                .into());
+            }
+            profiler
+                .active_frame
+                .query_pools
+                .get_mut()
+                .unused_pools
+                .push(pool);
+        }
+        Ok(profiler)
+    }
+
     /// Creates a new profiler and connects to a running Tracy client.
+    ///
+    /// On creation, a single timestamp is written, submitted and read back (blocking!)
+    /// in order to calibrate the gpu timeline against Tracy's cpu timeline.
+    ///
+    /// Every query opened with timer queries enabled then opens a Tracy gpu zone with the query's label,
+    /// nested the same way as the query.
+    /// The zones' gpu timestamps are uploaded to Tracy once the frame's results are retrieved via
+    /// [`GpuProfiler::process_finished_frame`].
+    ///
+    /// Fails with [`CreationError::TracyClientNotRunning`] if [`tracy_client::Client`] wasn't started yet.
     #[cfg(feature = "tracy")]
     pub fn new_with_tracy_client(
         settings: GpuProfilerSettings,
@@ -106,11 +419,139 @@ impl GpuProfiler {
         if !settings.enable_timer_queries {
             self.unused_pools.clear();
         }
+        if !settings.track_open_queries {
+            self.open_queries.get_mut().clear();
+        }
+        if !settings.recycle_result_buffers {
+            self.recycled_result_vecs = Vec::new();
+        }
+        self.size_for_new_query_pools =
+            self.size_for_new_query_pools
+                .max(QueryPool::capacity_for_queries(
+                    settings.expected_queries_per_frame,
+                ));
         self.settings = settings;
+        for registered_scope in &mut self.registered_scopes {
+            registered_scope.is_profiled =
+                Self::is_profiled_label(&self.settings, &registered_scope.label);
+        }
+
+        Ok(())
+    }
+
+    /// Sets [`GpuProfilerSettings::max_num_pending_frames`], bounding the memory used for frames in flight.
+    ///
+    /// Takes effect at the next call to [`GpuProfiler::end_frame`]: if more frames are pending than the new limit allows,
+    /// the newest pending frames are dropped, the oldest ones are kept and can still be processed.
+    pub fn set_max_num_pending_frames(
+        &mut self,
+        max_num_pending_frames: usize,
+    ) -> Result<(), SettingsError> {
+        if max_num_pending_frames == 0 {
+            return Err(SettingsError::InvalidMaxNumPendingFrames);
+        }
+        self.settings.max_num_pending_frames = max_num_pending_frames;
+        Ok(())
+    }
 
+    /// Sets [`GpuProfilerSettings::sample_interval`], profiling only every `sample_interval`-th frame.
+    ///
+    /// Takes effect at the next call to [`GpuProfiler::end_frame`], after which the next frame is sampled.
+    /// [`GpuProfiler::is_enabled`] returns false for frames that are skipped.
+    pub fn set_sample_interval(&mut self, sample_interval: u32) -> Result<(), SettingsError> {
+        if sample_interval == 0 {
+            return Err(SettingsError::InvalidSampleInterval);
+        }
+        self.settings.sample_interval = sample_interval;
+        self.frames_since_sample = sample_interval - 1;
         Ok(())
     }
 
+    /// Discards all pending frames and all queries of the current frame.
+    ///
+    /// Useful to get rid of stale results after a hitch, e.g. a long loading phase.
+    /// Afterwards, the profiler behaves as if it was just created, except that query sets & buffers are kept for reuse.
+    /// Queries of the current frame are invalidated, closing them afterwards has no effect.
+    pub fn clear(&mut self) {
+        self.reset_frames();
+    }
+
+    /// Moves the profiler to a different device, e.g. after the previous one was lost.
+    ///
+    /// Releases all query sets & buffers of the previous device and allocates a fresh query pool on the new device
+    /// that is as large as the ones previously used.
+    /// All pending frames are discarded since their results can no longer be read back.
+    /// Queries of the current frame are invalidated as well, closing them afterwards has no effect.
+    /// Settings are preserved and the timestamp period is updated from the passed queue.
+    ///
+    /// Note that a Tracy gpu context created with [`GpuProfiler::new_with_tracy_client`] stays associated
+    /// with the previous device.
+    pub fn change_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.release_frames_and_query_pools();
+
+        let query_pools = self.active_frame.query_pools.get_mut();
+        if self.settings.enable_timer_queries
+            && device.features().contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            query_pools
+                .unused_pools
+                .push(QueryPool::new(self.size_for_new_query_pools, device));
+        }
+
+        self.timestamp_period = Some(queue.get_timestamp_period());
+
+        if self.needs_device {
+            self.needs_device = false;
+            self.enabled = self.enabled_next_frame && self.is_sampled_frame;
+        }
+    }
+
+    /// Releases all query sets & buffers after the device was lost.
+    ///
+    /// All pending frames and all queries of the current frame are discarded without issuing any further
+    /// operations on the lost device's resources.
+    /// Queries of the current frame are invalidated, closing them afterwards has no effect.
+    ///
+    /// Until a new device is passed to [`GpuProfiler::change_device`], the profiler behaves as if it was disabled:
+    /// Scopes can still be opened, but no queries are allocated and no results are produced.
+    /// Use [`GpuProfiler::needs_device`] to check whether this is the case.
+    pub fn on_device_lost(&mut self) {
+        self.release_frames_and_query_pools();
+
+        self.needs_device = true;
+        self.enabled = false;
+    }
+
+    /// Returns whether [`GpuProfiler::on_device_lost`] was called without a subsequent [`GpuProfiler::change_device`].
+    pub fn needs_device(&self) -> bool {
+        self.needs_device
+    }
+
+    /// Enables or disables the profiler entirely.
+    ///
+    /// A disabled profiler doesn't write any timestamps, doesn't allocate any queries, doesn't push debug groups
+    /// and doesn't even create query labels.
+    /// All scope types & query methods can still be used as before, they just don't produce any results.
+    ///
+    /// To avoid unbalanced queries, the change is deferred to the next frame boundary,
+    /// i.e. it takes effect after the next successful call to [`GpuProfiler::end_frame`].
+    ///
+    /// Unlike [`GpuProfilerSettings::enable_timer_queries`], this also disables debug groups.
+    ///
+    /// Has no effect if the `profiling-enabled` feature is disabled, in which case the profiler is always disabled.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled_next_frame = enabled && PROFILING_ENABLED;
+    }
+
+    /// Returns whether the profiler is enabled for the current frame.
+    ///
+    /// Changes made with [`GpuProfiler::set_enabled`] are only reflected here after the next frame boundary.
+    /// Frames skipped due to [`GpuProfilerSettings::sample_interval`] count as disabled.
+    /// While the profiler [needs a new device](GpuProfiler::needs_device), it is disabled as well.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
     /// Starts a new auto-closing profiler scope.
     ///
     /// To nest scopes inside this scope, call [`Scope::scope`] on the returned scope.
@@ -123,13 +564,17 @@ impl GpuProfiler {
     ///
     /// If [`GpuProfilerSettings::enable_debug_groups`] is true, a debug group will be pushed on the encoder or pass.
     ///
+    /// Scopes on a [`wgpu::CommandEncoder`] aren't limited to passes, they can wrap any encoder commands.
+    /// In particular, copies like [`wgpu::CommandEncoder::copy_buffer_to_buffer`] can be timed this way
+    /// in order to measure uploads & downloads.
+    ///
     /// Scope is automatically closed on drop.
     #[must_use]
     #[track_caller]
     #[inline]
     pub fn scope<'a, Recorder: ProfilerCommandRecorder>(
         &'a self,
-        label: impl Into<String>,
+        label: impl Into<Cow<'static, str>>,
         encoder_or_pass: &'a mut Recorder,
         device: &wgpu::Device,
     ) -> Scope<'a, Recorder> {
@@ -141,6 +586,47 @@ impl GpuProfiler {
         }
     }
 
+    /// Like [`GpuProfiler::scope`], but tags the scope with a category, see [`GpuProfiler::begin_query_with_category`].
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn scope_with_category<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<Cow<'static, str>>,
+        category: &'static str,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder> {
+        let scope = self.begin_query_with_category(label, category, encoder_or_pass, device);
+        Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    /// Like [`GpuProfiler::scope`], but fails instead of opening a scope that can't be timed.
+    ///
+    /// This is useful for profiling passes that were begun elsewhere, e.g. by a framework that only hands out
+    /// a `&mut wgpu::RenderPass`: timing a section of such a pass requires
+    /// [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`], whose absence is reported as
+    /// [`BeginQueryError::TimestampWritesNotSupported`].
+    /// See [`GpuProfiler::try_begin_query`] for all failure reasons.
+    #[track_caller]
+    pub fn try_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<Cow<'static, str>>,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Result<Scope<'a, Recorder>, BeginQueryError> {
+        let scope = self.try_begin_query(label, encoder_or_pass, device)?;
+        Ok(Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        })
+    }
+
     /// Starts a new auto-closing profiler scope that takes ownership of the passed encoder or rendering/compute pass.
     ///
     /// To nest scopes inside this scope, call [`OwningScope::scope`] on the returned scope.
@@ -159,7 +645,7 @@ impl GpuProfiler {
     #[inline]
     pub fn owning_scope<'a, Recorder: ProfilerCommandRecorder>(
         &'a self,
-        label: impl Into<String>,
+        label: impl Into<Cow<'static, str>>,
         mut encoder_or_pass: Recorder,
         device: &wgpu::Device,
     ) -> OwningScope<'a, Recorder> {
@@ -192,7 +678,7 @@ impl GpuProfiler {
     #[inline]
     pub fn manual_owning_scope<'a, Recorder: ProfilerCommandRecorder>(
         &'a self,
-        label: impl Into<String>,
+        label: impl Into<Cow<'static, str>>,
         mut encoder_or_pass: Recorder,
         device: &wgpu::Device,
     ) -> ManualOwningScope<'a, Recorder> {
@@ -204,122 +690,613 @@ impl GpuProfiler {
         }
     }
 
-    /// Starts a new profiler query on the given encoder or rendering/compute pass (if enabled).
-    ///
-    /// The returned query *must* be closed by calling [`GpuProfiler::end_query`] with the same encoder/pass,
-    /// even if timer queries are disabled.
-    /// To do this automatically, use [`GpuProfiler::scope`]/[`GpuProfiler::owning_scope`] instead.
+    /// Starts a render pass wrapped in a top-level [`OwningScope`], timed via the pass' timestamp writes.
     ///
-    /// If an [`wgpu::CommandEncoder`] is passed but the [`wgpu::Device`]
-    /// does not support [`wgpu::Features::TIMESTAMP_QUERY`], no gpu timer will be queried and the scope will
-    /// not show up in the final results.
-    /// If an [`wgpu::ComputePass`] or [`wgpu::RenderPass`] is passed but the [`wgpu::Device`]
-    /// does not support [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`], no timer queries will be allocated.
+    /// Ignores passed `wgpu::RenderPassDescriptor::timestamp_writes` and replaces it with
+    /// `timestamp_writes` managed by `GpuProfiler`.
+    /// Measuring the pass this way only requires [`wgpu::Features::TIMESTAMP_QUERY`],
+    /// not [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`].
     ///
-    /// If [`GpuProfilerSettings::enable_debug_groups`] is true, a debug group will be pushed on the encoder or pass.
-    #[track_caller]
+    /// Same as [`Scope::scoped_render_pass`], but doesn't require an enclosing scope.
     #[must_use]
-    pub fn begin_query<Recorder: ProfilerCommandRecorder>(
-        &self,
-        label: impl Into<String>,
-        encoder_or_pass: &mut Recorder,
+    #[track_caller]
+    pub fn scoped_render_pass<'a>(
+        &'a self,
+        label: impl Into<Cow<'static, str>>,
+        encoder: &'a mut wgpu::CommandEncoder,
         device: &wgpu::Device,
-    ) -> GpuProfilerQuery {
-        let mut query = self.begin_query_internal(label.into(), encoder_or_pass, device);
-        if let Some(timer_query) = &mut query.timer_query_pair {
-            encoder_or_pass
-                .write_timestamp(&timer_query.pool.query_set, timer_query.start_query_idx);
-            timer_query.usage_state = QueryPairUsageState::OnlyStartWritten;
-        };
+        pass_descriptor: wgpu::RenderPassDescriptor<'a, '_>,
+    ) -> OwningScope<'a, wgpu::RenderPass<'a>> {
+        let mut query = self.begin_pass_query(label, encoder, device);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            timestamp_writes: query.render_pass_timestamp_writes(),
+            ..pass_descriptor
+        });
+        self.begin_pipeline_statistics_query(&mut query, &mut render_pass, device);
 
-        if self.settings.enable_debug_groups {
-            encoder_or_pass.push_debug_group(&query.label);
-            query.has_debug_group = true;
+        OwningScope {
+            profiler: self,
+            recorder: render_pass,
+            scope: Some(query),
         }
-        query
     }
 
-    /// Starts a new profiler query to be used for render/compute pass timestamp writes.
-    ///
-    /// The returned query *must* be closed by calling [`GpuProfiler::end_query`], even if timer queries are disabled.
-    /// To do this automatically, use [`Scope::scoped_render_pass`]/[`Scope::scoped_compute_pass`] instead.
-    ///
-    /// Call [`GpuProfilerQuery::render_pass_timestamp_writes`] or [`GpuProfilerQuery::compute_pass_timestamp_writes`]
-    /// to acquire the corresponding `wgpu::RenderPassTimestampWrites`/`wgpu::ComputePassTimestampWrites` object.
+    /// Starts a compute pass wrapped in a top-level [`OwningScope`], timed via the pass' timestamp writes.
     ///
-    /// If the [`wgpu::Device`] does not support [`wgpu::Features::TIMESTAMP_QUERY`], no gpu timer will be reserved.
+    /// Uses passed label both for profiler scope and compute pass label.
+    /// Measuring the pass this way only requires [`wgpu::Features::TIMESTAMP_QUERY`],
+    /// not [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`].
     ///
-    /// Unlike [`GpuProfiler::begin_query`] this will not create a debug scope,
-    /// in order to not force passing of the same encoder/pass to [`GpuProfiler::end_query`].
-    /// (this is needed to relax resource tracking requirements a bit, making it easier to implement the automatic scopes)
-    pub fn begin_pass_query(
-        &self,
-        label: impl Into<String>,
-        encoder: &mut wgpu::CommandEncoder,
+    /// Same as [`Scope::scoped_compute_pass`], but doesn't require an enclosing scope.
+    #[must_use]
+    #[track_caller]
+    pub fn scoped_compute_pass<'a>(
+        &'a self,
+        label: impl Into<Cow<'static, str>>,
+        encoder: &'a mut wgpu::CommandEncoder,
         device: &wgpu::Device,
-    ) -> GpuProfilerQuery {
-        let mut query = self.begin_query_internal(label.into(), encoder, device);
-        if let Some(timer_query) = &mut query.timer_query_pair {
-            timer_query.usage_state = QueryPairUsageState::ReservedForPassTimestampWrites;
+    ) -> OwningScope<'a, wgpu::ComputePass<'a>> {
+        let mut query = self.begin_pass_query(label, encoder, device);
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(&query.label),
+            timestamp_writes: query.compute_pass_timestamp_writes(),
+        });
+        self.begin_pipeline_statistics_query(&mut query, &mut compute_pass, device);
+
+        OwningScope {
+            profiler: self,
+            recorder: compute_pass,
+            scope: Some(query),
         }
-        query
     }
 
-    /// Ends passed query.
+    /// Executes a render bundle within a new profiler scope, labeled as given by [`ProfiledRenderBundle::label`].
     ///
-    /// If the passed query was opened with [`GpuProfiler::begin_query`], the passed encoder or pass must be the same
-    /// as when the query was opened.
-    pub fn end_query<Recorder: ProfilerCommandRecorder>(
+    /// Every call opens a separate scope, so a bundle that is executed several times shows up once per execution.
+    /// Like any other scope inside a render pass, this requires [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`]
+    /// for measurements.
+    #[track_caller]
+    pub fn execute_bundle<'a>(
         &self,
-        encoder_or_pass: &mut Recorder,
-        mut query: GpuProfilerQuery,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        bundle: &'a ProfiledRenderBundle,
+        device: &wgpu::Device,
     ) {
-        if let Some(timer_query) = &mut query.timer_query_pair {
-            match timer_query.usage_state {
-                QueryPairUsageState::Reserved => {
-                    unreachable!("Query pair has been reserved but isn't used for anything!")
-                }
-                QueryPairUsageState::ReservedForPassTimestampWrites => {
-                    // No need to do a timestamp write, this is handled by wgpu.
-                }
-                QueryPairUsageState::OnlyStartWritten => {
-                    encoder_or_pass.write_timestamp(
-                        &timer_query.pool.query_set,
-                        timer_query.start_query_idx + 1,
-                    );
-                    timer_query.usage_state = QueryPairUsageState::BothStartAndEndWritten;
-                }
-                QueryPairUsageState::BothStartAndEndWritten => {
-                    unreachable!("Query pair has already been used!")
-                }
-            }
-        }
-
-        #[cfg(feature = "tracy")]
-        if let Some(ref mut tracy_scope) = query.tracy_scope {
-            tracy_scope.end_zone();
-        }
-
-        if query.has_debug_group {
-            encoder_or_pass.pop_debug_group();
-        }
-
-        let send_result = self.active_frame.closed_query_sender.send(query);
-
-        // The only way we can fail sending the query is if the receiver has been dropped.
-        // Since it sits on `active_frame` as well, there's no way for this to happen!
-        debug_assert!(send_result.is_ok());
-
-        // Count queries even if we haven't processed this one, makes experiences more consistent
-        // if there's a lack of support for some queries.
-        self.num_open_queries.fetch_sub(1, Ordering::Release);
+        let query = self.begin_query(bundle.label.clone(), render_pass, device);
+        render_pass.execute_bundles(std::iter::once(&bundle.bundle));
+        self.end_query(render_pass, query);
     }
 
-    /// Puts query resolve commands in the encoder for all unresolved, pending queries of the active profiler frame.
+    /// Runs the passed closure within a new profiler scope and returns the closure's result.
     ///
-    /// Note that you do *not* need to do this for every encoder, it is sufficient do do this once per frame as long
-    /// as you submit the corresponding command buffer after all others that may have opened queries in the same frame.
-    /// (It does not matter if the passed encoder itself has previously opened queries or not.)
+    /// The scope is opened before `f` is invoked and closed as soon as `f` returns, no matter how it returns.
+    /// The closure receives the [`Scope`] wrapping the passed encoder or pass,
+    /// which can be used just like the encoder/pass itself or to nest further scopes via [`Scope::profile`].
+    ///
+    /// If an [`wgpu::CommandEncoder`] is passed but the [`wgpu::Device`]
+    /// does not support [`wgpu::Features::TIMESTAMP_QUERY`], no gpu timer will be queried and the scope will
+    /// not show up in the final results.
+    /// If an [`wgpu::ComputePass`] or [`wgpu::RenderPass`] is passed but the [`wgpu::Device`]
+    /// does not support [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`], no scope will be opened.
+    ///
+    /// If [`GpuProfilerSettings::enable_debug_groups`] is true, a debug group will be pushed on the encoder or pass.
+    #[track_caller]
+    #[inline]
+    pub fn profile<'a, Recorder: ProfilerCommandRecorder, T>(
+        &'a self,
+        label: impl Into<Cow<'static, str>>,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+        f: impl FnOnce(&mut Scope<'a, Recorder>) -> T,
+    ) -> T {
+        let mut scope = self.scope(label, encoder_or_pass, device);
+        f(&mut scope)
+    }
+
+    /// Runs the passed closure on the encoder or pass within a new profiler scope
+    /// and returns the closure's result along with the id of the scope.
+    ///
+    /// Unlike [`GpuProfiler::profile`], the closure receives the encoder or pass itself instead of a [`Scope`],
+    /// so existing functions recording into it can be measured as-is.
+    /// The scope's timing is available once its frame is processed,
+    /// the returned id identifies it via [`GpuTimerQueryResult::id`].
+    /// The id is `None` if the scope doesn't show up in the results,
+    /// e.g. because the profiler is disabled or the scope was filtered out.
+    ///
+    /// Otherwise identical to [`GpuProfiler::profile`].
+    #[track_caller]
+    #[inline]
+    pub fn measure<Recorder: ProfilerCommandRecorder, T>(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+        f: impl FnOnce(&mut Recorder) -> T,
+    ) -> (T, Option<QueryId>) {
+        let mut scope = self.scope(label, encoder_or_pass, device);
+        let id = scope.scope.as_ref().and_then(GpuProfilerQuery::reported_id);
+        (f(&mut scope), id)
+    }
+
+    /// Starts a new auto-closing profiler scope with a label given by [`format_args!`].
+    ///
+    /// The label is only formatted if it is actually needed, i.e. if neither timer queries nor debug groups
+    /// are enabled, no formatting takes place.
+    /// Otherwise identical to [`GpuProfiler::scope`].
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn scope_fmt<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: std::fmt::Arguments<'_>,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder> {
+        let scope = self.begin_query_fmt(label, encoder_or_pass, device);
+        Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    /// Starts a new auto-closing profiler scope with a label that is computed by `label` only if needed.
+    ///
+    /// Like for [`GpuProfiler::scope_fmt`], the label isn't created if neither timer queries nor debug groups
+    /// are enabled, in particular not while the profiler is disabled.
+    /// Otherwise identical to [`GpuProfiler::scope`].
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn scope_lazy<'a, Recorder: ProfilerCommandRecorder, L: Into<Cow<'static, str>>>(
+        &'a self,
+        label: impl FnOnce() -> L,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder> {
+        let scope = self.begin_query_lazy(label, encoder_or_pass, device);
+        Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    /// Registers a scope that is opened in every frame, e.g. a pass of a render graph with fixed structure.
+    ///
+    /// The returned id opens the scope via [`GpuProfiler::registered_scope`]/[`GpuProfiler::begin_registered_query`]
+    /// or nested within another scope via `registered_scope` of the scope types.
+    /// These skip all per-query label handling: the label is evaluated against [`GpuProfilerSettings::query_filter`]
+    /// only once here (and again on [`GpuProfiler::change_settings`]), namespaces pushed via
+    /// [`GpuProfiler::push_namespace`] are not applied and borrowed labels are never copied.
+    ///
+    /// `expected_depth` is the nesting depth the scope is expected to be opened at, 0 for scopes without parent.
+    /// In debug builds, opening the scope via `registered_scope` at a different depth panics.
+    ///
+    /// Registrations are kept for the lifetime of the profiler, so this is meant to be called once per scope
+    /// rather than every frame.
+    pub fn register_scope(
+        &mut self,
+        label: impl Into<Cow<'static, str>>,
+        expected_depth: u32,
+    ) -> RegisteredScopeId {
+        let label = label.into();
+        let id = RegisteredScopeId(self.registered_scopes.len() as u32);
+        self.registered_scopes.push(RegisteredScope {
+            is_profiled: Self::is_profiled_label(&self.settings, &label),
+            label,
+            expected_depth,
+        });
+        id
+    }
+
+    /// Label a scope was registered with, see [`GpuProfiler::register_scope`].
+    ///
+    /// Panics if the id wasn't returned by this profiler.
+    pub fn registered_scope_label(&self, id: RegisteredScopeId) -> &str {
+        &self.registered_scopes[id.0 as usize].label
+    }
+
+    /// Like [`GpuProfiler::scope`], but opens a scope registered via [`GpuProfiler::register_scope`].
+    ///
+    /// Panics if the id wasn't returned by this profiler.
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn registered_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        id: RegisteredScopeId,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder> {
+        let scope = self.begin_registered_query(id, encoder_or_pass, device);
+        self.debug_check_registered_depth(id, &scope);
+        Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but opens a query for a scope registered via [`GpuProfiler::register_scope`].
+    ///
+    /// Unlike [`GpuProfiler::registered_scope`], this doesn't check the expected depth,
+    /// since the query's parent is only known once [`GpuProfilerQuery::with_parent`] is called.
+    /// Panics if the id wasn't returned by this profiler.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_registered_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        id: RegisteredScopeId,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let registered_scope = &self.registered_scopes[id.0 as usize];
+        let timer_query_device =
+            timestamp_write_supported(encoder_or_pass, device.features()).then_some(device);
+        let query = if !PROFILING_ENABLED {
+            self.inactive_query(false)
+        } else if self.enabled && !registered_scope.is_profiled {
+            // Still counts as open query, so that unbalanced begin/end calls are reported as usual.
+            self.num_open_queries.fetch_add(1, Ordering::Acquire);
+            self.inactive_query(true)
+        } else {
+            self.begin_profiled_query(|| registered_scope.label.clone(), timer_query_device)
+        };
+        self.begin_query_on_recorder(query, encoder_or_pass)
+    }
+
+    /// Starts a new profiler query on the given encoder or rendering/compute pass (if enabled).
+    ///
+    /// The returned query *must* be closed by calling [`GpuProfiler::end_query`] with the same encoder/pass,
+    /// even if timer queries are disabled.
+    /// To do this automatically, use [`GpuProfiler::scope`]/[`GpuProfiler::owning_scope`] instead.
+    ///
+    /// If an [`wgpu::CommandEncoder`] is passed but the [`wgpu::Device`]
+    /// does not support [`wgpu::Features::TIMESTAMP_QUERY`], no gpu timer will be queried and the scope will
+    /// show up in the final results without timing information.
+    /// If an [`wgpu::ComputePass`] or [`wgpu::RenderPass`] is passed but the [`wgpu::Device`]
+    /// does not support [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`], no timer queries will be allocated.
+    ///
+    /// If [`GpuProfilerSettings::enable_debug_groups`] is true, a debug group will be pushed on the encoder or pass.
+    ///
+    /// Labels can be either `&'static str`, which are never copied, or owned strings.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.begin_query_with_label_fn(|| label.into(), encoder_or_pass, device)
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but additionally attaches key/value pairs to the query.
+    ///
+    /// The pairs show up in [`GpuTimerQueryResult::metadata`] and are exported by e.g.
+    /// the chrome trace exporter as event arguments.
+    /// They are only copied if the query is going to be reported.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_with_args<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        args: &[(&str, String)],
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let mut query = self.begin_query(label, encoder_or_pass, device);
+        if query.is_reported {
+            query.metadata = args
+                .iter()
+                .map(|(key, value)| ((*key).to_owned(), value.clone()))
+                .collect();
+        }
+        query
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but additionally attaches a color hint for trace viewers to the query.
+    ///
+    /// Reported via [`GpuTimerQueryResult::color`], the chrome trace exporter uses it as the event's color.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_colored<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        color: TraceColor,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let mut query = self.begin_query(label, encoder_or_pass, device);
+        query.color = Some(color);
+        query
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but additionally tags the query with a category.
+    ///
+    /// Reported via [`GpuTimerQueryResult::category`]. Categories allow aggregating the time spent in related
+    /// scopes regardless of where they are nested, see [`crate::category_durations`] and
+    /// [`crate::GpuProfilerStatistics::category`].
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_with_category<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        category: &'static str,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let mut query = self.begin_query(label, encoder_or_pass, device);
+        query.category = Some(category);
+        query
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but fails instead of opening a query that can't be timed.
+    ///
+    /// If an error is returned, no query was opened and nothing needs to be closed.
+    /// Note that with [`QueryOverflowPolicy::Drop`], a query may still end up untimed if other threads open
+    /// queries concurrently.
+    #[track_caller]
+    pub fn try_begin_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> Result<GpuProfilerQuery, BeginQueryError> {
+        let timestamp_writes_supported =
+            timestamp_write_supported(encoder_or_pass, device.features());
+        self.check_timed_query(timestamp_writes_supported)?;
+        // The filter applies to the namespaced label, so the namespace is applied before checking it.
+        let label = self.namespaced_label(label.into());
+        self.check_query_filter(&label)?;
+        let query =
+            self.begin_namespaced_query(|| label, timestamp_writes_supported.then_some(device));
+        Ok(self.begin_query_on_recorder(query, encoder_or_pass))
+    }
+
+    /// Like [`GpuProfiler::begin_pass_query`], but lets the caller pick how the pass is timed and
+    /// fails instead of opening a query that can't be timed, see [`GpuProfiler::try_begin_query`].
+    ///
+    /// With [`PassTimestampMethod::InsidePass`], the query's start timestamp isn't written yet,
+    /// [`GpuProfiler::begin_query_on_recorder`] needs to be called with the pass right after beginning it.
+    #[track_caller]
+    pub(crate) fn try_begin_pass_query(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        method: PassTimestampMethod,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+    ) -> Result<GpuProfilerQuery, BeginQueryError> {
+        match method {
+            PassTimestampMethod::TimestampWrites => {
+                let timestamp_writes_supported =
+                    timestamp_write_supported(encoder, device.features());
+                self.check_timed_query(timestamp_writes_supported)?;
+                let label = self.namespaced_label(label.into());
+                self.check_query_filter(&label)?;
+                let query = self
+                    .begin_namespaced_query(|| label, timestamp_writes_supported.then_some(device));
+                Ok(Self::reserve_for_pass_timestamp_writes(query))
+            }
+            PassTimestampMethod::InsidePass => {
+                self.check_timed_query(
+                    device
+                        .features()
+                        .contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES),
+                )?;
+                let label = self.namespaced_label(label.into());
+                self.check_query_filter(&label)?;
+                Ok(self.begin_namespaced_query(|| label, Some(device)))
+            }
+        }
+    }
+
+    /// Checks whether a query can be timed, see [`GpuProfiler::try_begin_query`].
+    ///
+    /// The label is checked separately by [`GpuProfiler::check_query_filter`], since applying the namespace
+    /// to it isn't free.
+    fn check_timed_query(&self, timestamp_writes_supported: bool) -> Result<(), BeginQueryError> {
+        if !PROFILING_ENABLED || !self.enabled {
+            return Err(BeginQueryError::ProfilerDisabled);
+        }
+        if !self.settings.enable_timer_queries {
+            return Err(BeginQueryError::TimerQueriesDisabled);
+        }
+        if !timestamp_writes_supported {
+            return Err(BeginQueryError::TimestampWritesNotSupported);
+        }
+        if self.settings.query_overflow_policy == QueryOverflowPolicy::Drop
+            && self.num_requested_timer_queries.load(Ordering::Relaxed)
+                >= self.settings.max_num_queries_per_frame
+        {
+            return Err(BeginQueryError::MaxNumQueriesPerFrameExceeded);
+        }
+        Ok(())
+    }
+
+    /// Fails with [`BeginQueryError::FilteredOut`] if the namespaced label is rejected by
+    /// [`GpuProfilerSettings::query_filter`].
+    fn check_query_filter(&self, label: &str) -> Result<(), BeginQueryError> {
+        if let Some(filter) = &self.settings.query_filter {
+            if !filter.is_profiled(label) {
+                return Err(BeginQueryError::FilteredOut);
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a new profiler query with a label given by [`format_args!`].
+    ///
+    /// The label is only formatted if it is actually needed, i.e. if neither timer queries nor debug groups
+    /// are enabled, no formatting takes place.
+    /// Otherwise identical to [`GpuProfiler::begin_query`].
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_fmt<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: std::fmt::Arguments<'_>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.begin_query_with_label_fn(|| std::fmt::format(label).into(), encoder_or_pass, device)
+    }
+
+    /// Starts a new profiler query with a label that is computed by `label` only if needed.
+    ///
+    /// See [`GpuProfiler::scope_lazy`], otherwise identical to [`GpuProfiler::begin_query`].
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_lazy<Recorder: ProfilerCommandRecorder, L: Into<Cow<'static, str>>>(
+        &self,
+        label: impl FnOnce() -> L,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.begin_query_with_label_fn(|| label().into(), encoder_or_pass, device)
+    }
+
+    /// Starts a new profiler query to be used for render/compute pass timestamp writes.
+    ///
+    /// The returned query *must* be closed by calling [`GpuProfiler::end_query`], even if timer queries are disabled.
+    /// To do this automatically, use [`Scope::scoped_render_pass`]/[`Scope::scoped_compute_pass`] instead.
+    ///
+    /// Call [`GpuProfilerQuery::render_pass_timestamp_writes`] or [`GpuProfilerQuery::compute_pass_timestamp_writes`]
+    /// to acquire the corresponding `wgpu::RenderPassTimestampWrites`/`wgpu::ComputePassTimestampWrites` object.
+    ///
+    /// If the [`wgpu::Device`] does not support [`wgpu::Features::TIMESTAMP_QUERY`], no gpu timer will be reserved.
+    ///
+    /// Unlike [`GpuProfiler::begin_query`] this will not create a debug scope,
+    /// in order to not force passing of the same encoder/pass to [`GpuProfiler::end_query`].
+    /// (this is needed to relax resource tracking requirements a bit, making it easier to implement the automatic scopes)
+    pub fn begin_pass_query(
+        &self,
+        label: impl Into<Cow<'static, str>>,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let timer_query_device =
+            timestamp_write_supported(encoder, device.features()).then_some(device);
+        let query = self.begin_query_internal(|| label.into(), timer_query_device);
+        Self::reserve_for_pass_timestamp_writes(query)
+    }
+
+    /// Marks the timer query of a query as written by the timestamp writes of a pass.
+    fn reserve_for_pass_timestamp_writes(mut query: GpuProfilerQuery) -> GpuProfilerQuery {
+        if let Some(timer_query) = &mut query.timer_query_pair {
+            timer_query.usage_state = QueryPairUsageState::ReservedForPassTimestampWrites;
+        }
+        query
+    }
+
+    /// Ends passed query.
+    ///
+    /// If the passed query was opened with [`GpuProfiler::begin_query`], the passed encoder or pass must be the same
+    /// as when the query was opened.
+    ///
+    /// Nested queries (see [`GpuProfilerQuery::with_parent`]) need to be closed before their parent.
+    /// In debug builds with [`GpuProfilerSettings::track_open_queries`] enabled (the default), this panics if the parent
+    /// of the passed query was already closed.
+    pub fn end_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        encoder_or_pass: &mut Recorder,
+        mut query: GpuProfilerQuery,
+    ) {
+        if !PROFILING_ENABLED {
+            return;
+        }
+
+        if let Some(timer_query) = &mut query.timer_query_pair {
+            match timer_query.usage_state {
+                QueryPairUsageState::Reserved => {
+                    unreachable!("Query pair has been reserved but isn't used for anything!")
+                }
+                QueryPairUsageState::ReservedForPassTimestampWrites => {
+                    // No need to do a timestamp write, this is handled by wgpu.
+                }
+                QueryPairUsageState::OnlyStartWritten => {
+                    encoder_or_pass.write_timestamp(
+                        &timer_query.pool.query_set,
+                        timer_query.start_query_idx + 1,
+                    );
+                    timer_query.usage_state = QueryPairUsageState::BothStartAndEndWritten;
+                }
+                QueryPairUsageState::BothStartAndEndWritten => {
+                    unreachable!("Query pair has already been used!")
+                }
+            }
+        }
+
+        if query.pipeline_statistics_query.is_some() {
+            encoder_or_pass.end_pipeline_statistics_query();
+        }
+
+        #[cfg(feature = "tracy")]
+        if let Some(ref mut tracy_scope) = query.tracy_scope {
+            tracy_scope.end_zone();
+        }
+
+        if query.has_debug_group {
+            encoder_or_pass.pop_debug_group();
+        }
+
+        self.end_query_internal(query);
+    }
+
+    /// Starts a new auto-closing scope that only measures cpu time.
+    ///
+    /// Unlike [`GpuProfiler::scope`], this isn't tied to any encoder or pass, so it can wrap arbitrary cpu work
+    /// like [`wgpu::Queue::submit`] or presenting a surface.
+    /// The scope shows up in the results along with all other scopes of the frame,
+    /// with [`GpuTimerQueryResult::time`] being `None` and [`GpuTimerQueryResult::cpu_time`] set if
+    /// [`GpuProfilerSettings::enable_cpu_timings`] is enabled.
+    ///
+    /// To nest scopes inside this scope, call [`CpuScope::cpu_scope`] on the returned scope.
+    ///
+    /// Scope is automatically closed on drop.
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn cpu_scope(&self, label: impl Into<Cow<'static, str>>) -> CpuScope<'_> {
+        CpuScope {
+            profiler: self,
+            scope: Some(self.begin_cpu_query(label)),
+        }
+    }
+
+    /// Starts a new query that only measures cpu time, see [`GpuProfiler::cpu_scope`].
+    ///
+    /// The returned query *must* be closed by calling [`GpuProfiler::end_cpu_query`],
+    /// even if the profiler is disabled.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_cpu_query(&self, label: impl Into<Cow<'static, str>>) -> GpuProfilerQuery {
+        self.begin_query_internal(|| label.into(), None)
+    }
+
+    /// Ends a query opened with [`GpuProfiler::begin_cpu_query`].
+    ///
+    /// Queries opened on an encoder or pass have to be closed with [`GpuProfiler::end_query`] instead.
+    pub fn end_cpu_query(&self, query: GpuProfilerQuery) {
+        if !PROFILING_ENABLED {
+            return;
+        }
+
+        debug_assert!(
+            query.timer_query_pair.is_none()
+                && query.pipeline_statistics_query.is_none()
+                && !query.has_debug_group,
+            "Query {:?} was opened on an encoder or pass, close it with `GpuProfiler::end_query` instead.",
+            query.label
+        );
+        self.end_query_internal(query);
+    }
+
+    /// Puts query resolve commands in the encoder for all unresolved, pending queries of the active profiler frame.
+    ///
+    /// Note that you do *not* need to do this for every encoder, it is sufficient do do this once per frame as long
+    /// as you submit the corresponding command buffer after all others that may have opened queries in the same frame.
+    /// (It does not matter if the passed encoder itself has previously opened queries or not.)
     /// If you were to make this part of a command buffer that is enqueued before any other that has
     /// opened queries in the same profiling frame, no failure will occur but some timing results may be invalid.
     ///
@@ -339,31 +1316,284 @@ impl GpuProfiler {
             // but the concern is that if we don't acquire here, we may miss on other side prior effects of the query begin.
             let num_used_queries = query_pool.num_used_queries.load(Ordering::Acquire);
             let num_resolved_queries = query_pool.num_resolved_queries.load(Ordering::Acquire);
-
+
+            if num_resolved_queries == num_used_queries {
+                continue;
+            }
+
+            assert!(num_resolved_queries < num_used_queries);
+
+            encoder.resolve_query_set(
+                &query_pool.query_set,
+                num_resolved_queries..num_used_queries,
+                &query_pool.resolve_buffer,
+                (num_resolved_queries * wgpu::QUERY_SIZE) as u64,
+            );
+            query_pool
+                .num_resolved_queries
+                .store(num_used_queries, Ordering::Release);
+
+            encoder.copy_buffer_to_buffer(
+                &query_pool.resolve_buffer,
+                0,
+                &query_pool.read_buffer,
+                0,
+                (num_used_queries * wgpu::QUERY_SIZE) as u64,
+            );
+        }
+
+        Self::resolve_pipeline_statistics_queries(query_pools, encoder);
+    }
+
+    /// Like [`GpuProfiler::resolve_queries`], but resolves into a caller-provided buffer instead of the profiler's
+    /// internal resolve buffers.
+    ///
+    /// `buffer` needs [`wgpu::BufferUsages::QUERY_RESOLVE`] and [`wgpu::BufferUsages::COPY_SRC`] usage and
+    /// `offset` has to be a multiple of [`wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT`].
+    /// Use [`GpuProfiler::required_resolve_buffer_size`] to determine how much space is needed.
+    ///
+    /// Returns the range of bytes of `buffer` that were written to.
+    /// The written timestamps are copied from `buffer` to the profiler's read back buffers,
+    /// so results are still retrieved via [`GpuProfiler::process_finished_frame`].
+    /// The layout of the timestamps within the written range is unspecified.
+    /// Pipeline statistics (see [`GpuProfilerSettings::pipeline_statistics`]) are still resolved into
+    /// the profiler's internal buffers and don't take up space in `buffer`.
+    ///
+    /// Panics if `offset` is misaligned or if the buffer is too small.
+    pub fn resolve_queries_into(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+    ) -> std::ops::Range<wgpu::BufferAddress> {
+        assert!(
+            offset % wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT == 0,
+            "Offset needs to be a multiple of wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT"
+        );
+
+        let query_pools = self.active_frame.query_pools.get_mut();
+
+        let mut next_offset = offset;
+        let mut written_end = offset;
+        for query_pool in query_pools.used_pools.iter_mut() {
+            // See `resolve_queries` for why we acquire here.
+            let num_used_queries = query_pool.num_used_queries.load(Ordering::Acquire);
+            let num_resolved_queries = query_pool.num_resolved_queries.load(Ordering::Acquire);
+
+            if num_resolved_queries == num_used_queries {
+                continue;
+            }
+
+            assert!(num_resolved_queries < num_used_queries);
+
+            let num_bytes = ((num_used_queries - num_resolved_queries) * wgpu::QUERY_SIZE) as u64;
+            assert!(
+                next_offset + num_bytes <= buffer.size(),
+                "Buffer is too small to resolve all queries, see GpuProfiler::required_resolve_buffer_size"
+            );
+
+            encoder.resolve_query_set(
+                &query_pool.query_set,
+                num_resolved_queries..num_used_queries,
+                buffer,
+                next_offset,
+            );
+            query_pool
+                .num_resolved_queries
+                .store(num_used_queries, Ordering::Release);
+
+            encoder.copy_buffer_to_buffer(
+                buffer,
+                next_offset,
+                &query_pool.read_buffer,
+                (num_resolved_queries * wgpu::QUERY_SIZE) as u64,
+                num_bytes,
+            );
+
+            written_end = next_offset + num_bytes;
+            next_offset = wgpu::util::align_to(written_end, wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT);
+        }
+
+        Self::resolve_pipeline_statistics_queries(query_pools, encoder);
+
+        offset..written_end
+    }
+
+    /// Number of bytes a buffer passed to [`GpuProfiler::resolve_queries_into`] needs to have after the given offset
+    /// in order to resolve all currently unresolved queries.
+    pub fn required_resolve_buffer_size(&self) -> wgpu::BufferAddress {
+        let query_pools = self.active_frame.query_pools.read();
+
+        let mut size = 0;
+        for query_pool in query_pools.used_pools.iter() {
+            let num_used_queries = query_pool.num_used_queries.load(Ordering::Acquire);
+            let num_resolved_queries = query_pool.num_resolved_queries.load(Ordering::Acquire);
             if num_resolved_queries == num_used_queries {
                 continue;
             }
+            size = wgpu::util::align_to(size, wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT)
+                + ((num_used_queries - num_resolved_queries) * wgpu::QUERY_SIZE) as u64;
+        }
+        size
+    }
 
-            assert!(num_resolved_queries < num_used_queries);
+    /// Query sets used by the current frame, each along with the number of queries used in it so far.
+    ///
+    /// New timer queries are always allocated from the last query set, a new one is added once it is full.
+    /// Within a query set, every timed scope occupies two consecutive queries:
+    /// the start timestamp at an even index and the end timestamp at the following odd index.
+    /// Use [`GpuProfilerQuery::timestamp_query_index`] to find out which queries belong to a scope.
+    ///
+    /// The query sets are owned by the profiler and only belong to the current frame until [`GpuProfiler::end_frame`]
+    /// is called, after which they may be reused for later frames.
+    /// Any custom `resolve_query_set` for them therefore needs to be recorded before ending the frame.
+    /// The profiler doesn't track resolves it doesn't do itself, so [`GpuProfiler::resolve_queries`] still needs
+    /// to be called in order to retrieve results via [`GpuProfiler::process_finished_frame`].
+    pub fn active_query_sets(&mut self) -> impl Iterator<Item = (&wgpu::QuerySet, u32)> + '_ {
+        self.active_frame
+            .query_pools
+            .get_mut()
+            .used_pools
+            .iter()
+            .map(|query_pool| {
+                (
+                    &query_pool.query_set,
+                    query_pool.num_used_queries.load(Ordering::Acquire),
+                )
+            })
+    }
 
-            encoder.resolve_query_set(
-                &query_pool.query_set,
-                num_resolved_queries..num_used_queries,
-                &query_pool.resolve_buffer,
-                (num_resolved_queries * wgpu::QUERY_SIZE) as u64,
-            );
-            query_pool
-                .num_resolved_queries
-                .store(num_used_queries, Ordering::Release);
+    /// Gpu memory currently allocated for query sets and buffers.
+    ///
+    /// Reflects the current allocations across the active frame, all pending frames and pools kept for reuse,
+    /// which grow with [`GpuProfilerSettings::max_num_pending_frames`] and the number of queries per frame.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+
+        let active_query_pools = self.active_frame.query_pools.read();
+        for pool in active_query_pools
+            .used_pools
+            .iter()
+            .chain(active_query_pools.used_statistics_pools.iter())
+        {
+            report.add_pool(pool);
+        }
+        for pool in active_query_pools
+            .unused_pools
+            .iter()
+            .chain(active_query_pools.unused_statistics_pools.iter())
+            .chain(self.unused_pools.iter())
+        {
+            report.add_pool(pool);
+        }
+        for frame in &self.pending_frames {
+            for pool in frame
+                .query_pools
+                .iter()
+                .chain(frame.statistics_pools.iter())
+            {
+                report.add_pool(pool);
+            }
+        }
 
-            encoder.copy_buffer_to_buffer(
-                &query_pool.resolve_buffer,
-                0,
-                &query_pool.read_buffer,
-                0,
-                (num_used_queries * wgpu::QUERY_SIZE) as u64,
-            );
+        report
+    }
+
+    /// Measures the offset between gpu timestamps and the cpu's wall clock.
+    ///
+    /// Writes a single timestamp, submits it to `queue` and blocks until it has been read back.
+    /// The resulting [`ClockCalibration`] maps gpu times of results, e.g. [`GpuTimerQueryResult::time`],
+    /// to absolute time since the unix epoch. Calibrating again also estimates the drift between the clocks,
+    /// see [`ClockCalibration::drift`].
+    /// Accuracy is limited by the round trip to the gpu, see [`ClockCalibration::uncertainty_seconds`].
+    ///
+    /// Returns `None` if the device doesn't support [`wgpu::Features::TIMESTAMP_QUERY`]
+    /// or if the timestamp couldn't be read back, e.g. on WebGPU where polling can't block.
+    ///
+    /// Not available on `wasm32-unknown-unknown` since it relies on [`std::time::SystemTime`].
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn calibrate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<ClockCalibration> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let calibration =
+            ClockCalibration::measure(device, queue, self.clock_calibration.as_ref())?;
+        self.clock_calibration = Some(calibration);
+        Some(calibration)
+    }
+
+    /// Result of the most recent call to [`GpuProfiler::calibrate`].
+    pub fn clock_calibration(&self) -> Option<ClockCalibration> {
+        self.clock_calibration
+    }
+
+    /// Sets [`GpuProfilerSettings::track_name`], the name of the track this profiler's results are exported to.
+    ///
+    /// Applies to all frames that are processed afterwards, including ones that are already pending.
+    pub fn set_track_name(&mut self, track_name: impl Into<Cow<'static, str>>) {
+        self.settings.track_name = Some(track_name.into());
+    }
+
+    /// Prefixes the labels of all queries opened from now on with `namespace` and a `/`.
+    ///
+    /// Namespaces nest: pushing `"physics"` and then `"broadphase"` turns a query labeled `"draw"`
+    /// into `"physics/broadphase/draw"` in the results.
+    /// This allows independently instrumented subsystems to share a profiler without label collisions.
+    /// Labels are prefixed when queries are opened, so a namespace applies to all queries opened while it is pushed,
+    /// regardless of the thread they are opened on.
+    /// Debug groups and [`GpuProfilerSettings::query_filter`] see the prefixed labels as well.
+    ///
+    /// Pushing & popping is cheap, the prefix is only applied when a query's label is created.
+    pub fn push_namespace(&self, namespace: &str) {
+        let mut current = self.namespace.write();
+        let prefix_length = current.prefix.len();
+        current.prefix_lengths.push(prefix_length);
+        current.prefix.push_str(namespace);
+        current.prefix.push('/');
+    }
+
+    /// Removes the namespace most recently pushed with [`GpuProfiler::push_namespace`].
+    ///
+    /// Returns false if there was no namespace to remove.
+    pub fn pop_namespace(&self) -> bool {
+        let mut current = self.namespace.write();
+        let Some(prefix_length) = current.prefix_lengths.pop() else {
+            return false;
+        };
+        current.prefix.truncate(prefix_length);
+        true
+    }
+
+    /// Discards all queries of the current frame without ending it.
+    ///
+    /// Useful to bail out of a frame that failed midway, e.g. due to a validation error.
+    /// Unlike [`GpuProfiler::end_frame`], the frame doesn't become a pending frame and thus never produces results,
+    /// nor does it count towards [`GpuProfilerSettings::max_num_pending_frames`], [`GpuProfiler::frame_index`]
+    /// or [`GpuProfilerSettings::sample_interval`]. Pending frames are kept, unlike with [`GpuProfiler::clear`].
+    /// Query sets & buffers of the current frame are kept for reuse.
+    ///
+    /// All queries need to be closed before, i.e. all scopes need to be dropped, otherwise this fails
+    /// with [`EndFrameError::UnclosedQueries`] just like [`GpuProfiler::end_frame`] and the frame is left untouched.
+    pub fn abort_frame(&mut self) -> Result<(), EndFrameError> {
+        if !PROFILING_ENABLED {
+            return Ok(());
         }
+
+        self.check_no_open_queries()?;
+        self.reset_active_frame();
+        Ok(())
+    }
+
+    /// Returns a guard for the current frame that calls [`GpuProfiler::end_frame`] when dropped.
+    ///
+    /// This makes it impossible to forget ending the frame, e.g. on early returns.
+    /// Errors of [`GpuProfiler::end_frame`] are discarded on drop, use [`FrameGuard::finish`] to handle them.
+    pub fn begin_frame(&mut self) -> FrameGuard<'_> {
+        FrameGuard::new(self)
     }
 
     /// Marks the end of a frame.
@@ -371,18 +1601,28 @@ impl GpuProfiler {
     /// Needs to be called **after** submitting any encoder used in the current profiler frame.
     ///
     /// Fails if there are still open queries or unresolved queries.
+    /// If [`GpuProfilerSettings::track_open_queries`] is enabled, the error lists the labels of all open queries.
+    ///
+    /// Queries nested deeper than [`GpuProfilerSettings::max_query_depth`] don't fail the frame,
+    /// they are reported via [`GpuProfiler::num_queries_exceeding_max_depth`] afterwards.
     pub fn end_frame(&mut self) -> Result<(), EndFrameError> {
-        let num_open_queries = self.num_open_queries.load(Ordering::Acquire);
-        if num_open_queries != 0 {
-            return Err(EndFrameError::UnclosedQueries(num_open_queries));
+        if !PROFILING_ENABLED {
+            return Ok(());
         }
 
+        self.check_no_open_queries()?;
+
         let query_pools = self.active_frame.query_pools.get_mut();
 
         let mut new_pending_frame = PendingFrame {
             query_pools: std::mem::take(&mut query_pools.used_pools),
-            closed_query_by_parent_handle: HashMap::new(),
+            statistics_pools: std::mem::take(&mut query_pools.used_statistics_pools),
+            closed_query_by_parent_handle: HashMap::with_capacity(
+                self.settings.expected_queries_per_frame as usize,
+            ),
             mapped_buffers: Arc::new(AtomicU32::new(0)),
+            mapping_waker: Arc::new(Mutex::new(None)),
+            frame_index: self.frame_index,
         };
 
         for query in self.active_frame.closed_query_receiver.get_mut().try_iter() {
@@ -400,6 +1640,7 @@ impl GpuProfiler {
         let num_unresolved_queries = new_pending_frame
             .query_pools
             .iter()
+            .chain(new_pending_frame.statistics_pools.iter())
             .map(|pool| {
                 pool.num_used_queries.load(Ordering::Relaxed)
                     - pool.num_resolved_queries.load(Ordering::Relaxed)
@@ -422,7 +1663,12 @@ impl GpuProfiler {
             .min(QUERY_SET_MAX_QUERIES);
 
         // Make sure we don't overflow.
-        if self.pending_frames.len() == self.settings.max_num_pending_frames {
+        // There may be more than one frame too many if the limit was lowered since the last frame,
+        // which has to be handled even if this frame isn't enqueued.
+        // Frames skipped due to the sample interval don't contain any queries and aren't enqueued at all,
+        // so they don't need to make room for themselves.
+        let num_new_frames = self.is_sampled_frame as usize;
+        while self.pending_frames.len() + num_new_frames > self.settings.max_num_pending_frames {
             // Drop previous (!) frame.
             // Dropping the oldest frame could get us into an endless cycle where we're never able to complete
             // any pending frames as the ones closest to completion would be evicted.
@@ -433,14 +1679,20 @@ impl GpuProfiler {
                 // Mark the frame as dropped. We'll give back the query pools once the mapping is done.
                 // Any previously issued map_async call that haven't finished yet, will invoke their callback with mapping abort.
                 self.reset_and_cache_unused_query_pools(dropped_frame.query_pools);
+                self.reset_and_cache_unused_query_pools(dropped_frame.statistics_pools);
             }
         }
 
         // Map all buffers.
-        for pool in new_pending_frame.query_pools.iter_mut() {
+        for pool in new_pending_frame
+            .query_pools
+            .iter()
+            .chain(new_pending_frame.statistics_pools.iter())
+        {
             let mapped_buffers = new_pending_frame.mapped_buffers.clone();
+            let mapping_waker = new_pending_frame.mapping_waker.clone();
             pool.read_buffer
-                .slice(0..(pool.num_used_queries.load(Ordering::Relaxed) * wgpu::QUERY_SIZE) as u64)
+                .slice(0..pool.num_used_queries.load(Ordering::Relaxed) as u64 * pool.query_size())
                 .map_async(wgpu::MapMode::Read, move |mapping_result| {
                     // Mapping should not fail unless it was cancelled due to the frame being dropped.
                     match mapping_result {
@@ -452,20 +1704,115 @@ impl GpuProfiler {
                         }
                         Ok(()) => {
                             mapped_buffers.fetch_add(1, std::sync::atomic::Ordering::Release);
+                            if let Some(waker) = mapping_waker.lock().take() {
+                                waker.wake();
+                            }
                         }
                     }
                 });
         }
 
         // Enqueue
-        self.pending_frames.push(new_pending_frame);
+        if self.is_sampled_frame {
+            self.pending_frames.push(new_pending_frame);
+        } else {
+            self.reset_and_cache_unused_query_pools(new_pending_frame.query_pools);
+            self.reset_and_cache_unused_query_pools(new_pending_frame.statistics_pools);
+        }
         assert!(self.pending_frames.len() <= self.settings.max_num_pending_frames);
 
+        self.frame_index = self.frame_index.wrapping_add(1);
+        self.frames_since_sample = (self.frames_since_sample + 1) % self.settings.sample_interval;
+        self.is_sampled_frame = self.frames_since_sample == 0;
+        self.enabled = self.enabled_next_frame && self.is_sampled_frame && !self.needs_device;
+        *self.num_requested_timer_queries.get_mut() = 0;
+        self.num_dropped_queries_last_frame = std::mem::take(self.num_dropped_queries.get_mut());
+
+        self.num_queries_exceeding_max_depth_last_frame =
+            std::mem::take(self.num_queries_exceeding_max_depth.get_mut());
+        self.first_query_exceeding_max_depth_last_frame =
+            self.first_query_exceeding_max_depth.get_mut().take();
+
         Ok(())
     }
 
+    /// Number of queries in the last ended frame that didn't write any timestamps since they exceeded
+    /// [`GpuProfilerSettings::max_num_queries_per_frame`].
+    ///
+    /// Always zero unless [`GpuProfilerSettings::query_overflow_policy`] is [`QueryOverflowPolicy::Drop`].
+    pub fn num_dropped_queries(&self) -> u32 {
+        self.num_dropped_queries_last_frame
+    }
+
+    /// Number of queries in the last ended frame that were nested deeper than
+    /// [`GpuProfilerSettings::max_query_depth`].
+    ///
+    /// Such queries are still recorded, but usually indicate runaway recursion.
+    /// See [`GpuProfiler::first_query_exceeding_max_depth`] for the label of the first one.
+    pub fn num_queries_exceeding_max_depth(&self) -> u32 {
+        self.num_queries_exceeding_max_depth_last_frame
+    }
+
+    /// Label of the first query in the last ended frame that was closed while nested deeper than
+    /// [`GpuProfilerSettings::max_query_depth`], see [`GpuProfiler::num_queries_exceeding_max_depth`].
+    pub fn first_query_exceeding_max_depth(&self) -> Option<&str> {
+        self.first_query_exceeding_max_depth_last_frame.as_deref()
+    }
+
+    /// Labels of all currently open queries in the order they were opened.
+    ///
+    /// Since queries may be opened on several threads and encoders, this is not necessarily a single stack of
+    /// nested queries, but for queries recorded on a single thread the labels of nested queries follow their parent.
+    /// Only available if [`GpuProfilerSettings::track_open_queries`] is enabled, always empty otherwise.
+    pub fn open_query_labels(&self) -> Vec<Cow<'static, str>> {
+        let open_queries = self.open_queries.lock();
+        let mut open_queries = open_queries.iter().collect::<Vec<_>>();
+        open_queries.sort_by_key(|(handle, _)| **handle);
+        open_queries
+            .into_iter()
+            .map(|(_, label)| label.clone())
+            .collect()
+    }
+
+    /// Number of timer queries requested so far in the active frame, i.e. since the last call to [`GpuProfiler::end_frame`].
+    ///
+    /// Includes queries that were dropped due to [`GpuProfilerSettings::max_num_queries_per_frame`],
+    /// but not queries that write no timestamps in the first place (e.g. because the profiler is disabled).
+    /// Comparing this against [`GpuProfilerSettings::max_num_queries_per_frame`] allows throttling profiling
+    /// before queries get dropped.
+    pub fn num_queries_in_active_frame(&self) -> u32 {
+        self.num_requested_timer_queries.load(Ordering::Relaxed)
+    }
+
+    /// Number of frames that were ended via [`GpuProfiler::end_frame`] but not yet retrieved via
+    /// [`GpuProfiler::process_finished_frame`].
+    ///
+    /// Never exceeds [`GpuProfilerSettings::max_num_pending_frames`]: if another frame is ended while at the limit,
+    /// the newest pending frame is dropped.
+    /// Together with [`GpuProfiler::is_oldest_pending_frame_ready`], this tells how many frames the results lag behind.
+    pub fn num_pending_frames(&self) -> usize {
+        self.pending_frames.len()
+    }
+
+    /// Returns true if the next call to [`GpuProfiler::process_finished_frame`] will return a frame.
+    ///
+    /// This is the case once all query buffers of the oldest pending frame are mapped,
+    /// which requires the device to have finished the frame's work and to be polled.
+    pub fn is_oldest_pending_frame_ready(&self) -> bool {
+        self.pending_frames.first().is_some_and(|frame| {
+            frame.mapped_buffers.load(Ordering::Acquire) == frame.num_buffers()
+        })
+    }
+
     /// Checks if all timer queries for the oldest pending finished frame are done and returns that snapshot if any.
     ///
+    /// Never blocks: the profiler doesn't poll the device itself, query buffers only become available once the
+    /// application polls the device, e.g. via [`wgpu::Device::poll`] or by submitting to the queue.
+    /// With [`wgpu::Maintain::Poll`], results show up after a few frames of latency without ever stalling,
+    /// whereas [`wgpu::Maintain::Wait`] blocks until all submitted work is finished,
+    /// making results of the last ended frame available right away.
+    /// (Note that the tracy integration polls the device once during profiler creation to calibrate its clock.)
+    ///
     /// timestamp_period:
     ///    The timestamp period of the device. Pass the result of [`wgpu::Queue::get_timestamp_period()`].
     ///    Note that some implementations (Chrome as of writing) may converge to a timestamp period while the application is running,
@@ -474,32 +1821,318 @@ impl GpuProfiler {
         &mut self,
         timestamp_period: f32,
     ) -> Option<Vec<GpuTimerQueryResult>> {
-        let frame = self.pending_frames.first_mut()?;
-
-        // We only process if all mappings succeed.
-        if frame
-            .mapped_buffers
-            .load(std::sync::atomic::Ordering::Acquire)
-            != frame.query_pools.len() as u32
-        {
+        if !self.is_oldest_pending_frame_ready() {
             return None;
         }
 
-        let mut frame = self.pending_frames.remove(0);
-
-        let results = {
-            let timestamp_to_sec = timestamp_period as f64 / 1000.0 / 1000.0 / 1000.0;
+        let mut builder = self.result_tree_builder(ResultTreeBuilder::default());
+        self.process_finished_frame_with(timestamp_period, |depth, result| {
+            builder.push(depth, result)
+        });
+        Some(self.finish_result_tree(builder))
+    }
 
-            Self::process_timings_recursive(
-                timestamp_to_sec,
-                &mut frame.closed_query_by_parent_handle,
-                ROOT_QUERY_HANDLE,
+    /// Like [`GpuProfiler::process_finished_frame`], but waits for the oldest pending frame to become ready
+    /// instead of returning `None` if it isn't yet.
+    ///
+    /// The returned future completes once all query buffers of the oldest pending frame are mapped.
+    /// This is meant for environments that can't block on [`wgpu::Maintain::Wait`], like WebGPU in the browser,
+    /// where buffer mappings resolve on their own.
+    /// On native, mappings still only complete while the device is polled, so the application needs to keep polling
+    /// the device (or submitting work) for the future to make progress.
+    ///
+    /// Returns `None` right away if there is no pending frame.
+    pub async fn process_finished_frame_async(
+        &mut self,
+        timestamp_period: f32,
+    ) -> Option<Vec<GpuTimerQueryResult>> {
+        let (num_buffers, mapped_buffers, mapping_waker) = {
+            let frame = self.pending_frames.first()?;
+            (
+                frame.num_buffers(),
+                frame.mapped_buffers.clone(),
+                frame.mapping_waker.clone(),
             )
         };
 
-        self.reset_and_cache_unused_query_pools(frame.query_pools);
+        std::future::poll_fn(|cx| {
+            // Register before checking, so that a mapping finishing in between can't be missed.
+            *mapping_waker.lock() = Some(cx.waker().clone());
+            if mapped_buffers.load(Ordering::Acquire) == num_buffers {
+                std::task::Poll::Ready(())
+            } else {
+                std::task::Poll::Pending
+            }
+        })
+        .await;
+
+        self.process_finished_frame(timestamp_period)
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but additionally returns the index of the processed frame.
+    ///
+    /// The index is the value [`GpuProfiler::frame_index`] had while the frame was recorded,
+    /// which allows correlating results with other per-frame data of the application.
+    pub fn process_finished_frame_indexed(
+        &mut self,
+        timestamp_period: f32,
+    ) -> Option<(u64, Vec<GpuTimerQueryResult>)> {
+        if !self.is_oldest_pending_frame_ready() {
+            return None;
+        }
+        let frame_index = self.pending_frames[0].frame_index;
+        self.process_finished_frame(timestamp_period)
+            .map(|results| (frame_index, results))
+    }
+
+    /// Index of the frame that is currently being recorded.
+    ///
+    /// Starts at 0 and is incremented by every call to [`GpuProfiler::end_frame`] that ends the frame,
+    /// including frames that are skipped or dropped, wrapping around on overflow.
+    /// Stays at 0 if the `profiling-enabled` feature is disabled.
+    /// Discarding frames via [`GpuProfiler::clear`] or [`GpuProfiler::change_device`] doesn't reset it.
+    /// See [`GpuProfiler::process_finished_frame_indexed`] for retrieving the index along with a frame's results.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but returns the results as [`FinishedFrame`].
+    ///
+    /// [`FinishedFrame`] dereferences to the same list of results and additionally offers
+    /// convenience methods like [`FinishedFrame::total_duration`] and [`FinishedFrame::find`].
+    pub fn take_finished_frame(&mut self, timestamp_period: f32) -> Option<FinishedFrame> {
+        self.process_finished_frame(timestamp_period)
+            .map(FinishedFrame::from)
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but writes the results into the passed vector.
+    ///
+    /// All previous contents of `results` are dropped, but the allocations of `results` and all its nested
+    /// result vectors are reused. Passing in the results of the previous frame thus avoids most allocations
+    /// for frames of similar shape.
+    ///
+    /// Returns false if no frame was ready, in which case `results` is left untouched.
+    pub fn process_finished_frame_into(
+        &mut self,
+        timestamp_period: f32,
+        results: &mut Vec<GpuTimerQueryResult>,
+    ) -> bool {
+        if !self.is_oldest_pending_frame_ready() {
+            return false;
+        }
+
+        let mut builder =
+            self.result_tree_builder(ResultTreeBuilder::reusing(std::mem::take(results)));
+        self.process_finished_frame_with(timestamp_period, |depth, result| {
+            builder.push(depth, result)
+        });
+        *results = self.finish_result_tree(builder);
+        true
+    }
+
+    /// Hands back results that are no longer needed, so that their nested result vectors can be reused
+    /// by later frames.
+    ///
+    /// Does nothing but dropping the results unless [`GpuProfilerSettings::recycle_result_buffers`] is enabled.
+    pub fn recycle_results(&mut self, mut results: Vec<GpuTimerQueryResult>) {
+        if !self.settings.recycle_result_buffers {
+            return;
+        }
+        let mut unused_vecs = std::mem::take(&mut self.recycled_result_vecs);
+        ResultTreeBuilder::recycle(&mut results, &mut unused_vecs);
+        unused_vecs.push(results);
+        self.store_recycled_result_vecs(unused_vecs);
+    }
+
+    /// Configures a builder according to the settings, handing it all recycled vectors.
+    fn result_tree_builder(&mut self, builder: ResultTreeBuilder) -> ResultTreeBuilder {
+        let builder = builder.prune_empty_scopes(self.settings.prune_empty_scopes);
+        if self.settings.recycle_result_buffers {
+            builder.with_unused_vecs(std::mem::take(&mut self.recycled_result_vecs))
+        } else {
+            builder
+        }
+    }
+
+    /// Finishes the result tree, keeping the builder's unused vectors if recycling is enabled.
+    fn finish_result_tree(&mut self, builder: ResultTreeBuilder) -> Vec<GpuTimerQueryResult> {
+        if !self.settings.recycle_result_buffers {
+            return builder.finish();
+        }
+        let (results, unused_vecs) = builder.finish_with_unused_vecs();
+        self.store_recycled_result_vecs(unused_vecs);
+        results
+    }
+
+    fn store_recycled_result_vecs(&mut self, mut unused_vecs: Vec<Vec<GpuTimerQueryResult>>) {
+        unused_vecs.retain(|vec| {
+            vec.capacity() > 0 && vec.capacity() <= Self::MAX_RECYCLED_RESULT_VEC_CAPACITY
+        });
+        unused_vecs.truncate(Self::MAX_NUM_RECYCLED_RESULT_VECS);
+        self.recycled_result_vecs = unused_vecs;
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but passes every result to `f` instead of building a tree.
+    ///
+    /// Results are passed in depth-first order, i.e. every result is directly followed by its nested results,
+    /// along with their nesting depth. [`GpuTimerQueryResult::nested_queries`] is always empty.
+    /// This is useful to consume results with minimal overhead, e.g. by pushing them to a ring buffer.
+    ///
+    /// Returns false if no frame was ready, in which case `f` isn't called at all.
+    pub fn process_finished_frame_with(
+        &mut self,
+        timestamp_period: f32,
+        mut f: impl FnMut(usize, GpuTimerQueryResult),
+    ) -> bool {
+        if !self.is_oldest_pending_frame_ready() {
+            return false;
+        }
+
+        self.timestamp_period = Some(timestamp_period);
+        let timestamp_to_sec = timestamp_period as f64 / 1000.0 / 1000.0 / 1000.0;
+
+        let mut anomalies = std::mem::take(&mut self.timestamp_anomalies);
+        anomalies.clear();
+        let mut overlap_detector = self
+            .settings
+            .detect_overlapping_scopes
+            .then(OverlapDetector::default);
+        let wrap_policy = self.settings.timestamp_wrap_policy;
+        let mut frame_range: Option<(f64, f64)> = None;
+        self.process_oldest_frame(|raw_result| {
+            let (depth, result) =
+                result_from_raw(raw_result, timestamp_to_sec, wrap_policy, &mut anomalies);
+            if let Some(overlap_detector) = &mut overlap_detector {
+                overlap_detector.push(depth, &result);
+            }
+            if let Some(time) = &result.time {
+                frame_range = Some(match frame_range {
+                    Some((start, end)) => (start.min(time.start), end.max(time.end)),
+                    None => (time.start, time.end),
+                });
+            }
+            f(depth, result);
+        });
+        self.timestamp_anomalies = anomalies;
+        self.overlapping_scopes = overlap_detector
+            .map(|overlap_detector| overlap_detector.overlaps)
+            .unwrap_or_default();
+        self.last_frame_total = frame_range.map(|(start, end)| end - start);
+
+        true
+    }
+
+    /// Blocks until the device finished all submitted work, then processes the oldest pending frame.
+    ///
+    /// This is meant for synchronous readback, see [`GpuProfilerSettings::synchronous`]:
+    /// resolving all queries via [`GpuProfiler::resolve_queries`], submitting, calling [`GpuProfiler::end_frame`] and
+    /// then this method returns the results of the frame that was just ended, as long as no older frames are pending.
+    /// Stalls until the gpu is idle, so this is not suited for profiling interactive applications.
+    ///
+    /// Returns `None` only if there is no pending frame.
+    /// If [`GpuProfilerSettings::allow_device_poll`] is disabled, this doesn't poll and therefore doesn't block,
+    /// returning `None` as well if the oldest pending frame isn't ready yet.
+    pub fn process_finished_frame_blocking(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<Vec<GpuTimerQueryResult>> {
+        if self.pending_frames.is_empty() {
+            return None;
+        }
+        if self.settings.allow_device_poll {
+            device.poll(wgpu::Maintain::Wait);
+        }
+        self.process_finished_frame_with_queue(queue)
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but reports why no frame was returned.
+    ///
+    /// Never polls the device, regardless of [`GpuProfilerSettings::allow_device_poll`], and never blocks.
+    /// [`ProcessFrameError::NotReady`] means that the device either hasn't finished the oldest pending frame yet
+    /// or wasn't polled since: after [`wgpu::Device::poll`] with [`wgpu::Maintain::Wait`], all frames whose work
+    /// was submitted are ready, whereas [`wgpu::Maintain::Poll`] only picks up work that already finished.
+    pub fn try_process_finished_frame(
+        &mut self,
+        timestamp_period: f32,
+    ) -> Result<Vec<GpuTimerQueryResult>, ProcessFrameError> {
+        if self.pending_frames.is_empty() {
+            return Err(ProcessFrameError::NoPendingFrame);
+        }
+        self.process_finished_frame(timestamp_period)
+            .ok_or(ProcessFrameError::NotReady)
+    }
+
+    /// Total gpu time in seconds of the frame most recently returned by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// This is the time between the earliest start and the latest end of all scopes of the frame.
+    /// Returns `None` if no frame was processed yet or the frame didn't contain any timed scopes.
+    pub fn last_frame_total(&self) -> Option<f64> {
+        self.last_frame_total
+    }
+
+    /// Scopes with implausible timestamps in the frame most recently returned by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// Some drivers occasionally report timestamps that are both zero or where the end lies before the start.
+    /// The affected scopes have their [`GpuTimerQueryResult::time`] set to `None` and are listed here instead.
+    /// [`GpuProfiler::process_finished_frame_raw`] doesn't check for anomalies.
+    pub fn timestamp_anomalies(&self) -> &[TimestampAnomaly] {
+        &self.timestamp_anomalies
+    }
+
+    /// Overlapping sibling scopes in the frame most recently returned by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// Always empty unless [`GpuProfilerSettings::detect_overlapping_scopes`] is enabled, which it is in debug builds.
+    /// [`GpuProfiler::process_finished_frame_raw`] doesn't check for overlaps.
+    pub fn overlapping_scopes(&self) -> &[ScopeOverlap] {
+        &self.overlapping_scopes
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but returns a flat list of results with raw timestamps.
+    ///
+    /// Results are in depth-first order, i.e. every result is directly followed by its nested results,
+    /// and carry their nesting depth.
+    /// This avoids building the tree of [`GpuTimerQueryResult`], which may be useful if results are processed
+    /// into a custom data structure anyways.
+    /// Timestamps are not converted, use the device's timestamp period to convert them to nanoseconds.
+    pub fn process_finished_frame_raw(&mut self) -> Option<Vec<GpuTimerQueryRawResult>> {
+        let mut results = Vec::with_capacity(self.settings.expected_queries_per_frame as usize);
+        self.process_oldest_frame(|raw_result| results.push(raw_result))
+            .then_some(results)
+    }
+
+    /// Same as [`GpuProfiler::process_finished_frame`], but queries the timestamp period from the given queue.
+    ///
+    /// Since the period is retrieved anew on every call, this is the recommended way of processing frames.
+    pub fn process_finished_frame_with_queue(
+        &mut self,
+        queue: &wgpu::Queue,
+    ) -> Option<Vec<GpuTimerQueryResult>> {
+        self.process_finished_frame(queue.get_timestamp_period())
+    }
+
+    /// All resolved timestamps of the frame most recently processed by [`GpuProfiler::process_finished_frame`]
+    /// or any of its variants, as raw gpu ticks.
+    ///
+    /// Every timed scope occupies two consecutive queries: the start timestamp at an even index and the end timestamp
+    /// at the following odd index. [`GpuProfilerQuery::timestamp_query_index`] tells the index of a scope's start
+    /// timestamp within its query set.
+    /// If a frame used several query sets (see [`GpuProfiler::active_query_sets`]), their timestamps are concatenated
+    /// in order, i.e. indices of a query set are offset by the number of queries used in all previous query sets.
+    /// Multiply by [`GpuProfiler::timestamp_period`] to convert ticks to nanoseconds.
+    ///
+    /// Returns `None` if no frame was processed yet.
+    pub fn last_frame_raw_timestamps(&self) -> Option<&[u64]> {
+        self.last_frame_raw_timestamps.as_deref()
+    }
 
-        Some(results)
+    /// Timestamp period in nanoseconds per timestamp tick that was most recently used to convert timestamps.
+    ///
+    /// This is the period passed to the last successful [`GpuProfiler::process_finished_frame`] call,
+    /// or the one retrieved by [`GpuProfiler::new_with_queue`] if no frame was processed yet.
+    /// Returns `None` if neither happened.
+    pub fn timestamp_period(&self) -> Option<f32> {
+        self.timestamp_period
     }
 }
 
@@ -509,11 +2142,17 @@ impl GpuProfiler {
 
 const QUERY_SET_MAX_QUERIES: u32 = wgpu::QUERY_SET_MAX_QUERIES;
 
+/// If false, all profiling is disabled at compile time, see the `profiling-enabled` feature.
+const PROFILING_ENABLED: bool = cfg!(feature = "profiling-enabled");
+
 /// Returns true if a timestamp should be written to the encoder or pass.
 fn timestamp_write_supported<Recorder: ProfilerCommandRecorder>(
     encoder_or_pass: &mut Recorder,
     features: wgpu::Features,
 ) -> bool {
+    if !encoder_or_pass.supports_timestamp_writes() {
+        return false;
+    }
     let required_feature = if encoder_or_pass.is_pass() {
         wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES
     } else {
@@ -522,26 +2161,178 @@ fn timestamp_write_supported<Recorder: ProfilerCommandRecorder>(
     features.contains(required_feature)
 }
 
-impl GpuProfiler {
-    fn next_scope_tree_handle(&self) -> GpuTimerQueryTreeHandle {
-        // Relaxed is fine, we just want a number that nobody uses this frame already.
-        let mut handle = self.next_query_handle.fetch_add(1, Ordering::Relaxed);
+impl GpuProfiler {
+    #[track_caller]
+    #[must_use]
+    fn begin_query_with_label_fn<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl FnOnce() -> Cow<'static, str>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let timer_query_device =
+            timestamp_write_supported(encoder_or_pass, device.features()).then_some(device);
+        let query = self.begin_query_internal(label, timer_query_device);
+        self.begin_query_on_recorder(query, encoder_or_pass)
+    }
+
+    /// Writes the start timestamp of a freshly opened query and pushes its debug group on the recorder.
+    pub(crate) fn begin_query_on_recorder<Recorder: ProfilerCommandRecorder>(
+        &self,
+        mut query: GpuProfilerQuery,
+        encoder_or_pass: &mut Recorder,
+    ) -> GpuProfilerQuery {
+        if let Some(timer_query) = &mut query.timer_query_pair {
+            encoder_or_pass
+                .write_timestamp(&timer_query.pool.query_set, timer_query.start_query_idx);
+            timer_query.usage_state = QueryPairUsageState::OnlyStartWritten;
+        };
+
+        if self.enabled
+            && !query.is_skipped
+            && self.settings.enable_debug_groups
+            && encoder_or_pass.supports_debug_groups()
+        {
+            encoder_or_pass.push_debug_group(&query.label);
+            query.has_debug_group = true;
+        }
+        query
+    }
+
+    /// Seconds since the first cpu timing taken by this profiler.
+    fn cpu_time_now(&self) -> f64 {
+        if let Some(clock) = &self.settings.cpu_clock {
+            return clock.now_nanos() as f64 / 1000.0 / 1000.0 / 1000.0;
+        }
+        let origin = *self.cpu_time_origin.get_or_init(std::time::Instant::now);
+        origin.elapsed().as_secs_f64()
+    }
+
+    fn next_scope_tree_handle(&self) -> GpuTimerQueryTreeHandle {
+        // Relaxed is fine, we just want a number that nobody uses this frame already.
+        let mut handle = self.next_query_handle.fetch_add(1, Ordering::Relaxed);
+
+        // We don't ever expect to run out of handles during a single frame, but who knows how long the app runs.
+        while handle == ROOT_QUERY_HANDLE {
+            handle = self.next_query_handle.fetch_add(1, Ordering::Relaxed);
+        }
+
+        handle
+    }
+
+    /// Discards all pending frames and all queries of the active frame.
+    ///
+    /// Query pools of the discarded frames are kept for reuse.
+    fn reset_frames(&mut self) {
+        self.reset_active_frame();
+
+        let pending_frames = std::mem::take(&mut self.pending_frames);
+        for frame in pending_frames {
+            drop(frame.closed_query_by_parent_handle);
+            self.reset_and_cache_unused_query_pools(frame.query_pools);
+            self.reset_and_cache_unused_query_pools(frame.statistics_pools);
+        }
+    }
+
+    /// Discards all pending frames and all queries of the active frame and drops all query pools.
+    ///
+    /// Unlike [`GpuProfiler::reset_frames`], no pool is reset or unmapped,
+    /// since they belong to a device that is no longer used.
+    fn release_frames_and_query_pools(&mut self) {
+        self.discard_active_queries();
+
+        let query_pools = self.active_frame.query_pools.get_mut();
+        query_pools.used_pools.clear();
+        query_pools.unused_pools.clear();
+        query_pools.used_statistics_pools.clear();
+        query_pools.unused_statistics_pools.clear();
+        self.unused_pools.clear();
+        self.pending_frames.clear();
+    }
+
+    /// Discards all queries of the active frame, keeping its query pools for reuse.
+    fn reset_active_frame(&mut self) {
+        // Queries hold references to the pools, so they need to be dropped first.
+        self.discard_active_queries();
 
-        // We don't ever expect to run out of handles during a single frame, but who knows how long the app runs.
-        while handle == ROOT_QUERY_HANDLE {
-            handle = self.next_query_handle.fetch_add(1, Ordering::Relaxed);
+        // Pools that are still referenced by open queries can't be reused.
+        let active_query_pools = self.active_frame.query_pools.get_mut();
+        let active_pools = std::mem::take(&mut active_query_pools.used_pools)
+            .into_iter()
+            .chain(std::mem::take(
+                &mut active_query_pools.used_statistics_pools,
+            ))
+            .filter(|pool| Arc::strong_count(pool) == 1)
+            .collect();
+        // Read buffers of the active frame were never mapped, unmapping them would be a validation error.
+        self.cache_unused_query_pools(active_pools, false);
+    }
+
+    /// Fails with [`EndFrameError::UnclosedQueries`] if any query of the active frame is still open.
+    fn check_no_open_queries(&mut self) -> Result<(), EndFrameError> {
+        let num_open_queries = self.num_open_queries.load(Ordering::Acquire);
+        if num_open_queries == 0 {
+            return Ok(());
         }
 
-        handle
+        let mut open_queries = self.open_queries.get_mut().iter().collect::<Vec<_>>();
+        open_queries.sort_by_key(|(handle, _)| **handle);
+        Err(EndFrameError::UnclosedQueries {
+            num_open_queries,
+            open_query_labels: open_queries
+                .into_iter()
+                .map(|(_, label)| label.to_string())
+                .collect(),
+        })
+    }
+
+    /// Drops all closed queries of the active frame and resets all per-frame query counters.
+    fn discard_active_queries(&mut self) {
+        self.active_frame
+            .closed_query_receiver
+            .get_mut()
+            .try_iter()
+            .for_each(drop);
+        // Queries that are still open become stale and are ignored once closed.
+        self.query_generation = self.query_generation.wrapping_add(1);
+        *self.num_open_queries.get_mut() = 0;
+        self.open_queries.get_mut().clear();
+        *self.num_requested_timer_queries.get_mut() = 0;
+        *self.num_dropped_queries.get_mut() = 0;
+        *self.num_queries_exceeding_max_depth.get_mut() = 0;
+        *self.first_query_exceeding_max_depth.get_mut() = None;
+    }
+
+    fn reset_and_cache_unused_query_pools(&mut self, discarded_pools: Vec<Arc<QueryPool>>) {
+        self.cache_unused_query_pools(discarded_pools, true);
     }
 
-    fn reset_and_cache_unused_query_pools(&mut self, mut discarded_pools: Vec<Arc<QueryPool>>) {
+    /// Resets the given pools and keeps them for reuse if they're still suitable.
+    ///
+    /// `unmap_read_buffers` has to be false for pools whose read buffers were never passed to `map_async`.
+    fn cache_unused_query_pools(
+        &mut self,
+        mut discarded_pools: Vec<Arc<QueryPool>>,
+        unmap_read_buffers: bool,
+    ) {
         let capacity_threshold = self.size_for_new_query_pools / 2;
         for pool in discarded_pools.drain(..) {
             // If the pool is truly unused now, it's ref count should be 1!
             // If we use it anywhere else we have an implementation bug.
             let mut pool = Arc::into_inner(pool).expect("Pool still in use");
-            pool.reset();
+            pool.reset(unmap_read_buffers);
+
+            // Pipeline statistics pools are only kept if they still collect the statistics that are asked for.
+            if let Some(pipeline_statistics) = pool.pipeline_statistics {
+                if pipeline_statistics == self.settings.pipeline_statistics {
+                    self.active_frame
+                        .query_pools
+                        .get_mut()
+                        .unused_statistics_pools
+                        .push(pool);
+                }
+                continue;
+            }
 
             // If a pool was less than half of the size of the max frame, then we don't keep it.
             // This way we're going to need less pools in upcoming frames and thus have less overhead in the long run.
@@ -556,6 +2347,117 @@ impl GpuProfiler {
         }
     }
 
+    /// Starts collecting pipeline statistics for `query` on `pass` if [`GpuProfilerSettings::pipeline_statistics`]
+    /// is set and supported by the device.
+    ///
+    /// The statistics query is ended by [`GpuProfiler::end_query`] when closing `query` on the same pass.
+    pub(crate) fn begin_pipeline_statistics_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        query: &mut GpuProfilerQuery,
+        pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) {
+        if !PROFILING_ENABLED
+            || !query.is_reported
+            || self.settings.pipeline_statistics.is_empty()
+            || !device
+                .features()
+                .contains(wgpu::Features::PIPELINE_STATISTICS_QUERY)
+        {
+            return;
+        }
+
+        let statistics_query = self.reserve_pipeline_statistics_query(device);
+        pass.begin_pipeline_statistics_query(
+            &statistics_query.pool.query_set,
+            statistics_query.query_idx,
+        );
+        query.pipeline_statistics_query = Some(statistics_query);
+    }
+
+    // Unlike timer queries, statistics queries are only ever opened for passes,
+    // so we don't bother with trying to get away with a read lock first.
+    fn reserve_pipeline_statistics_query(
+        &self,
+        device: &wgpu::Device,
+    ) -> ReservedPipelineStatisticsQuery {
+        let mut query_pools = self.active_frame.query_pools.write();
+
+        let has_capacity = query_pools
+            .used_statistics_pools
+            .last()
+            .is_some_and(|pool| pool.num_used_queries.load(Ordering::Relaxed) < pool.capacity);
+        if !has_capacity {
+            // Cached pools may have been created before the collected statistics were changed.
+            let new_pool = match query_pools.unused_statistics_pools.pop() {
+                Some(reused_pool)
+                    if reused_pool.pipeline_statistics
+                        == Some(self.settings.pipeline_statistics) =>
+                {
+                    reused_pool
+                }
+                _ => QueryPool::new_pipeline_statistics(
+                    query_pools
+                        .used_statistics_pools
+                        .iter()
+                        .map(|pool| pool.capacity)
+                        .sum::<u32>()
+                        .max(QueryPool::MIN_STATISTICS_CAPACITY)
+                        .min(QUERY_SET_MAX_QUERIES),
+                    self.settings.pipeline_statistics,
+                    device,
+                ),
+            };
+            query_pools.used_statistics_pools.push(Arc::new(new_pool));
+        }
+
+        let pool = query_pools
+            .used_statistics_pools
+            .last()
+            .expect("Statistics pool with free capacity was just ensured")
+            .clone();
+        let query_idx = pool.num_used_queries.fetch_add(1, Ordering::Release);
+
+        ReservedPipelineStatisticsQuery { pool, query_idx }
+    }
+
+    /// Puts resolve commands for all unresolved pipeline statistics queries into the encoder.
+    fn resolve_pipeline_statistics_queries(
+        query_pools: &mut PendingFramePools,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        for query_pool in query_pools.used_statistics_pools.iter_mut() {
+            // See `resolve_queries` for why we acquire here.
+            let num_used_queries = query_pool.num_used_queries.load(Ordering::Acquire);
+            let num_resolved_queries = query_pool.num_resolved_queries.load(Ordering::Acquire);
+
+            if num_resolved_queries == num_used_queries {
+                continue;
+            }
+
+            assert!(num_resolved_queries < num_used_queries);
+
+            let query_size = query_pool.query_size();
+            encoder.resolve_query_set(
+                &query_pool.query_set,
+                num_resolved_queries..num_used_queries,
+                &query_pool.resolve_buffer,
+                num_resolved_queries as u64 * query_size,
+            );
+            query_pool
+                .num_resolved_queries
+                .store(num_used_queries, Ordering::Release);
+
+            encoder.copy_buffer_to_buffer(
+                &query_pool.resolve_buffer,
+                0,
+                &query_pool.read_buffer,
+                0,
+                num_used_queries as u64 * query_size,
+            );
+        }
+    }
+
     fn try_reserve_query_pair(pool: &Arc<QueryPool>) -> Option<ReservedTimerQueryPair> {
         let mut num_used_queries = pool.num_used_queries.load(Ordering::Relaxed);
 
@@ -648,120 +2550,381 @@ impl GpuProfiler {
         }
     }
 
+    /// Opens a query, applying the current namespace and [`GpuProfilerSettings::query_filter`].
+    ///
+    /// A timer query is only reserved if `timer_query_device` is passed,
+    /// i.e. the encoder or pass supports timestamp writes, and never for cpu queries.
     #[track_caller]
     #[must_use]
-    fn begin_query_internal<Recorder: ProfilerCommandRecorder>(
+    fn begin_query_internal(
         &self,
-        label: String,
-        encoder_or_pass: &mut Recorder,
-        device: &wgpu::Device,
+        label: impl FnOnce() -> Cow<'static, str>,
+        timer_query_device: Option<&wgpu::Device>,
+    ) -> GpuProfilerQuery {
+        self.begin_namespaced_query(|| self.namespaced_label(label()), timer_query_device)
+    }
+
+    /// Like [`GpuProfiler::begin_query_internal`], but for a label that already includes the namespace.
+    #[track_caller]
+    #[must_use]
+    fn begin_namespaced_query(
+        &self,
+        label: impl FnOnce() -> Cow<'static, str>,
+        timer_query_device: Option<&wgpu::Device>,
+    ) -> GpuProfilerQuery {
+        if !PROFILING_ENABLED {
+            return self.inactive_query(false);
+        }
+
+        if self.enabled {
+            if let Some(filter) = &self.settings.query_filter {
+                let label = label();
+                if filter.is_profiled(&label) {
+                    return self.begin_profiled_query(|| label, timer_query_device);
+                }
+
+                // Still counts as open query, so that unbalanced begin/end calls are reported as usual.
+                self.num_open_queries.fetch_add(1, Ordering::Acquire);
+                return self.inactive_query(true);
+            }
+        }
+
+        self.begin_profiled_query(label, timer_query_device)
+    }
+
+    /// A query that neither writes timestamps nor shows up in the results.
+    ///
+    /// Used if profiling is disabled at compile time or, with `is_skipped`, for queries rejected by
+    /// [`GpuProfilerSettings::query_filter`].
+    fn inactive_query(&self, is_skipped: bool) -> GpuProfilerQuery {
+        GpuProfilerQuery {
+            label: Cow::Borrowed(""),
+            pid: 0,
+            tid: std::thread::current().id(),
+            timer_query_pair: None,
+            pipeline_statistics_query: None,
+            handle: ROOT_QUERY_HANDLE,
+            parent_handle: ROOT_QUERY_HANDLE,
+            has_debug_group: false,
+            depth: 0,
+            generation: self.query_generation,
+            is_skipped,
+            is_reported: false,
+            cpu_time: None,
+            metadata: Vec::new(),
+            color: None,
+            category: None,
+            #[cfg(debug_assertions)]
+            parent_label: Cow::Borrowed(""),
+            #[cfg(feature = "tracy")]
+            tracy_scope: None,
+        }
+    }
+
+    /// Whether a query with the given label passes [`GpuProfilerSettings::query_filter`].
+    fn is_profiled_label(settings: &GpuProfilerSettings, label: &str) -> bool {
+        settings
+            .query_filter
+            .as_ref()
+            .map_or(true, |filter| filter.is_profiled(label))
+    }
+
+    /// Panics in debug builds if a reported query of a registered scope isn't at the scope's expected depth.
+    #[track_caller]
+    pub(crate) fn debug_check_registered_depth(
+        &self,
+        id: RegisteredScopeId,
+        query: &GpuProfilerQuery,
+    ) {
+        if cfg!(debug_assertions) && query.is_reported {
+            let registered_scope = &self.registered_scopes[id.0 as usize];
+            assert_eq!(
+                query.depth, registered_scope.expected_depth,
+                "Registered scope {:?} was opened at an unexpected depth",
+                registered_scope.label
+            );
+        }
+    }
+
+    /// Prefixes the label with all namespaces pushed via [`GpuProfiler::push_namespace`].
+    fn namespaced_label(&self, label: Cow<'static, str>) -> Cow<'static, str> {
+        let namespace = self.namespace.read();
+        if namespace.prefix.is_empty() {
+            label
+        } else {
+            Cow::Owned(format!("{}{label}", namespace.prefix))
+        }
+    }
+
+    /// Opens a query that passed [`GpuProfilerSettings::query_filter`].
+    #[track_caller]
+    #[must_use]
+    fn begin_profiled_query(
+        &self,
+        label: impl FnOnce() -> Cow<'static, str>,
+        timer_query_device: Option<&wgpu::Device>,
     ) -> GpuProfilerQuery {
         // Give opening/closing queries acquire/release semantics:
         // This way, we won't get any nasty surprises when observing zero open queries.
         self.num_open_queries.fetch_add(1, Ordering::Acquire);
 
-        let query = if self.settings.enable_timer_queries
-            && timestamp_write_supported(encoder_or_pass, device.features())
+        let query = if let Some(device) =
+            timer_query_device.filter(|_| self.enabled && self.settings.enable_timer_queries)
         {
-            Some(self.reserve_query_pair(device))
+            let num_previously_requested_timer_queries = self
+                .num_requested_timer_queries
+                .fetch_add(1, Ordering::Relaxed);
+            if self.settings.query_overflow_policy == QueryOverflowPolicy::Drop
+                && num_previously_requested_timer_queries >= self.settings.max_num_queries_per_frame
+            {
+                self.num_dropped_queries.fetch_add(1, Ordering::Relaxed);
+                None
+            } else {
+                Some(self.reserve_query_pair(device))
+            }
         } else {
             None
         };
 
-        let _tracy_scope = if self.settings.enable_timer_queries {
-            #[cfg(feature = "tracy")]
-            {
-                let location = std::panic::Location::caller();
-                self.tracy_context.as_ref().and_then(|c| {
-                    c.span_alloc(&label, "", location.file(), location.line())
-                        .ok()
-                })
-            }
-            #[cfg(not(feature = "tracy"))]
-            Option::<()>::None
+        // The label is used only by timer queries and debug groups, don't bother creating it otherwise.
+        let label = if self.enabled
+            && (self.settings.enable_timer_queries || self.settings.enable_debug_groups)
+        {
+            label()
         } else {
-            None
+            Cow::Borrowed("")
         };
 
+        // Tracy gpu spans are completed with the query's timestamps, pointless if there won't be any.
+        let _tracy_scope =
+            if self.enabled && self.settings.enable_timer_queries && timer_query_device.is_some() {
+                #[cfg(feature = "tracy")]
+                {
+                    let location = std::panic::Location::caller();
+                    self.tracy_context.as_ref().and_then(|c| {
+                        c.span_alloc(&label, "", location.file(), location.line())
+                            .ok()
+                    })
+                }
+                #[cfg(not(feature = "tracy"))]
+                Option::<()>::None
+            } else {
+                None
+            };
+
         let pid = if cfg!(target_arch = "wasm32") {
             0
         } else {
             std::process::id()
         };
 
+        let cpu_time = (self.enabled && self.settings.enable_cpu_timings).then(|| {
+            let now = self.cpu_time_now();
+            now..now
+        });
+
+        let handle = self.next_scope_tree_handle();
+        if self.enabled && self.settings.track_open_queries {
+            self.open_queries.lock().insert(handle, label.clone());
+        }
+
         GpuProfilerQuery {
             label,
             pid,
             tid: std::thread::current().id(),
             timer_query_pair: query,
-            handle: self.next_scope_tree_handle(),
+            pipeline_statistics_query: None,
+            handle,
             parent_handle: ROOT_QUERY_HANDLE,
             has_debug_group: false,
+            depth: 0,
+            generation: self.query_generation,
+            is_skipped: false,
+            is_reported: self.enabled && self.settings.enable_timer_queries,
+            cpu_time,
+            metadata: Vec::new(),
+            color: None,
+            category: None,
+            #[cfg(debug_assertions)]
+            parent_label: Cow::Borrowed(""),
             #[cfg(feature = "tracy")]
             tracy_scope: _tracy_scope,
         }
     }
 
+    /// Closes a query once the encoder or pass specific parts are taken care of.
+    fn end_query_internal(&self, mut query: GpuProfilerQuery) {
+        // Stale queries were already discarded along with their frame and no longer count as open.
+        // Dropping them releases their query pool, which isn't tracked by the profiler anymore.
+        if query.generation != self.query_generation {
+            return;
+        }
+
+        // Skipped queries were never tracked and share the handle of their parent.
+        if self.settings.track_open_queries && !query.is_skipped {
+            let mut open_queries = self.open_queries.lock();
+
+            // Closing a parent before its nested queries silently corrupts the result tree.
+            #[cfg(debug_assertions)]
+            if self.enabled
+                && query.parent_handle != ROOT_QUERY_HANDLE
+                && !open_queries.contains_key(&query.parent_handle)
+            {
+                panic!(
+                    "Query {:?} was closed after its parent query {:?}. Nested queries need to be closed before their parent.",
+                    query.label, query.parent_label
+                );
+            }
+
+            open_queries.remove(&query.handle);
+        }
+
+        if !query.is_skipped
+            && query.depth >= self.settings.max_query_depth
+            && self
+                .num_queries_exceeding_max_depth
+                .fetch_add(1, Ordering::Relaxed)
+                == 0
+        {
+            *self.first_query_exceeding_max_depth.lock() = Some(query.label.to_string());
+        }
+
+        if let Some(cpu_time) = &mut query.cpu_time {
+            cpu_time.end = self.cpu_time_now();
+        }
+
+        // Queries opened while the profiler or its timer queries were disabled don't produce any results,
+        // no need to keep them around.
+        if query.is_reported {
+            let send_result = self.active_frame.closed_query_sender.send(query);
+
+            // The only way we can fail sending the query is if the receiver has been dropped.
+            // Since it sits on `active_frame` as well, there's no way for this to happen!
+            debug_assert!(send_result.is_ok());
+        }
+
+        // Count queries even if we haven't processed this one, makes experiences more consistent
+        // if there's a lack of support for some queries.
+        self.num_open_queries.fetch_sub(1, Ordering::Release);
+    }
+
+    /// Passes all queries of the oldest pending frame depth-first to `emit` if the frame is ready.
+    fn process_oldest_frame(&mut self, mut emit: impl FnMut(GpuTimerQueryRawResult)) -> bool {
+        // We only process if all mappings succeed.
+        if !self.is_oldest_pending_frame_ready() {
+            return false;
+        }
+
+        let mut frame = self.pending_frames.remove(0);
+
+        let raw_timestamps = self.last_frame_raw_timestamps.get_or_insert_with(Vec::new);
+        raw_timestamps.clear();
+        for pool in &frame.query_pools {
+            let num_used_queries = pool.num_used_queries.load(Ordering::Relaxed);
+            if num_used_queries == 0 {
+                continue;
+            }
+            let buffer_slice = pool
+                .read_buffer
+                .slice(0..(num_used_queries * wgpu::QUERY_SIZE) as u64)
+                .get_mapped_range();
+            raw_timestamps.extend(decode_timestamps(&buffer_slice));
+        }
+
+        Self::process_timings_recursive(
+            &mut frame.closed_query_by_parent_handle,
+            ROOT_QUERY_HANDLE,
+            0,
+            self.settings.timeline,
+            &self.settings.track_name,
+            &mut emit,
+        );
+
+        self.reset_and_cache_unused_query_pools(frame.query_pools);
+        self.reset_and_cache_unused_query_pools(frame.statistics_pools);
+
+        true
+    }
+
+    /// Passes all queries with the given parent and their nested queries depth-first to `emit`.
     fn process_timings_recursive(
-        timestamp_to_sec: f64,
         closed_scope_by_parent_handle: &mut HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
         parent_handle: GpuTimerQueryTreeHandle,
-    ) -> Vec<GpuTimerQueryResult> {
+        depth: usize,
+        timeline: u32,
+        track_name: &Option<Cow<'static, str>>,
+        emit: &mut impl FnMut(GpuTimerQueryRawResult),
+    ) {
         let Some(queries_with_same_parent) = closed_scope_by_parent_handle.remove(&parent_handle)
         else {
-            return Vec::new();
+            return;
         };
 
-        queries_with_same_parent
-            .into_iter()
-            .filter_map(|mut scope| {
-                let Some(query) = scope.timer_query_pair.take() else {
-                    // Inactive queries don't have any results or nested queries with results.
-                    // Currently, we drop them from the results completely.
-                    // In the future we could still make them show up since they convey information like label & pid/tid.
-                    return None;
-                };
-
+        for mut scope in queries_with_same_parent {
+            // Untimed queries still show up since they convey information like label & pid/tid.
+            let timestamps = scope.timer_query_pair.take().map(|query| {
                 // Read timestamp from buffer.
                 // By design timestamps for start/end are consecutive.
                 let offset = (query.start_query_idx * wgpu::QUERY_SIZE) as u64;
-                let buffer_slice = &query
+                let buffer_slice = query
                     .pool
                     .read_buffer
                     .slice(offset..(offset + (wgpu::QUERY_SIZE * 2) as u64))
                     .get_mapped_range();
-                let start_raw = u64::from_le_bytes(
-                    buffer_slice[0..wgpu::QUERY_SIZE as usize]
-                        .try_into()
-                        .unwrap(),
-                );
-                let end_raw = u64::from_le_bytes(
-                    buffer_slice[wgpu::QUERY_SIZE as usize..(wgpu::QUERY_SIZE as usize) * 2]
-                        .try_into()
-                        .unwrap(),
-                );
+                let mut timestamps = decode_timestamps(&buffer_slice);
+                let start_raw = timestamps.next().unwrap();
+                let end_raw = timestamps.next().unwrap();
 
                 #[cfg(feature = "tracy")]
                 if let Some(tracy_scope) = scope.tracy_scope.take() {
                     tracy_scope.upload_timestamp(start_raw as i64, end_raw as i64);
                 }
 
-                let nested_queries = Self::process_timings_recursive(
-                    timestamp_to_sec,
-                    closed_scope_by_parent_handle,
-                    scope.handle,
-                );
+                start_raw..end_raw
+            });
 
-                Some(GpuTimerQueryResult {
-                    label: std::mem::take(&mut scope.label),
-                    time: (start_raw as f64 * timestamp_to_sec)
-                        ..(end_raw as f64 * timestamp_to_sec),
-                    nested_queries,
-                    pid: scope.pid,
-                    tid: scope.tid,
-                })
-            })
-            .collect::<Vec<_>>()
+            let pipeline_stats = scope.pipeline_statistics_query.take().map(|query| {
+                let query_size = query.pool.query_size();
+                let offset = query.query_idx as u64 * query_size;
+                let buffer_slice = query
+                    .pool
+                    .read_buffer
+                    .slice(offset..(offset + query_size))
+                    .get_mapped_range();
+                PipelineStats::from_resolved(
+                    query
+                        .pool
+                        .pipeline_statistics
+                        .unwrap_or(wgpu::PipelineStatisticsTypes::empty()),
+                    decode_timestamps(&buffer_slice),
+                )
+            });
+
+            emit(GpuTimerQueryRawResult {
+                label: std::mem::take(&mut scope.label),
+                id: scope.id(),
+                pid: scope.pid,
+                tid: scope.tid,
+                timestamps,
+                cpu_time: scope.cpu_time.take(),
+                depth,
+                timeline,
+                track_name: track_name.clone(),
+                metadata: std::mem::take(&mut scope.metadata),
+                color: scope.color,
+                category: scope.category.map(Cow::Borrowed),
+                pipeline_stats,
+            });
+
+            Self::process_timings_recursive(
+                closed_scope_by_parent_handle,
+                scope.handle,
+                depth + 1,
+                timeline,
+                track_name,
+                emit,
+            );
+        }
     }
 }
 
@@ -795,6 +2958,14 @@ pub struct ReservedTimerQueryPair {
     pub usage_state: QueryPairUsageState,
 }
 
+pub struct ReservedPipelineStatisticsQuery {
+    /// QueryPool of type [`wgpu::QueryType::PipelineStatistics`] the query is done on.
+    pub pool: Arc<QueryPool>,
+
+    /// Index of the query within the pool.
+    pub query_idx: u32,
+}
+
 /// A pool of queries, consisting of a single queryset & buffer for query results.
 #[derive(Debug)]
 pub struct QueryPool {
@@ -806,10 +2977,79 @@ pub struct QueryPool {
     capacity: u32,
     num_used_queries: AtomicU32,
     num_resolved_queries: AtomicU32,
+
+    /// Statistics collected by every query if this is a pool of pipeline statistics queries,
+    /// `None` for pools of timestamp queries.
+    pipeline_statistics: Option<wgpu::PipelineStatisticsTypes>,
+}
+
+/// Query set & buffers for timer queries that are allocated by the application, see [`GpuProfiler::new_with_query_pools`].
+pub struct QueryPoolResources {
+    /// Query set of type [`wgpu::QueryType::Timestamp`].
+    pub query_set: wgpu::QuerySet,
+
+    /// Number of queries in `query_set`, needs to be even since every timer query uses two timestamps.
+    pub capacity: u32,
+
+    /// Buffer the queries are resolved to, with [`wgpu::BufferUsages::QUERY_RESOLVE`] and [`wgpu::BufferUsages::COPY_SRC`] usage.
+    ///
+    /// Needs to be at least `capacity * wgpu::QUERY_SIZE` bytes large.
+    pub resolve_buffer: wgpu::Buffer,
+
+    /// Buffer the resolved queries are read back from, with [`wgpu::BufferUsages::COPY_DST`] and [`wgpu::BufferUsages::MAP_READ`] usage.
+    ///
+    /// Needs to be at least `capacity * wgpu::QUERY_SIZE` bytes large.
+    pub read_buffer: wgpu::Buffer,
 }
 
 impl QueryPool {
     const MIN_CAPACITY: u32 = 32;
+    const MIN_STATISTICS_CAPACITY: u32 = 8;
+
+    fn from_resources(resources: QueryPoolResources) -> Result<Self, QueryPoolError> {
+        let QueryPoolResources {
+            query_set,
+            capacity,
+            resolve_buffer,
+            read_buffer,
+        } = resources;
+
+        if capacity < 2 || capacity % 2 != 0 || capacity > QUERY_SET_MAX_QUERIES {
+            return Err(QueryPoolError::InvalidCapacity { capacity });
+        }
+        let required_size = (wgpu::QUERY_SIZE * capacity) as u64;
+        if resolve_buffer.size() < required_size
+            || !resolve_buffer
+                .usage()
+                .contains(wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC)
+        {
+            return Err(QueryPoolError::InvalidResolveBuffer { required_size });
+        }
+        if read_buffer.size() < required_size
+            || !read_buffer
+                .usage()
+                .contains(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
+        {
+            return Err(QueryPoolError::InvalidReadBuffer { required_size });
+        }
+
+        Ok(QueryPool {
+            query_set,
+            resolve_buffer,
+            read_buffer,
+            capacity,
+            num_used_queries: AtomicU32::new(0),
+            num_resolved_queries: AtomicU32::new(0),
+            pipeline_statistics: None,
+        })
+    }
+
+    /// Capacity of a pool that can hold the timestamps of the given number of timer queries.
+    fn capacity_for_queries(num_queries: u32) -> u32 {
+        num_queries
+            .saturating_mul(2)
+            .clamp(Self::MIN_CAPACITY, QUERY_SET_MAX_QUERIES)
+    }
 
     fn new(capacity: u32, device: &wgpu::Device) -> Self {
         QueryPool {
@@ -836,16 +3076,90 @@ impl QueryPool {
             capacity,
             num_used_queries: AtomicU32::new(0),
             num_resolved_queries: AtomicU32::new(0),
+            pipeline_statistics: None,
+        }
+    }
+
+    fn new_pipeline_statistics(
+        capacity: u32,
+        pipeline_statistics: wgpu::PipelineStatisticsTypes,
+        device: &wgpu::Device,
+    ) -> Self {
+        let size = capacity as u64 * Self::pipeline_statistics_query_size(pipeline_statistics);
+        QueryPool {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("GpuProfiler - Pipeline Statistics Query Set"),
+                ty: wgpu::QueryType::PipelineStatistics(pipeline_statistics),
+                count: capacity,
+            }),
+
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GpuProfiler - Pipeline Statistics Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+
+            read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GpuProfiler - Pipeline Statistics Read Buffer"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+
+            capacity,
+            num_used_queries: AtomicU32::new(0),
+            num_resolved_queries: AtomicU32::new(0),
+            pipeline_statistics: Some(pipeline_statistics),
         }
     }
 
-    fn reset(&mut self) {
+    /// Size of a single pipeline statistics query, which resolves to one value per collected statistic.
+    fn pipeline_statistics_query_size(pipeline_statistics: wgpu::PipelineStatisticsTypes) -> u64 {
+        pipeline_statistics.bits().count_ones() as u64 * wgpu::QUERY_SIZE as u64
+    }
+
+    /// Size of a single resolved query of this pool in bytes.
+    fn query_size(&self) -> u64 {
+        self.pipeline_statistics.map_or(
+            wgpu::QUERY_SIZE as u64,
+            Self::pipeline_statistics_query_size,
+        )
+    }
+
+    fn reset(&mut self, unmap_read_buffer: bool) {
         self.num_used_queries = AtomicU32::new(0);
         self.num_resolved_queries = AtomicU32::new(0);
-        self.read_buffer.unmap();
+        if unmap_read_buffer {
+            self.read_buffer.unmap();
+        }
     }
 }
 
+/// Handle of a scope registered via [`GpuProfiler::register_scope`].
+///
+/// Only valid for the profiler that returned it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisteredScopeId(u32);
+
+/// A scope registered via [`GpuProfiler::register_scope`].
+struct RegisteredScope {
+    label: Cow<'static, str>,
+    expected_depth: u32,
+    /// Cached result of [`GpuProfilerSettings::query_filter`] for the label.
+    is_profiled: bool,
+}
+
+/// Label prefix of all queries, see [`GpuProfiler::push_namespace`].
+#[derive(Default)]
+struct Namespace {
+    /// All pushed namespaces, each followed by a `/`.
+    prefix: String,
+
+    /// Length of `prefix` before each namespace was pushed.
+    prefix_lengths: Vec<usize>,
+}
+
 #[derive(Default)]
 struct PendingFramePools {
     /// List of all pools used in this frame.
@@ -854,6 +3168,12 @@ struct PendingFramePools {
 
     /// List of unused pools recycled from previous frames.
     unused_pools: Vec<QueryPool>,
+
+    /// List of all pipeline statistics pools used in this frame, the last one is used for new queries.
+    used_statistics_pools: Vec<Arc<QueryPool>>,
+
+    /// List of unused pipeline statistics pools recycled from previous frames.
+    unused_statistics_pools: Vec<QueryPool>,
 }
 
 /// Internal handle to building a tree of profiling queries.
@@ -878,8 +3198,22 @@ struct ActiveFrame {
 
 struct PendingFrame {
     query_pools: Vec<Arc<QueryPool>>,
+    statistics_pools: Vec<Arc<QueryPool>>,
     closed_query_by_parent_handle: HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
 
     /// Keeps track of the number of buffers in the query pool that have been mapped successfully.
     mapped_buffers: std::sync::Arc<std::sync::atomic::AtomicU32>,
+
+    /// Task waiting in [`GpuProfiler::process_finished_frame_async`] for this frame's buffers to be mapped.
+    mapping_waker: Arc<Mutex<Option<std::task::Waker>>>,
+
+    /// See [`GpuProfiler::frame_index`].
+    frame_index: u64,
+}
+
+impl PendingFrame {
+    /// Number of read buffers that need to be mapped before the frame can be processed.
+    fn num_buffers(&self) -> u32 {
+        (self.query_pools.len() + self.statistics_pools.len()) as u32
+    }
 }
@@ -1,14 +1,31 @@
-/// Trait for exposing the methods of `wgpu::CommandEncoder`, `wgpu::RenderPass` and `wgpu::ComputePass` that are used by the profiler.
+/// Trait for exposing the methods of `wgpu::CommandEncoder`, `wgpu::RenderPass`, `wgpu::ComputePass`
+/// and `wgpu::RenderBundleEncoder` that are used by the profiler.
 pub trait ProfilerCommandRecorder {
     /// Returns `true` if it's a pass or `false` if it's an encoder
     fn is_pass(&self) -> bool;
+    /// Returns `false` if timestamps can never be written to this recorder, independent of the device's features.
+    fn supports_timestamp_writes(&self) -> bool {
+        true
+    }
+    /// Returns `false` if debug groups can't be pushed on this recorder.
+    ///
+    /// If so, the profiler doesn't push debug groups even if [`crate::GpuProfilerSettings::enable_debug_groups`] is enabled.
+    fn supports_debug_groups(&self) -> bool {
+        true
+    }
     fn write_timestamp(&mut self, query_set: &wgpu::QuerySet, query_index: u32);
     fn push_debug_group(&mut self, label: &str);
     fn pop_debug_group(&mut self);
+    /// Starts collecting pipeline statistics, see [`crate::GpuProfilerSettings::pipeline_statistics`].
+    ///
+    /// Only render & compute passes support pipeline statistics, the default implementation does nothing.
+    fn begin_pipeline_statistics_query(&mut self, _query_set: &wgpu::QuerySet, _query_index: u32) {}
+    /// Stops collecting the pipeline statistics started with [`ProfilerCommandRecorder::begin_pipeline_statistics_query`].
+    fn end_pipeline_statistics_query(&mut self) {}
 }
 
 macro_rules! ImplProfilerCommandRecorder {
-    ($($name:ident $(< $lt:lifetime >)? : $pass:literal,)*) => {
+    ($($name:ident $(< $lt:lifetime >)? : $pass:literal $({ $($pass_methods:tt)* })?,)*) => {
         $(
             impl $(< $lt >)? ProfilerCommandRecorder for wgpu::$name $(< $lt >)? {
                 fn is_pass(&self) -> bool { $pass }
@@ -24,9 +41,57 @@ macro_rules! ImplProfilerCommandRecorder {
                 fn pop_debug_group(&mut self) {
                     self.pop_debug_group()
                 }
+
+                $($($pass_methods)*)?
             }
         )*
     };
 }
 
-ImplProfilerCommandRecorder!(CommandEncoder:false, RenderPass<'a>:true, ComputePass<'a>:true,);
+ImplProfilerCommandRecorder!(
+    CommandEncoder:false,
+    RenderPass<'a>:true {
+        fn begin_pipeline_statistics_query(&mut self, query_set: &wgpu::QuerySet, query_index: u32) {
+            self.begin_pipeline_statistics_query(query_set, query_index)
+        }
+
+        fn end_pipeline_statistics_query(&mut self) {
+            self.end_pipeline_statistics_query()
+        }
+    },
+    ComputePass<'a>:true {
+        fn begin_pipeline_statistics_query(&mut self, query_set: &wgpu::QuerySet, query_index: u32) {
+            self.begin_pipeline_statistics_query(query_set, query_index)
+        }
+
+        fn end_pipeline_statistics_query(&mut self) {
+            self.end_pipeline_statistics_query()
+        }
+    },
+);
+
+/// Render bundles can't write timestamps since they may be executed any number of times in different passes.
+/// Scopes on a [`wgpu::RenderBundleEncoder`] are therefore never timed and don't push debug groups,
+/// but can still be opened & closed, allowing generic code to profile arbitrary recorders.
+/// Note that such scopes are reported in the frame the bundle is recorded in, not in the frames it is executed in.
+/// To time executions of a bundle, use [`crate::GpuProfiler::execute_bundle`].
+impl<'a> ProfilerCommandRecorder for wgpu::RenderBundleEncoder<'a> {
+    fn is_pass(&self) -> bool {
+        true
+    }
+
+    fn supports_timestamp_writes(&self) -> bool {
+        false
+    }
+
+    fn supports_debug_groups(&self) -> bool {
+        // wgpu doesn't implement debug groups for render bundles yet.
+        false
+    }
+
+    fn write_timestamp(&mut self, _query_set: &wgpu::QuerySet, _query_index: u32) {}
+
+    fn push_debug_group(&mut self, _label: &str) {}
+
+    fn pop_debug_group(&mut self) {}
+}
@@ -1,30 +1,749 @@
-use std::{ops::Range, thread::ThreadId};
+use std::{borrow::Cow, ops::Range, thread::ThreadId};
 
-use crate::profiler::{
-    GpuTimerQueryTreeHandle, QueryPairUsageState, ReservedTimerQueryPair, ROOT_QUERY_HANDLE,
+use crate::{
+    profiler::{
+        GpuTimerQueryTreeHandle, QueryPairUsageState, ReservedPipelineStatisticsQuery,
+        ReservedTimerQueryPair, ROOT_QUERY_HANDLE,
+    },
+    MergeError, TimestampWrapPolicy,
 };
 
 /// The result of a gpu timer scope.
+///
+/// With the `serde` feature enabled, this implements `serde::Serialize` and `serde::Deserialize`.
+/// Note that [`GpuTimerQueryResult::tid`] is not serialized.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GpuTimerQueryResult {
     /// Label that was specified when opening the scope.
-    pub label: String,
+    pub label: Cow<'static, str>,
+
+    /// Id of the query this result belongs to, see [`GpuProfilerQuery::id`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub id: QueryId,
 
     /// The process id of the process that opened this scope.
     pub pid: u32,
 
     /// The thread id of the thread that opened this scope.
+    ///
+    /// Thread ids can't be serialized, deserialized results use the id of the deserializing thread instead.
+    #[cfg_attr(feature = "serde", serde(skip, default = "current_thread_id"))]
     pub tid: ThreadId,
 
     /// Time range of this scope in seconds.
     ///
     /// Meaning of absolute value is not defined.
-    pub time: Range<f64>,
+    /// `None` if no timestamps were written for this scope, e.g. because the device doesn't support the
+    /// required timer query features (see [`crate::GpuProfiler::features_supported`]).
+    pub time: Option<Range<f64>>,
+
+    /// Time range on the cpu between opening and closing this scope in seconds.
+    ///
+    /// Only recorded if [`crate::GpuProfilerSettings::enable_cpu_timings`] is enabled.
+    /// Measured with [`crate::GpuProfilerSettings::cpu_clock`] if set,
+    /// otherwise with [`std::time::Instant`] relative to the first cpu timing taken by the profiler.
+    /// Like for [`GpuTimerQueryResult::time`], only differences between values are meaningful,
+    /// in particular the cpu and gpu time ranges don't share a common origin.
+    pub cpu_time: Option<Range<f64>>,
+
+    /// Timeline this scope was recorded on, see [`crate::GpuProfilerSettings::timeline`].
+    ///
+    /// Nested scopes are always on the same timeline as their parent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timeline: u32,
+
+    /// Name of the track this scope is exported to, see [`crate::GpuProfilerSettings::track_name`].
+    ///
+    /// Exporters use a generic name like "GPU" if `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub track_name: Option<Cow<'static, str>>,
+
+    /// Key/value pairs that were attached when opening the scope via [`crate::GpuProfiler::begin_query_with_args`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: Vec<(String, String)>,
+
+    /// Color hint for trace viewers that was passed to [`crate::GpuProfiler::begin_query_colored`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub color: Option<TraceColor>,
+
+    /// Category that was passed to [`crate::GpuProfiler::begin_query_with_category`].
+    ///
+    /// Used to group the durations of related scopes, see [`crate::category_durations`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub category: Option<Cow<'static, str>>,
+
+    /// Pipeline statistics of this scope, see [`crate::GpuProfilerSettings::pipeline_statistics`].
+    ///
+    /// `None` if no statistics were collected for this scope.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pipeline_stats: Option<PipelineStats>,
 
     /// Scopes that were opened while this scope was open.
     pub nested_queries: Vec<GpuTimerQueryResult>,
 }
 
+/// A single result of a frame that was processed with [`crate::GpuProfiler::process_finished_frame_raw`].
+#[derive(Debug, Clone)]
+pub struct GpuTimerQueryRawResult {
+    /// Label that was specified when opening the scope.
+    pub label: Cow<'static, str>,
+
+    /// See [`GpuTimerQueryResult::id`].
+    pub id: QueryId,
+
+    /// The process id of the process that opened this scope.
+    pub pid: u32,
+
+    /// The thread id of the thread that opened this scope.
+    pub tid: ThreadId,
+
+    /// Start and end timestamp of this scope as resolved from the query set, in timestamp ticks.
+    ///
+    /// Multiply with [`wgpu::Queue::get_timestamp_period`] to get nanoseconds.
+    /// `None` if no timestamps were written for this scope, see [`GpuTimerQueryResult::time`].
+    pub timestamps: Option<Range<u64>>,
+
+    /// See [`GpuTimerQueryResult::cpu_time`].
+    pub cpu_time: Option<Range<f64>>,
+
+    /// Nesting depth of this scope, 0 for scopes without parent.
+    pub depth: usize,
+
+    /// See [`GpuTimerQueryResult::timeline`].
+    pub timeline: u32,
+
+    /// See [`GpuTimerQueryResult::track_name`].
+    pub track_name: Option<Cow<'static, str>>,
+
+    /// See [`GpuTimerQueryResult::metadata`].
+    pub metadata: Vec<(String, String)>,
+
+    /// See [`GpuTimerQueryResult::color`].
+    pub color: Option<TraceColor>,
+
+    /// See [`GpuTimerQueryResult::category`].
+    pub category: Option<Cow<'static, str>>,
+
+    /// See [`GpuTimerQueryResult::pipeline_stats`].
+    pub pipeline_stats: Option<PipelineStats>,
+}
+
+/// Description of a closed scope whose timestamps are stored in a list of resolved timestamps, see [`build_query_tree`].
+#[derive(Debug, Clone)]
+pub struct GpuTimerQueryRecord {
+    /// Label that was specified when opening the scope.
+    pub label: Cow<'static, str>,
+
+    /// See [`GpuTimerQueryResult::id`].
+    pub id: QueryId,
+
+    /// The process id of the process that opened this scope.
+    pub pid: u32,
+
+    /// The thread id of the thread that opened this scope.
+    pub tid: ThreadId,
+
+    /// Index of the start timestamp of this scope, the end timestamp directly follows it.
+    ///
+    /// `None` if no timestamps were written for this scope.
+    pub timestamp_index: Option<usize>,
+
+    /// Nesting depth of this scope, 0 for scopes without parent.
+    pub depth: usize,
+
+    /// See [`GpuTimerQueryResult::timeline`].
+    pub timeline: u32,
+
+    /// See [`GpuTimerQueryResult::track_name`].
+    pub track_name: Option<Cow<'static, str>>,
+}
+
+/// Identifies a query within a frame, see [`GpuProfilerQuery::id`].
+///
+/// Ids are unique among all queries of a frame, but are not stable across frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryId(pub u32);
+
+/// Color hint for displaying a scope in trace viewers, see [`GpuTimerQueryResult::color`].
+///
+/// The variants correspond to the reserved color names of the chrome trace format,
+/// which is why some of them are named after their original purpose rather than their color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceColor {
+    /// Green.
+    Good,
+    /// Orange.
+    Bad,
+    /// Red.
+    Terrible,
+    Yellow,
+    Olive,
+    Grey,
+    White,
+    Black,
+    /// Blue.
+    RailResponse,
+    /// Bright red.
+    RailAnimation,
+    /// Dark orange.
+    RailIdle,
+    /// Dark green.
+    RailLoad,
+    /// Light grey.
+    GenericWork,
+}
+
+impl TraceColor {
+    /// Name of this color in the chrome trace format, used for an event's `cname` field.
+    pub fn chrome_name(self) -> &'static str {
+        match self {
+            TraceColor::Good => "good",
+            TraceColor::Bad => "bad",
+            TraceColor::Terrible => "terrible",
+            TraceColor::Yellow => "yellow",
+            TraceColor::Olive => "olive",
+            TraceColor::Grey => "grey",
+            TraceColor::White => "white",
+            TraceColor::Black => "black",
+            TraceColor::RailResponse => "rail_response",
+            TraceColor::RailAnimation => "rail_animation",
+            TraceColor::RailIdle => "rail_idle",
+            TraceColor::RailLoad => "rail_load",
+            TraceColor::GenericWork => "generic_work",
+        }
+    }
+}
+
+/// Pipeline statistics of a pass scope, see [`crate::GpuProfilerSettings::pipeline_statistics`].
+///
+/// Only the statistics that were enabled are `Some`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineStats {
+    /// See [`wgpu::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS`].
+    pub vertex_shader_invocations: Option<u64>,
+
+    /// See [`wgpu::PipelineStatisticsTypes::CLIPPER_INVOCATIONS`].
+    pub clipper_invocations: Option<u64>,
+
+    /// See [`wgpu::PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT`].
+    pub clipper_primitives_out: Option<u64>,
+
+    /// See [`wgpu::PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS`].
+    pub fragment_shader_invocations: Option<u64>,
+
+    /// See [`wgpu::PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS`].
+    pub compute_shader_invocations: Option<u64>,
+}
+
+impl PipelineStats {
+    /// Assigns resolved values to the given statistics, which are resolved in the order of their bits.
+    pub(crate) fn from_resolved(
+        types: wgpu::PipelineStatisticsTypes,
+        mut values: impl Iterator<Item = u64>,
+    ) -> Self {
+        let mut next = |ty| {
+            if types.contains(ty) {
+                values.next()
+            } else {
+                None
+            }
+        };
+        Self {
+            vertex_shader_invocations: next(
+                wgpu::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS,
+            ),
+            clipper_invocations: next(wgpu::PipelineStatisticsTypes::CLIPPER_INVOCATIONS),
+            clipper_primitives_out: next(wgpu::PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT),
+            fragment_shader_invocations: next(
+                wgpu::PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS,
+            ),
+            compute_shader_invocations: next(
+                wgpu::PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS,
+            ),
+        }
+    }
+}
+
+/// A scope whose timestamps were implausible, see [`crate::GpuProfiler::timestamp_anomalies`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampAnomaly {
+    /// Label of the affected scope.
+    pub label: Cow<'static, str>,
+
+    /// Raw start timestamp as resolved from the query set.
+    pub start_timestamp: u64,
+
+    /// Raw end timestamp as resolved from the query set.
+    pub end_timestamp: u64,
+}
+
+/// Two sibling scopes on the same timeline whose time ranges overlap,
+/// see [`crate::GpuProfiler::overlapping_scopes`] and [`find_overlapping_scopes`].
+///
+/// Sibling scopes are recorded one after another, so overlaps usually hint at an instrumentation bug,
+/// like ending the wrong scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeOverlap {
+    /// Label of the earlier scope.
+    pub first_label: Cow<'static, str>,
+
+    /// Time range of the earlier scope in seconds.
+    pub first_time: Range<f64>,
+
+    /// Label of the later scope, which starts before the earlier one ends.
+    pub second_label: Cow<'static, str>,
+
+    /// Time range of the later scope in seconds.
+    pub second_time: Range<f64>,
+
+    /// Nesting depth of both scopes, 0 for scopes without parent.
+    pub depth: usize,
+
+    /// Timeline both scopes were recorded on.
+    pub timeline: u32,
+}
+
+impl GpuTimerQueryResult {
+    /// Exclusive time of this scope in seconds, i.e. its duration minus the durations of all directly nested scopes.
+    ///
+    /// Nested scopes without timing information are ignored.
+    /// Nested scopes are not expected to overlap, but if they do, the result is clamped to zero.
+    /// Returns `None` if this scope has no timing information.
+    pub fn self_time(&self) -> Option<f64> {
+        let time = self.time.as_ref()?;
+        let nested_duration = self
+            .nested_queries
+            .iter()
+            .filter_map(|nested| nested.time.as_ref())
+            .map(|time| time.end - time.start)
+            .sum::<f64>();
+        Some((time.end - time.start - nested_duration).max(0.0))
+    }
+
+    /// Iterates depth-first over this result and all its nested results.
+    ///
+    /// Yields the nesting depth relative to this result (starting at 0) along with every result.
+    pub fn iter_flattened(&self) -> FlattenedQueryResults<'_> {
+        flatten(std::slice::from_ref(self))
+    }
+}
+
+/// Iterates depth-first over all passed results and their nested results.
+///
+/// Yields the nesting depth (0 for the passed results) along with every result.
+pub fn flatten(results: &[GpuTimerQueryResult]) -> FlattenedQueryResults<'_> {
+    FlattenedQueryResults {
+        stack: vec![results.iter()],
+    }
+}
+
+/// Finds all sibling scopes on the same timeline whose time ranges overlap, see [`ScopeOverlap`].
+///
+/// Every scope is compared against the one of its earlier siblings that ends last.
+/// Scopes without timing information are ignored.
+pub fn find_overlapping_scopes(results: &[GpuTimerQueryResult]) -> Vec<ScopeOverlap> {
+    let mut detector = OverlapDetector::default();
+    for (depth, result) in flatten(results) {
+        detector.push(depth, result);
+    }
+    detector.overlaps
+}
+
+/// Detects overlapping sibling scopes in results that are pushed in depth-first order.
+#[derive(Default)]
+pub(crate) struct OverlapDetector {
+    /// `levels[d]` contains, per timeline, the sibling at depth `d` that ends last among the siblings seen so far.
+    levels: Vec<Vec<(u32, Cow<'static, str>, Range<f64>)>>,
+
+    pub(crate) overlaps: Vec<ScopeOverlap>,
+}
+
+impl OverlapDetector {
+    pub(crate) fn push(&mut self, depth: usize, result: &GpuTimerQueryResult) {
+        // Deeper levels belong to a previous sibling, their scopes aren't siblings of any later scope.
+        self.levels.resize_with(depth + 1, Vec::new);
+        let Some(time) = &result.time else {
+            return;
+        };
+
+        let siblings = &mut self.levels[depth];
+        match siblings
+            .iter_mut()
+            .find(|(timeline, _, _)| *timeline == result.timeline)
+        {
+            Some((_, label, latest)) => {
+                if time.start < latest.end {
+                    self.overlaps.push(ScopeOverlap {
+                        first_label: label.clone(),
+                        first_time: latest.clone(),
+                        second_label: result.label.clone(),
+                        second_time: time.clone(),
+                        depth,
+                        timeline: result.timeline,
+                    });
+                }
+                if time.end > latest.end {
+                    *label = result.label.clone();
+                    *latest = time.clone();
+                }
+            }
+            None => siblings.push((result.timeline, result.label.clone(), time.clone())),
+        }
+    }
+}
+
+/// Like [`flatten`], but additionally yields the path of every result.
+///
+/// The path consists of the labels of all parents and the result itself, separated by `/`.
+pub fn flatten_with_paths(
+    results: &[GpuTimerQueryResult],
+) -> impl Iterator<Item = (String, usize, &GpuTimerQueryResult)> {
+    let mut labels = Vec::new();
+    flatten(results).map(move |(depth, result)| {
+        labels.truncate(depth);
+        labels.push(&*result.label);
+        (labels.join("/"), depth, result)
+    })
+}
+
+/// Merges the results of several profilers into a single list of results.
+///
+/// The top-level results of all passed lists are concatenated and sorted by their start time,
+/// results without timing information are placed at the end.
+/// Nested results are kept as they are.
+///
+/// Since only timestamps of the same timeline are comparable (see [`crate::GpuProfilerSettings::timeline`]),
+/// this fails if the results don't all belong to the same timeline.
+pub fn merge(trees: Vec<Vec<GpuTimerQueryResult>>) -> Result<Vec<GpuTimerQueryResult>, MergeError> {
+    let mut merged: Vec<GpuTimerQueryResult> = trees.into_iter().flatten().collect();
+
+    if let Some(first) = merged.first() {
+        if let Some(other) = merged
+            .iter()
+            .find(|result| result.timeline != first.timeline)
+        {
+            return Err(MergeError::DifferentTimelines {
+                first: first.timeline,
+                second: other.timeline,
+            });
+        }
+    }
+
+    merged.sort_by(|a, b| match (&a.time, &b.time) {
+        (Some(a), Some(b)) => a.start.total_cmp(&b.start),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    Ok(merged)
+}
+
+/// Builds the tree of results from a depth-first list of raw results, as returned by
+/// [`crate::GpuProfiler::process_finished_frame_raw`].
+///
+/// `timestamp_period` is the number of nanoseconds per timestamp tick, see [`wgpu::Queue::get_timestamp_period`].
+/// Scopes with timestamps that are both zero or decreasing end up without timing information,
+/// see [`crate::GpuProfiler::timestamp_anomalies`].
+///
+/// This is what [`crate::GpuProfiler::process_finished_frame`] does internally, but doesn't require a device,
+/// which makes it possible to process raw results that were stored or created elsewhere.
+pub fn results_from_raw(
+    raw_results: Vec<GpuTimerQueryRawResult>,
+    timestamp_period: f32,
+) -> Vec<GpuTimerQueryResult> {
+    results_from_raw_with_wrap_policy(raw_results, timestamp_period, TimestampWrapPolicy::Skip)
+}
+
+/// Like [`results_from_raw`], but handles decreasing timestamps according to `wrap_policy`,
+/// see [`crate::GpuProfilerSettings::timestamp_wrap_policy`].
+pub fn results_from_raw_with_wrap_policy(
+    raw_results: Vec<GpuTimerQueryRawResult>,
+    timestamp_period: f32,
+    wrap_policy: TimestampWrapPolicy,
+) -> Vec<GpuTimerQueryResult> {
+    build_result_tree(
+        raw_results,
+        timestamp_period as f64 / 1000.0 / 1000.0 / 1000.0,
+        wrap_policy,
+        &mut Vec::new(),
+    )
+}
+
+/// Decodes timestamps that were resolved from a query set, e.g. into a buffer passed to
+/// [`crate::GpuProfiler::resolve_queries_into`].
+///
+/// Trailing bytes that don't form a complete timestamp are ignored.
+pub fn decode_timestamps(resolved: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    resolved
+        .chunks_exact(wgpu::QUERY_SIZE as usize)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Builds the tree of results from resolved timestamps and a depth-first list of records describing the scopes.
+///
+/// `timestamp_period` is the number of nanoseconds per timestamp tick, see [`wgpu::Queue::get_timestamp_period`].
+/// Records whose timestamps are out of range of `raw_timestamps` end up without timing information.
+/// Otherwise identical to [`results_from_raw`].
+pub fn build_query_tree(
+    raw_timestamps: &[u64],
+    records: &[GpuTimerQueryRecord],
+    timestamp_period: f32,
+) -> Vec<GpuTimerQueryResult> {
+    let raw_results = records
+        .iter()
+        .map(|record| GpuTimerQueryRawResult {
+            label: record.label.clone(),
+            id: record.id,
+            pid: record.pid,
+            tid: record.tid,
+            timestamps: record.timestamp_index.and_then(|index| {
+                let end_index = index.checked_add(1)?;
+                Some(*raw_timestamps.get(index)?..*raw_timestamps.get(end_index)?)
+            }),
+            cpu_time: None,
+            depth: record.depth,
+            timeline: record.timeline,
+            track_name: record.track_name.clone(),
+            metadata: Vec::new(),
+            color: None,
+            category: None,
+            pipeline_stats: None,
+        })
+        .collect();
+    results_from_raw(raw_results, timestamp_period)
+}
+
+/// Builds the tree of results from a depth-first list of raw results.
+///
+/// Timestamps that are both zero or decreasing are not converted but reported as anomalies.
+pub(crate) fn build_result_tree(
+    raw_results: Vec<GpuTimerQueryRawResult>,
+    timestamp_to_sec: f64,
+    wrap_policy: TimestampWrapPolicy,
+    anomalies: &mut Vec<TimestampAnomaly>,
+) -> Vec<GpuTimerQueryResult> {
+    let mut builder = ResultTreeBuilder::default();
+    for raw_result in raw_results {
+        let (depth, result) = result_from_raw(raw_result, timestamp_to_sec, wrap_policy, anomalies);
+        builder.push(depth, result);
+    }
+    builder.finish()
+}
+
+/// Converts a raw result into a result without nested results, returned along with its depth.
+///
+/// Timestamps that are both zero or decreasing are not converted but reported as anomalies,
+/// unless `wrap_policy` attributes the decrease to a wrap of the timestamp counter.
+pub(crate) fn result_from_raw(
+    raw_result: GpuTimerQueryRawResult,
+    timestamp_to_sec: f64,
+    wrap_policy: TimestampWrapPolicy,
+    anomalies: &mut Vec<TimestampAnomaly>,
+) -> (usize, GpuTimerQueryResult) {
+    let time = match raw_result.timestamps {
+        Some(timestamps)
+            if (timestamps.start == 0 && timestamps.end == 0)
+                || timestamps.end < timestamps.start =>
+        {
+            if let Some(duration) = unwrapped_duration(&timestamps, wrap_policy) {
+                let start = timestamps.start as f64 * timestamp_to_sec;
+                Some(start..start + duration as f64 * timestamp_to_sec)
+            } else {
+                anomalies.push(TimestampAnomaly {
+                    label: raw_result.label.clone(),
+                    start_timestamp: timestamps.start,
+                    end_timestamp: timestamps.end,
+                });
+                None
+            }
+        }
+        timestamps => timestamps.map(|timestamps| {
+            (timestamps.start as f64 * timestamp_to_sec)..(timestamps.end as f64 * timestamp_to_sec)
+        }),
+    };
+
+    (
+        raw_result.depth,
+        GpuTimerQueryResult {
+            label: raw_result.label,
+            id: raw_result.id,
+            pid: raw_result.pid,
+            tid: raw_result.tid,
+            time,
+            cpu_time: raw_result.cpu_time,
+            timeline: raw_result.timeline,
+            track_name: raw_result.track_name,
+            metadata: raw_result.metadata,
+            color: raw_result.color,
+            category: raw_result.category,
+            pipeline_stats: raw_result.pipeline_stats,
+            nested_queries: Vec::new(),
+        },
+    )
+}
+
+/// Duration in ticks of decreasing timestamps, if `wrap_policy` attributes the decrease to a wrap of the counter.
+fn unwrapped_duration(timestamps: &Range<u64>, wrap_policy: TimestampWrapPolicy) -> Option<u64> {
+    let TimestampWrapPolicy::Unwrap { valid_bits } = wrap_policy else {
+        return None;
+    };
+    if timestamps.end >= timestamps.start {
+        return None;
+    }
+    let mask = u64::MAX >> (64 - valid_bits.clamp(1, 64));
+    if timestamps.start > mask || timestamps.end > mask {
+        return None;
+    }
+    // After a wrap, the duration is small compared to the counter's range.
+    let duration = timestamps.end.wrapping_sub(timestamps.start) & mask;
+    (duration <= mask / 2).then_some(duration)
+}
+
+/// Builds the tree of results from results that are pushed in depth-first order.
+pub(crate) struct ResultTreeBuilder {
+    /// `levels[d]` contains the results at depth `d` that are nested in the last result of `levels[d - 1]`.
+    levels: Vec<Vec<GpuTimerQueryResult>>,
+
+    /// Empty vectors that are reused for nested results.
+    unused_vecs: Vec<Vec<GpuTimerQueryResult>>,
+
+    /// See [`crate::GpuProfilerSettings::prune_empty_scopes`].
+    prune_empty_scopes: bool,
+}
+
+impl Default for ResultTreeBuilder {
+    fn default() -> Self {
+        Self {
+            levels: vec![Vec::new()],
+            unused_vecs: Vec::new(),
+            prune_empty_scopes: false,
+        }
+    }
+}
+
+impl ResultTreeBuilder {
+    /// Creates a builder that reuses the allocations of a previously built tree.
+    pub(crate) fn reusing(mut results: Vec<GpuTimerQueryResult>) -> Self {
+        let mut unused_vecs = Vec::new();
+        Self::recycle(&mut results, &mut unused_vecs);
+        Self {
+            levels: vec![results],
+            unused_vecs,
+            prune_empty_scopes: false,
+        }
+    }
+
+    /// Adds empty vectors to reuse for nested results.
+    pub(crate) fn with_unused_vecs(mut self, unused_vecs: Vec<Vec<GpuTimerQueryResult>>) -> Self {
+        if self.unused_vecs.is_empty() {
+            self.unused_vecs = unused_vecs;
+        } else {
+            self.unused_vecs.extend(unused_vecs);
+        }
+        self
+    }
+
+    /// Drops childless scopes shorter than a nanosecond, see [`crate::GpuProfilerSettings::prune_empty_scopes`].
+    pub(crate) fn prune_empty_scopes(mut self, prune_empty_scopes: bool) -> Self {
+        self.prune_empty_scopes = prune_empty_scopes;
+        self
+    }
+
+    /// Clears `results`, moving all non-empty nested result vectors into `unused_vecs`.
+    pub(crate) fn recycle(
+        results: &mut Vec<GpuTimerQueryResult>,
+        unused_vecs: &mut Vec<Vec<GpuTimerQueryResult>>,
+    ) {
+        for mut result in results.drain(..) {
+            let mut nested_queries = std::mem::take(&mut result.nested_queries);
+            if nested_queries.capacity() > 0 {
+                Self::recycle(&mut nested_queries, unused_vecs);
+                unused_vecs.push(nested_queries);
+            }
+        }
+    }
+
+    pub(crate) fn push(&mut self, depth: usize, result: GpuTimerQueryResult) {
+        while self.levels.len() > depth + 1 {
+            self.close_level();
+        }
+        if self.levels.len() == depth {
+            self.levels.push(self.unused_vecs.pop().unwrap_or_default());
+        }
+        self.levels[depth].push(result);
+    }
+
+    pub(crate) fn finish(self) -> Vec<GpuTimerQueryResult> {
+        self.finish_with_unused_vecs().0
+    }
+
+    /// Like [`ResultTreeBuilder::finish`], but additionally returns all vectors that weren't reused.
+    pub(crate) fn finish_with_unused_vecs(
+        mut self,
+    ) -> (Vec<GpuTimerQueryResult>, Vec<Vec<GpuTimerQueryResult>>) {
+        while self.levels.len() > 1 {
+            self.close_level();
+        }
+        let mut results = self.levels.pop().unwrap();
+        self.prune(&mut results);
+        (results, self.unused_vecs)
+    }
+
+    fn close_level(&mut self) {
+        let mut nested_queries = self.levels.pop().unwrap();
+        // All scopes of a closed level already received their nested scopes, so they can be pruned now.
+        self.prune(&mut nested_queries);
+        self.levels
+            .last_mut()
+            .and_then(|parent_level| parent_level.last_mut())
+            .expect("results are expected to be in depth-first order")
+            .nested_queries = nested_queries;
+    }
+
+    fn prune(&self, results: &mut Vec<GpuTimerQueryResult>) {
+        if self.prune_empty_scopes {
+            results.retain(|result| {
+                !result.nested_queries.is_empty()
+                    || result
+                        .time
+                        .as_ref()
+                        .map_or(true, |time| time.end - time.start >= 1e-9)
+            });
+        }
+    }
+}
+
+/// Depth-first iterator over nested [`GpuTimerQueryResult`]s, see [`flatten`].
+pub struct FlattenedQueryResults<'a> {
+    stack: Vec<std::slice::Iter<'a, GpuTimerQueryResult>>,
+}
+
+impl<'a> Iterator for FlattenedQueryResults<'a> {
+    type Item = (usize, &'a GpuTimerQueryResult);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(results) = self.stack.last_mut() {
+            if let Some(result) = results.next() {
+                let depth = self.stack.len() - 1;
+                self.stack.push(result.nested_queries.iter());
+                return Some((depth, result));
+            }
+            self.stack.pop();
+        }
+        None
+    }
+}
+
+#[cfg(feature = "serde")]
+fn current_thread_id() -> ThreadId {
+    std::thread::current().id()
+}
+
 /// An inflight query for the profiler.
 ///
 /// If timer queries are enabled, this represents a reserved timer query pair on
@@ -35,7 +754,7 @@ pub struct GpuTimerQueryResult {
 pub struct GpuProfilerQuery {
     /// The label assigned to this query.
     /// Will be moved into [`GpuProfilerQuery::label`] once the query is fully processed.
-    pub label: String,
+    pub label: Cow<'static, str>,
 
     /// The process id of the process that opened this query.
     pub pid: u32,
@@ -46,6 +765,9 @@ pub struct GpuProfilerQuery {
     /// The actual query on a query pool if any (none if disabled for this type of query).
     pub(crate) timer_query_pair: Option<ReservedTimerQueryPair>,
 
+    /// Pipeline statistics query on the pass this query times, if any.
+    pub(crate) pipeline_statistics_query: Option<ReservedPipelineStatisticsQuery>,
+
     /// Handle which identifies this query, used for building the tree of queries.
     pub(crate) handle: GpuTimerQueryTreeHandle,
 
@@ -55,10 +777,66 @@ pub struct GpuProfilerQuery {
     /// Whether a debug group was opened for this scope.
     pub(crate) has_debug_group: bool,
 
+    /// Nesting depth of this query, 0 for queries without parent.
+    pub(crate) depth: u32,
+
+    /// Generation of the profiler's active frame when this query was opened.
+    ///
+    /// Discarding the queries of the active frame, e.g. via [`crate::GpuProfiler::clear`], starts a new generation.
+    /// Queries of an older generation are ignored when they are closed.
+    pub(crate) generation: u32,
+
+    /// Whether this query was rejected by [`crate::GpuProfilerSettings::query_filter`].
+    ///
+    /// Skipped queries pass on the handle of their parent, so that their nested queries are attributed to it.
+    pub(crate) is_skipped: bool,
+
+    /// Whether this query shows up in the results.
+    ///
+    /// Only queries that were opened while timer queries were enabled are reported.
+    pub(crate) is_reported: bool,
+
+    /// Cpu time range of this scope, see [`GpuTimerQueryResult::cpu_time`].
+    ///
+    /// Both start and end are set when opening the query, the end is updated upon closing.
+    pub(crate) cpu_time: Option<Range<f64>>,
+
+    /// Key/value pairs attached to this query, see [`GpuTimerQueryResult::metadata`].
+    pub(crate) metadata: Vec<(String, String)>,
+
+    /// See [`GpuTimerQueryResult::color`].
+    pub(crate) color: Option<TraceColor>,
+
+    /// See [`GpuTimerQueryResult::category`].
+    pub(crate) category: Option<&'static str>,
+
+    /// Label of the parent query, used to report queries that are closed after their parent.
+    #[cfg(debug_assertions)]
+    pub(crate) parent_label: Cow<'static, str>,
+
     #[cfg(feature = "tracy")]
     pub(crate) tracy_scope: Option<tracy_client::GpuSpan>,
 }
 
+impl std::fmt::Debug for GpuProfilerQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuProfilerQuery")
+            .field("label", &self.label)
+            .field("id", &self.id())
+            .field("depth", &self.depth)
+            .field(
+                "start_query_idx",
+                &self
+                    .timer_query_pair
+                    .as_ref()
+                    .map(|query| query.start_query_idx),
+            )
+            .field("has_debug_group", &self.has_debug_group)
+            .field("is_reported", &self.is_reported)
+            .finish_non_exhaustive()
+    }
+}
+
 impl GpuProfilerQuery {
     /// Use the reserved query for render pass timestamp writes if any.
     ///
@@ -92,11 +870,48 @@ impl GpuProfilerQuery {
         })
     }
 
+    /// Id of this query, reported via [`GpuTimerQueryResult::id`].
+    ///
+    /// This allows associating application data with the results of a query without storing it in the profiler.
+    ///
+    /// Queries that don't show up in the results, e.g. because the profiler is disabled or the query was
+    /// filtered out, don't have an id of their own and share it with their parent or with other such queries.
+    #[inline]
+    pub fn id(&self) -> QueryId {
+        QueryId(self.handle)
+    }
+
+    /// Id of this query if it shows up in the results, see [`GpuProfilerQuery::id`].
+    #[inline]
+    pub(crate) fn reported_id(&self) -> Option<QueryId> {
+        self.is_reported.then(|| self.id())
+    }
+
+    /// Query set and index of the start timestamp query reserved for this query, if any.
+    ///
+    /// The end timestamp query directly follows the start timestamp query.
+    /// See [`crate::GpuProfiler::active_query_sets`] for the layout of the query sets.
+    pub fn timestamp_query_index(&self) -> Option<(&wgpu::QuerySet, u32)> {
+        self.timer_query_pair
+            .as_ref()
+            .map(|query| (&query.pool.query_set, query.start_query_idx))
+    }
+
     /// Makes this scope a child of the passed scope.
     #[inline]
     pub fn with_parent(self, parent: Option<&GpuProfilerQuery>) -> Self {
+        let parent_handle = parent.map_or(ROOT_QUERY_HANDLE, |p| p.handle);
         Self {
-            parent_handle: parent.map_or(ROOT_QUERY_HANDLE, |p| p.handle),
+            handle: if self.is_skipped {
+                parent_handle
+            } else {
+                self.handle
+            },
+            parent_handle,
+            // Skipped queries carry the depth of their nested queries.
+            depth: parent.map_or(0, |p| if p.is_skipped { p.depth } else { p.depth + 1 }),
+            #[cfg(debug_assertions)]
+            parent_label: parent.map_or(Cow::Borrowed(""), |p| p.label.clone()),
             ..self
         }
     }
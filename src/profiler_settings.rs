@@ -1,6 +1,91 @@
+use std::{borrow::Cow, sync::Arc};
+
 use crate::SettingsError;
 
+/// Source of cpu timestamps, see [`GpuProfilerSettings::cpu_clock`].
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    /// Current time in nanoseconds.
+    ///
+    /// Needs to be monotonic, the meaning of the absolute value is up to the clock.
+    fn now_nanos(&self) -> u64;
+}
+
+/// Decides which queries are profiled, see [`GpuProfilerSettings::query_filter`].
+#[derive(Clone)]
+pub struct QueryFilter(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl QueryFilter {
+    /// Creates a filter from a function that returns true for all query labels that should be profiled.
+    pub fn new(filter: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    /// Returns true if a query with the given label should be profiled.
+    #[inline]
+    pub fn is_profiled(&self, label: &str) -> bool {
+        (self.0)(label)
+    }
+}
+
+impl std::fmt::Debug for QueryFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("QueryFilter")
+    }
+}
+
+/// Determines what happens if more queries than [`GpuProfilerSettings::max_num_queries_per_frame`]
+/// are opened within a single frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryOverflowPolicy {
+    /// The limit is ignored and additional query sets are allocated whenever needed.
+    ///
+    /// On every frame boundary, the size for newly allocated query sets is adjusted such that
+    /// upcoming frames of the same size can be handled by a single query set.
+    /// Note that wgpu limits the size of a single [`wgpu::QuerySet`] to [`wgpu::QUERY_SET_MAX_QUERIES`] (8192) queries,
+    /// i.e. 4096 timer queries since every query uses two timestamps.
+    #[default]
+    Grow,
+
+    /// Queries beyond the limit are still opened & closed, but don't write any timestamps.
+    ///
+    /// The number of affected queries is reported by [`crate::GpuProfiler::num_dropped_queries`].
+    Drop,
+}
+
+/// Determines how scopes whose end timestamp lies before their start timestamp are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampWrapPolicy {
+    /// Such scopes end up without timing information and are reported via
+    /// [`crate::GpuProfiler::timestamp_anomalies`].
+    #[default]
+    Skip,
+
+    /// Timestamps are treated as counters with the given number of valid bits that wrap around to zero.
+    ///
+    /// If the end timestamp lies before the start timestamp by more than half the counter's range,
+    /// the counter is assumed to have wrapped in between and the duration is computed modulo `2^valid_bits`.
+    /// Smaller decreases can't be explained by a wrap and are handled like with [`TimestampWrapPolicy::Skip`].
+    /// Note that only the scopes spanning the wrap are corrected, scopes that lie entirely after it
+    /// still appear to start before earlier scopes.
+    ///
+    /// `valid_bits` must be within `1..=64`.
+    Unwrap { valid_bits: u32 },
+}
+
 /// Settings passed on initialization of [`GpuProfiler`].
+///
+/// The [`Default`] settings are meant to work out of the box:
+/// * timer queries and debug groups are enabled
+/// * up to 3 frames are pending at a time, see [`GpuProfilerSettings::max_num_pending_frames`]
+/// * open queries are tracked in debug builds only
+/// * the number of queries per frame is unlimited, query sets grow as needed
+/// * cpu timings are disabled
+/// * no query filter, i.e. all queries are profiled
+/// * every frame is sampled
+/// * no pipeline statistics are collected
+///
+/// The timestamp period isn't part of the settings, it is passed when processing a frame
+/// or taken from the queue when using [`crate::GpuProfiler::new_default`].
 #[derive(Debug, Clone)]
 pub struct GpuProfilerSettings {
     /// Enables/disables gpu timer queries.
@@ -16,6 +101,8 @@ pub struct GpuProfilerSettings {
     /// This is useful for debugging with tools like RenderDoc.
     /// Debug markers will be emitted even if the device does not support timer queries or disables them via
     /// [`GpuProfilerSettings::enable_timer_queries`].
+    /// Recorders that don't support debug groups (see [`crate::ProfilerCommandRecorder::supports_debug_groups`])
+    /// are skipped.
     pub enable_debug_groups: bool,
 
     /// The profiler queues up to `max_num_pending_frames` "profiler-frames" at a time.
@@ -33,7 +120,160 @@ pub struct GpuProfilerSettings {
     /// Good values for `max_num_pending_frames` are 2-4 but may depend on your application workload
     /// and GPU-CPU syncing strategy.
     /// Must be greater than 0.
+    ///
+    /// For deterministic measurements, a value of 1 allows reading back every frame right after ending it,
+    /// accepting a stall until the device finished all work,
+    /// see [`GpuProfilerSettings::synchronous`] and [`GpuProfiler::process_finished_frame_blocking`].
+    /// Note that with a value of 1, ending a frame while the previous one wasn't processed yet drops the previous one.
     pub max_num_pending_frames: usize,
+
+    /// Enables/disables tracking of all currently open queries.
+    ///
+    /// If enabled, the labels of all queries that are still open are reported by [`crate::GpuProfiler::end_frame`]
+    /// via [`crate::EndFrameError::UnclosedQueries`].
+    /// It also enables the debug check for parent queries that are closed before their nested queries,
+    /// see [`crate::GpuProfiler::end_query`].
+    /// This comes at the cost of a lock shared by all threads and a label copy for every opened query,
+    /// which is why this is by default only enabled in debug builds.
+    /// Without tracking, unclosed queries are still detected, only their labels are missing.
+    pub track_open_queries: bool,
+
+    /// Maximum number of timer queries per frame.
+    ///
+    /// Only enforced if [`GpuProfilerSettings::query_overflow_policy`] is [`QueryOverflowPolicy::Drop`].
+    /// The default is the number of timer queries that fit into a single [`wgpu::QuerySet`].
+    pub max_num_queries_per_frame: u32,
+
+    /// What happens if more than [`GpuProfilerSettings::max_num_queries_per_frame`] timer queries are opened in a frame.
+    pub query_overflow_policy: QueryOverflowPolicy,
+
+    /// Enables/disables measuring the cpu time between opening and closing a query.
+    ///
+    /// Reported via [`crate::GpuTimerQueryResult::cpu_time`].
+    /// If disabled, no clock is read at all.
+    /// Note that [`std::time::Instant`], which is used unless [`GpuProfilerSettings::cpu_clock`] is set,
+    /// is not available on `wasm32-unknown-unknown`.
+    pub enable_cpu_timings: bool,
+
+    /// Clock used for cpu timings, see [`GpuProfilerSettings::enable_cpu_timings`].
+    ///
+    /// If set, [`crate::GpuTimerQueryResult::cpu_time`] is the clock's time converted to seconds,
+    /// which makes it possible to correlate cpu timings with other data measured by the same clock.
+    /// If `None`, [`std::time::Instant`] relative to the first cpu timing taken by the profiler is used.
+    pub cpu_clock: Option<Arc<dyn Clock>>,
+
+    /// Identifies the timeline all queries of this profiler are recorded on, reported via
+    /// [`crate::GpuTimerQueryResult::timeline`].
+    ///
+    /// Timestamps of different queues are generally not comparable and may even have different timestamp periods.
+    /// To profile work on several queues, use a separate profiler with a distinct timeline for every queue
+    /// and process its frames with the timestamp period of the respective queue.
+    /// Exporters place scopes of different timelines on separate tracks.
+    pub timeline: u32,
+
+    /// Name of the track this profiler's results are placed on by exporters, reported via
+    /// [`crate::GpuTimerQueryResult::track_name`].
+    ///
+    /// Useful to tell apart several profilers in a single trace, e.g. "Shadows" and "Post processing".
+    /// If `None`, exporters use a generic name like "GPU".
+    /// An owned name is cloned for every result, prefer `&'static str` where possible.
+    pub track_name: Option<Cow<'static, str>>,
+
+    /// Maximum number of nesting levels of queries, i.e. queries without parent are at level 1.
+    ///
+    /// Exceeding this limit usually indicates runaway recursion.
+    /// Deeper queries are still recorded, but are reported by
+    /// [`crate::GpuProfiler::num_queries_exceeding_max_depth`] once their frame ended.
+    /// Must be greater than 0.
+    pub max_query_depth: u32,
+
+    /// Number of queries that are expected to be opened per frame.
+    ///
+    /// Used to size query sets and internal bookkeeping upfront, avoiding reallocations during the first frames.
+    /// Opening more queries is fine and just triggers the usual growth.
+    pub expected_queries_per_frame: u32,
+
+    /// Decides by label which queries are profiled, all queries are profiled if `None`.
+    ///
+    /// Queries rejected by the filter are still opened & closed as usual, but write no timestamps,
+    /// open no debug groups and don't show up in the results.
+    /// Queries nested in a rejected query show up as nested queries of the rejected query's parent instead.
+    /// Note that a filter forces the label of every query to be created, even for [`crate::GpuProfiler::begin_query_fmt`].
+    pub query_filter: Option<QueryFilter>,
+
+    /// Only every `sample_interval`-th frame is profiled, all other frames are skipped entirely.
+    ///
+    /// Skipped frames behave as if the profiler was disabled (see [`crate::GpuProfiler::set_enabled`]):
+    /// no timestamps are written, no queries are allocated and no debug groups are pushed.
+    /// Unlike disabled frames, skipped frames don't become pending frames at all,
+    /// so [`crate::GpuProfiler::process_finished_frame`] only ever returns sampled frames.
+    /// This reduces the overhead of always-on profiling, e.g. for telemetry.
+    ///
+    /// The first frame is always sampled. Must be greater than 0, 1 samples every frame.
+    pub sample_interval: u32,
+
+    /// Pipeline statistics to collect for pass scopes, reported via [`crate::GpuTimerQueryResult::pipeline_stats`].
+    ///
+    /// Statistics are collected for passes opened via `scoped_render_pass`/`scoped_compute_pass` of the scope types,
+    /// in addition to their timing. Since pipeline statistics queries can't be nested, scopes within a pass don't
+    /// collect statistics.
+    /// Requires [`wgpu::Features::PIPELINE_STATISTICS_QUERY`], no statistics are collected if the device doesn't support it.
+    ///
+    /// Empty by default, i.e. no statistics are collected.
+    pub pipeline_statistics: wgpu::PipelineStatisticsTypes,
+
+    /// Drops timed scopes shorter than a nanosecond that have no nested scopes from the results.
+    ///
+    /// Such zero-width scopes are typically containers that didn't record any gpu work.
+    /// Pruning is applied bottom-up while building the result tree, so a container whose nested scopes
+    /// were all pruned is pruned as well. Scopes without timing information are kept.
+    /// Only affects results returned as tree, i.e. not [`crate::GpuProfiler::process_finished_frame_with`]
+    /// and [`crate::GpuProfiler::process_finished_frame_raw`].
+    ///
+    /// Disabled by default.
+    pub prune_empty_scopes: bool,
+
+    /// Checks processed frames for sibling scopes on the same timeline whose time ranges overlap.
+    ///
+    /// Overlaps usually hint at instrumentation bugs and are reported via [`crate::GpuProfiler::overlapping_scopes`].
+    /// The check needs to keep track of labels & time ranges of previous siblings while processing a frame,
+    /// which is why it's only enabled in debug builds by default.
+    /// [`crate::GpuProfiler::process_finished_frame_raw`] doesn't check for overlaps,
+    /// use [`crate::find_overlapping_scopes`] to check results at any time instead.
+    pub detect_overlapping_scopes: bool,
+
+    /// Keeps the vectors of nested results that are left over after building a frame's results for later frames.
+    ///
+    /// Nested results are stored in many small vectors, which are otherwise allocated anew for every frame.
+    /// With this enabled, vectors that aren't needed by [`crate::GpuProfiler::process_finished_frame_into`]
+    /// or that are handed back via [`crate::GpuProfiler::recycle_results`] are cleared and kept in a pool that
+    /// all variants of [`crate::GpuProfiler::process_finished_frame`] building a result tree draw from.
+    /// Combined with [`crate::GpuProfiler::process_finished_frame_into`], processing frames of similar shape
+    /// becomes nearly allocation free.
+    /// The pool is bounded, vectors exceeding [`crate::GpuProfiler::MAX_RECYCLED_RESULT_VEC_CAPACITY`] or
+    /// beyond [`crate::GpuProfiler::MAX_NUM_RECYCLED_RESULT_VECS`] vectors are dropped, so that an unusually large
+    /// frame doesn't retain memory forever.
+    ///
+    /// Disabled by default.
+    pub recycle_result_buffers: bool,
+
+    /// How to handle scopes whose end timestamp lies before their start timestamp.
+    ///
+    /// Over very long sessions or on some backends, timestamps may wrap around,
+    /// which would otherwise show up as a spurious spike. Defaults to [`TimestampWrapPolicy::Skip`].
+    pub timestamp_wrap_policy: TimestampWrapPolicy,
+
+    /// Whether the profiler may call [`wgpu::Device::poll`] on its own.
+    ///
+    /// The profiler never polls while recording or in [`crate::GpuProfiler::process_finished_frame`],
+    /// the only internal poll is the [`wgpu::Maintain::Wait`] of
+    /// [`crate::GpuProfiler::process_finished_frame_blocking`].
+    /// If disabled, that method doesn't wait either but only returns a frame whose results are already
+    /// available, just like [`crate::GpuProfiler::try_process_finished_frame`].
+    /// This leaves all polling, and thus all cpu stalls, to the application.
+    /// Explicit calibration via [`crate::GpuProfiler::calibrate`] always polls since it can't work otherwise.
+    /// Enabled by default.
+    pub allow_device_poll: bool,
 }
 
 impl Default for GpuProfilerSettings {
@@ -42,14 +282,196 @@ impl Default for GpuProfilerSettings {
             enable_timer_queries: true,
             enable_debug_groups: true,
             max_num_pending_frames: 3,
+            track_open_queries: cfg!(debug_assertions),
+            max_num_queries_per_frame: wgpu::QUERY_SET_MAX_QUERIES / 2,
+            query_overflow_policy: QueryOverflowPolicy::Grow,
+            enable_cpu_timings: false,
+            cpu_clock: None,
+            timeline: 0,
+            track_name: None,
+            max_query_depth: 128,
+            expected_queries_per_frame: 0,
+            query_filter: None,
+            sample_interval: 1,
+            pipeline_statistics: wgpu::PipelineStatisticsTypes::empty(),
+            prune_empty_scopes: false,
+            detect_overlapping_scopes: cfg!(debug_assertions),
+            recycle_result_buffers: false,
+            timestamp_wrap_policy: TimestampWrapPolicy::Skip,
+            allow_device_poll: true,
         }
     }
 }
 
 impl GpuProfilerSettings {
+    /// Default settings, but with a single pending frame for synchronous readback.
+    ///
+    /// Meant for benchmarks that end every frame with [`GpuProfiler::end_frame`] and immediately
+    /// retrieve its results with [`GpuProfiler::process_finished_frame_blocking`].
+    #[must_use]
+    pub fn synchronous() -> Self {
+        Self::default().with_max_num_pending_frames(1)
+    }
+
+    /// Sets [`GpuProfilerSettings::enable_timer_queries`].
+    #[must_use]
+    pub fn with_timer_queries(mut self, enable_timer_queries: bool) -> Self {
+        self.enable_timer_queries = enable_timer_queries;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::enable_debug_groups`].
+    #[must_use]
+    pub fn with_debug_groups(mut self, enable_debug_groups: bool) -> Self {
+        self.enable_debug_groups = enable_debug_groups;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::max_num_pending_frames`].
+    #[must_use]
+    pub fn with_max_num_pending_frames(mut self, max_num_pending_frames: usize) -> Self {
+        self.max_num_pending_frames = max_num_pending_frames;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::track_open_queries`].
+    #[must_use]
+    pub fn with_track_open_queries(mut self, track_open_queries: bool) -> Self {
+        self.track_open_queries = track_open_queries;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::max_num_queries_per_frame`] and [`GpuProfilerSettings::query_overflow_policy`].
+    #[must_use]
+    pub fn with_max_num_queries_per_frame(
+        mut self,
+        max_num_queries_per_frame: u32,
+        query_overflow_policy: QueryOverflowPolicy,
+    ) -> Self {
+        self.max_num_queries_per_frame = max_num_queries_per_frame;
+        self.query_overflow_policy = query_overflow_policy;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::enable_cpu_timings`].
+    #[must_use]
+    pub fn with_cpu_timings(mut self, enable_cpu_timings: bool) -> Self {
+        self.enable_cpu_timings = enable_cpu_timings;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::timeline`].
+    #[must_use]
+    pub fn with_timeline(mut self, timeline: u32) -> Self {
+        self.timeline = timeline;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::track_name`].
+    #[must_use]
+    pub fn with_track_name(mut self, track_name: impl Into<Cow<'static, str>>) -> Self {
+        self.track_name = Some(track_name.into());
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::max_query_depth`].
+    #[must_use]
+    pub fn with_max_query_depth(mut self, max_query_depth: u32) -> Self {
+        self.max_query_depth = max_query_depth;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::expected_queries_per_frame`].
+    #[must_use]
+    pub fn with_expected_queries_per_frame(mut self, expected_queries_per_frame: u32) -> Self {
+        self.expected_queries_per_frame = expected_queries_per_frame;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::cpu_clock`].
+    #[must_use]
+    pub fn with_cpu_clock(mut self, cpu_clock: impl Clock + 'static) -> Self {
+        self.cpu_clock = Some(Arc::new(cpu_clock));
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::query_filter`].
+    #[must_use]
+    pub fn with_query_filter(
+        mut self,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.query_filter = Some(QueryFilter::new(filter));
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::sample_interval`].
+    #[must_use]
+    pub fn with_sample_interval(mut self, sample_interval: u32) -> Self {
+        self.sample_interval = sample_interval;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::pipeline_statistics`].
+    #[must_use]
+    pub fn with_pipeline_statistics(
+        mut self,
+        pipeline_statistics: wgpu::PipelineStatisticsTypes,
+    ) -> Self {
+        self.pipeline_statistics = pipeline_statistics;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::prune_empty_scopes`].
+    #[must_use]
+    pub fn with_prune_empty_scopes(mut self, prune_empty_scopes: bool) -> Self {
+        self.prune_empty_scopes = prune_empty_scopes;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::detect_overlapping_scopes`].
+    #[must_use]
+    pub fn with_detect_overlapping_scopes(mut self, detect_overlapping_scopes: bool) -> Self {
+        self.detect_overlapping_scopes = detect_overlapping_scopes;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::recycle_result_buffers`].
+    #[must_use]
+    pub fn with_recycle_result_buffers(mut self, recycle_result_buffers: bool) -> Self {
+        self.recycle_result_buffers = recycle_result_buffers;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::timestamp_wrap_policy`].
+    #[must_use]
+    pub fn with_timestamp_wrap_policy(
+        mut self,
+        timestamp_wrap_policy: TimestampWrapPolicy,
+    ) -> Self {
+        self.timestamp_wrap_policy = timestamp_wrap_policy;
+        self
+    }
+
+    /// Sets [`GpuProfilerSettings::allow_device_poll`].
+    #[must_use]
+    pub fn with_allow_device_poll(mut self, allow_device_poll: bool) -> Self {
+        self.allow_device_poll = allow_device_poll;
+        self
+    }
+
     pub fn validate(&self) -> Result<(), SettingsError> {
         if self.max_num_pending_frames == 0 {
             Err(SettingsError::InvalidMaxNumPendingFrames)
+        } else if self.max_query_depth == 0 {
+            Err(SettingsError::InvalidMaxQueryDepth)
+        } else if self.sample_interval == 0 {
+            Err(SettingsError::InvalidSampleInterval)
+        } else if matches!(
+            self.timestamp_wrap_policy,
+            TimestampWrapPolicy::Unwrap { valid_bits } if !(1..=64).contains(&valid_bits)
+        ) {
+            Err(SettingsError::InvalidTimestampValidBits)
         } else {
             Ok(())
         }
@@ -0,0 +1,137 @@
+//! Reports profiling results to [puffin](https://github.com/EmbarkStudios/puffin).
+//!
+//! Requires the `puffin` feature.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::OnceLock,
+};
+
+use parking_lot::Mutex;
+
+use crate::GpuTimerQueryResult;
+
+/// Name of the puffin thread all gpu scopes are reported on.
+pub const GPU_THREAD_NAME: &str = "GPU";
+
+/// Reports a frame of profiling results to puffin's [`::puffin::GlobalProfiler`].
+///
+/// All scopes show up on a dedicated thread named [`GPU_THREAD_NAME`], keeping their nesting
+/// and using their labels as puffin scope names.
+/// Scopes of other [`GpuTimerQueryResult::timeline`]s than the default one are reported on separate threads.
+/// If [`GpuTimerQueryResult::track_name`] is set, it is used as thread name instead.
+///
+/// The gpu timeline is not synchronized with puffin's cpu clock.
+/// Therefore, the results are shifted such that the frame's last scope ends at the time of this call,
+/// separately for every timeline.
+/// Scopes without timing information are skipped, their nested scopes are still reported.
+/// Call this once for every frame returned by [`crate::GpuProfiler::process_finished_frame`],
+/// before calling [`::puffin::GlobalProfiler::new_frame`].
+pub fn emit_frame(profile_data: &[GpuTimerQueryResult]) {
+    let timelines = profile_data
+        .iter()
+        .map(|scope| scope.timeline)
+        .collect::<BTreeSet<_>>();
+
+    let mut global_profiler = ::puffin::GlobalProfiler::lock();
+    for timeline in timelines {
+        let scopes = profile_data
+            .iter()
+            .filter(|scope| scope.timeline == timeline)
+            .collect::<Vec<_>>();
+
+        let Some(frame_end) = scopes
+            .iter()
+            .flat_map(|scope| scope.iter_flattened())
+            .filter_map(|(_, scope)| scope.time.as_ref())
+            .map(|time| time.end)
+            .max_by(|a, b| a.total_cmp(b))
+        else {
+            continue;
+        };
+        let offset_ns = ::puffin::now_ns() - seconds_to_ns(frame_end);
+
+        let mut stream_info = ::puffin::StreamInfo {
+            stream: Default::default(),
+            num_scopes: 0,
+            depth: 0,
+            range_ns: (::puffin::NanoSecond::MAX, ::puffin::NanoSecond::MIN),
+        };
+        write_scopes_recursive(&mut stream_info, &mut global_profiler, scopes, offset_ns, 0);
+
+        let name = match scopes.iter().find_map(|scope| scope.track_name.as_deref()) {
+            Some(track_name) => track_name.to_owned(),
+            None if timeline == 0 => GPU_THREAD_NAME.to_owned(),
+            None => format!("{GPU_THREAD_NAME} timeline {timeline}"),
+        };
+        global_profiler.report_user_scopes(
+            ::puffin::ThreadInfo {
+                start_time_ns: None,
+                name,
+            },
+            &stream_info.as_stream_into_ref(),
+        );
+    }
+}
+
+fn seconds_to_ns(seconds: f64) -> ::puffin::NanoSecond {
+    (seconds * 1000.0 * 1000.0 * 1000.0) as ::puffin::NanoSecond
+}
+
+/// Returns the puffin scope id for the given label, registering a new scope if necessary.
+fn scope_id(global_profiler: &mut ::puffin::GlobalProfiler, label: &str) -> ::puffin::ScopeId {
+    // Puffin never forgets about registered scopes, so we need to make sure to register every label only once.
+    static SCOPE_IDS: OnceLock<Mutex<HashMap<String, ::puffin::ScopeId>>> = OnceLock::new();
+
+    *SCOPE_IDS
+        .get_or_init(Default::default)
+        .lock()
+        .entry(label.to_owned())
+        .or_insert_with(|| {
+            global_profiler
+                .register_user_scopes(&[::puffin::ScopeDetails::from_scope_name(label.to_owned())])
+                [0]
+        })
+}
+
+fn write_scopes_recursive<'a>(
+    stream_info: &mut ::puffin::StreamInfo,
+    global_profiler: &mut ::puffin::GlobalProfiler,
+    results: impl IntoIterator<Item = &'a GpuTimerQueryResult>,
+    offset_ns: ::puffin::NanoSecond,
+    depth: usize,
+) {
+    for scope in results {
+        let Some(time) = &scope.time else {
+            write_scopes_recursive(
+                stream_info,
+                global_profiler,
+                &scope.nested_queries,
+                offset_ns,
+                depth,
+            );
+            continue;
+        };
+        let start_ns = offset_ns + seconds_to_ns(time.start);
+        let end_ns = offset_ns + seconds_to_ns(time.end);
+
+        let (stream_offset, _) = stream_info.stream.begin_scope(
+            || start_ns,
+            scope_id(global_profiler, &scope.label),
+            "",
+        );
+        write_scopes_recursive(
+            stream_info,
+            global_profiler,
+            &scope.nested_queries,
+            offset_ns,
+            depth + 1,
+        );
+        stream_info.stream.end_scope(stream_offset, end_ns);
+
+        stream_info.num_scopes += 1;
+        stream_info.depth = stream_info.depth.max(depth + 1);
+        stream_info.range_ns.0 = stream_info.range_ns.0.min(start_ns);
+        stream_info.range_ns.1 = stream_info.range_ns.1.max(end_ns);
+    }
+}
@@ -1,7 +1,30 @@
 //! Scope types that wrap a `wgpu` encoder/pass and start a scope on creation. In most cases, they
 //! then allow automatically ending the scope on drop.
 
-use crate::{GpuProfiler, GpuProfilerQuery, ProfilerCommandRecorder};
+use std::borrow::Cow;
+
+use crate::{
+    BeginQueryError, GpuProfiler, GpuProfilerQuery, ProfilerCommandRecorder, QueryId,
+    RegisteredScopeId,
+};
+
+/// How a pass opened via `scoped_render_pass_with_timestamps`/`scoped_compute_pass_with_timestamps` is timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PassTimestampMethod {
+    /// The pass writes timestamps at its beginning & end via its `timestamp_writes`.
+    ///
+    /// Only requires [`wgpu::Features::TIMESTAMP_QUERY`]. This is what `scoped_render_pass`/`scoped_compute_pass` do.
+    #[default]
+    TimestampWrites,
+
+    /// Timestamps are written via `write_timestamp` as the first & last command within the pass.
+    ///
+    /// Requires [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`], but keeps the `timestamp_writes` of the passed
+    /// render pass descriptor, which is useful if the application uses them for its own purposes.
+    /// Like for other scopes on passes, a debug group is pushed within the pass
+    /// if [`crate::GpuProfilerSettings::enable_debug_groups`] is enabled.
+    InsidePass,
+}
 
 /// Scope that takes a (mutable) reference to the encoder/pass.
 ///
@@ -12,6 +35,15 @@ pub struct Scope<'a, Recorder: ProfilerCommandRecorder> {
     pub scope: Option<GpuProfilerQuery>,
 }
 
+impl<'a, R: ProfilerCommandRecorder> std::fmt::Debug for Scope<'a, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The recorder isn't necessarily `Debug` and the profiler is the same for all scopes.
+        f.debug_struct("Scope")
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<'a, R: ProfilerCommandRecorder> Drop for Scope<'a, R> {
     #[inline]
     fn drop(&mut self) {
@@ -30,6 +62,15 @@ pub struct OwningScope<'a, Recorder: ProfilerCommandRecorder> {
     pub scope: Option<GpuProfilerQuery>,
 }
 
+impl<'a, R: ProfilerCommandRecorder> std::fmt::Debug for OwningScope<'a, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The recorder isn't necessarily `Debug` and the profiler is the same for all scopes.
+        f.debug_struct("OwningScope")
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<'a, R: ProfilerCommandRecorder> Drop for OwningScope<'a, R> {
     #[inline]
     fn drop(&mut self) {
@@ -39,6 +80,24 @@ impl<'a, R: ProfilerCommandRecorder> Drop for OwningScope<'a, R> {
     }
 }
 
+impl<'a, R: ProfilerCommandRecorder> OwningScope<'a, R> {
+    /// Ends the scope and returns the owned [`ProfilerCommandRecorder`].
+    ///
+    /// Unlike [`ManualOwningScope::end_query`], the scope would otherwise be ended on drop,
+    /// so this is only needed if the owned encoder/pass has to be recovered.
+    #[track_caller]
+    #[inline]
+    pub fn into_inner(self) -> R {
+        let mut this = std::mem::ManuallyDrop::new(self);
+        if let Some(scope) = this.scope.take() {
+            this.profiler.end_query(&mut this.recorder, scope);
+        }
+        // SAFETY: `this` is never dropped, so the recorder is moved out exactly once.
+        // All other fields are either references or `None` at this point, so nothing is leaked.
+        unsafe { std::ptr::read(&this.recorder) }
+    }
+}
+
 /// Scope that takes ownership of the encoder/pass.
 ///
 /// Does NOT call [`GpuProfiler::end_query()`] on drop.
@@ -51,6 +110,15 @@ pub struct ManualOwningScope<'a, Recorder: ProfilerCommandRecorder> {
     pub scope: Option<GpuProfilerQuery>,
 }
 
+impl<'a, R: ProfilerCommandRecorder> std::fmt::Debug for ManualOwningScope<'a, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The recorder isn't necessarily `Debug` and the profiler is the same for all scopes.
+        f.debug_struct("ManualOwningScope")
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<'a, R: ProfilerCommandRecorder> ManualOwningScope<'a, R> {
     /// Ends the scope allowing the extraction of the owned [`ProfilerCommandRecorder`].
     #[track_caller]
@@ -61,6 +129,67 @@ impl<'a, R: ProfilerCommandRecorder> ManualOwningScope<'a, R> {
             .end_query(&mut self.recorder, self.scope.take().unwrap());
         self.recorder
     }
+
+    /// Ends the scope and returns the owned [`ProfilerCommandRecorder`] along with the scope's profiler.
+    ///
+    /// This allows to immediately start a sibling scope on the returned recorder
+    /// without having to keep a separate reference to the profiler around.
+    #[track_caller]
+    #[inline]
+    pub fn end_query_keep_profiler(self) -> (R, &'a GpuProfiler) {
+        let profiler = self.profiler;
+        (self.end_query(), profiler)
+    }
+}
+
+/// Scope that only measures cpu time, not tied to any encoder or pass.
+///
+/// Calls [`GpuProfiler::end_cpu_query()`] on drop.
+pub struct CpuScope<'a> {
+    pub profiler: &'a GpuProfiler,
+    pub scope: Option<GpuProfilerQuery>,
+}
+
+impl<'a> std::fmt::Debug for CpuScope<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CpuScope")
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Drop for CpuScope<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(scope) = self.scope.take() {
+            self.profiler.end_cpu_query(scope);
+        }
+    }
+}
+
+impl<'a> CpuScope<'a> {
+    /// Id of the query of this scope, see [`GpuProfilerQuery::id`].
+    ///
+    /// `None` only if the scope was already ended.
+    #[inline]
+    pub fn id(&self) -> Option<QueryId> {
+        self.scope.as_ref().map(GpuProfilerQuery::id)
+    }
+
+    /// Starts a new cpu scope nested within this one, see [`GpuProfiler::cpu_scope`].
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn cpu_scope(&mut self, label: impl Into<Cow<'static, str>>) -> CpuScope<'_> {
+        let scope = self
+            .profiler
+            .begin_cpu_query(label)
+            .with_parent(self.scope.as_ref());
+        CpuScope {
+            profiler: self.profiler,
+            scope: Some(scope),
+        }
+    }
 }
 
 /// Most implementation code of the different scope types is exactly the same.
@@ -71,13 +200,26 @@ impl<'a, R: ProfilerCommandRecorder> ManualOwningScope<'a, R> {
 macro_rules! impl_scope_ext {
     ($scope:ident, $recorder_type:ty) => {
         impl<'a, R: ProfilerCommandRecorder> $scope<'a, R> {
+            /// Id of the query of this scope, see [`GpuProfilerQuery::id`].
+            ///
+            /// `None` only if the scope was already ended.
+            #[inline]
+            pub fn id(&self) -> Option<QueryId> {
+                self.scope.as_ref().map(GpuProfilerQuery::id)
+            }
+
             /// Starts a new profiler scope nested within this one.
+            ///
+            /// On render/compute passes, this allows timing individual draws or dispatches within the pass.
+            /// Such scopes are only timed if the device supports [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`],
+            /// see [`GpuProfiler::features_supported`]. Otherwise they still show up in the results without time,
+            /// while a pass opened via `scoped_render_pass`/`scoped_compute_pass` is timed as a whole.
             #[must_use]
             #[track_caller]
             #[inline]
             pub fn scope(
                 &mut self,
-                label: impl Into<String>,
+                label: impl Into<Cow<'static, str>>,
                 device: &wgpu::Device,
             ) -> Scope<'_, R> {
                 let recorder: &mut R = &mut self.recorder;
@@ -91,6 +233,147 @@ macro_rules! impl_scope_ext {
                     scope: Some(scope),
                 }
             }
+
+            /// Starts a new profiler scope nested within this one, tagged with a category.
+            ///
+            /// See [`GpuProfiler::begin_query_with_category`].
+            #[must_use]
+            #[track_caller]
+            #[inline]
+            pub fn scope_with_category(
+                &mut self,
+                label: impl Into<Cow<'static, str>>,
+                category: &'static str,
+                device: &wgpu::Device,
+            ) -> Scope<'_, R> {
+                let recorder: &mut R = &mut self.recorder;
+                let scope = self
+                    .profiler
+                    .begin_query_with_category(label, category, recorder, device)
+                    .with_parent(self.scope.as_ref());
+                Scope {
+                    profiler: self.profiler,
+                    recorder,
+                    scope: Some(scope),
+                }
+            }
+
+            /// Starts a scope registered via [`GpuProfiler::register_scope`] nested within this one.
+            ///
+            /// See [`GpuProfiler::registered_scope`].
+            #[must_use]
+            #[track_caller]
+            #[inline]
+            pub fn registered_scope(
+                &mut self,
+                id: RegisteredScopeId,
+                device: &wgpu::Device,
+            ) -> Scope<'_, R> {
+                let recorder: &mut R = &mut self.recorder;
+                let scope = self
+                    .profiler
+                    .begin_registered_query(id, recorder, device)
+                    .with_parent(self.scope.as_ref());
+                self.profiler.debug_check_registered_depth(id, &scope);
+                Scope {
+                    profiler: self.profiler,
+                    recorder,
+                    scope: Some(scope),
+                }
+            }
+
+            /// Starts a new cpu scope nested within this one, see [`GpuProfiler::cpu_scope`].
+            #[must_use]
+            #[track_caller]
+            #[inline]
+            pub fn cpu_scope(&mut self, label: impl Into<Cow<'static, str>>) -> CpuScope<'_> {
+                let scope = self
+                    .profiler
+                    .begin_cpu_query(label)
+                    .with_parent(self.scope.as_ref());
+                CpuScope {
+                    profiler: self.profiler,
+                    scope: Some(scope),
+                }
+            }
+
+            /// Starts a new profiler scope nested within this one with a label given by [`format_args!`].
+            ///
+            /// See [`GpuProfiler::scope_fmt`].
+            #[must_use]
+            #[track_caller]
+            #[inline]
+            pub fn scope_fmt(
+                &mut self,
+                label: std::fmt::Arguments<'_>,
+                device: &wgpu::Device,
+            ) -> Scope<'_, R> {
+                let recorder: &mut R = &mut self.recorder;
+                let scope = self
+                    .profiler
+                    .begin_query_fmt(label, recorder, device)
+                    .with_parent(self.scope.as_ref());
+                Scope {
+                    profiler: self.profiler,
+                    recorder,
+                    scope: Some(scope),
+                }
+            }
+
+            /// Starts a new profiler scope nested within this one with a label that is computed only if needed.
+            ///
+            /// See [`GpuProfiler::scope_lazy`].
+            #[must_use]
+            #[track_caller]
+            #[inline]
+            pub fn scope_lazy<L: Into<Cow<'static, str>>>(
+                &mut self,
+                label: impl FnOnce() -> L,
+                device: &wgpu::Device,
+            ) -> Scope<'_, R> {
+                let recorder: &mut R = &mut self.recorder;
+                let scope = self
+                    .profiler
+                    .begin_query_lazy(label, recorder, device)
+                    .with_parent(self.scope.as_ref());
+                Scope {
+                    profiler: self.profiler,
+                    recorder,
+                    scope: Some(scope),
+                }
+            }
+
+            /// Runs the passed closure within a new profiler scope nested within this one and returns the closure's result.
+            ///
+            /// See [`GpuProfiler::profile`].
+            #[track_caller]
+            #[inline]
+            pub fn profile<T>(
+                &mut self,
+                label: impl Into<Cow<'static, str>>,
+                device: &wgpu::Device,
+                f: impl FnOnce(&mut Scope<'_, R>) -> T,
+            ) -> T {
+                let mut scope = self.scope(label, device);
+                f(&mut scope)
+            }
+
+            /// Runs the passed closure on the wrapped encoder or pass within a new nested scope
+            /// and returns the closure's result along with the id of the scope.
+            ///
+            /// See [`GpuProfiler::measure`].
+            #[track_caller]
+            #[inline]
+            pub fn measure<T>(
+                &mut self,
+                label: impl Into<Cow<'static, str>>,
+                device: &wgpu::Device,
+                f: impl FnOnce(&mut R) -> T,
+            ) -> (T, Option<QueryId>) {
+                let mut scope = self.scope(label, device);
+                let id = scope.scope.as_ref().and_then(GpuProfilerQuery::reported_id);
+                (f(&mut scope), id)
+            }
         }
 
         impl<'a> $scope<'a, wgpu::CommandEncoder> {
@@ -101,23 +384,32 @@ macro_rules! impl_scope_ext {
             ///
             /// Note that in order to take measurements, this does not require the
             /// [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] feature, only [`wgpu::Features::TIMESTAMP_QUERY`].
+            ///
+            /// To profile a section of a render pass that is owned elsewhere, use [`GpuProfiler::scope`] with a
+            /// reference to the pass instead, which yields a borrowing [`Scope`].
+            /// This requires [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] for measurements.
             #[track_caller]
             pub fn scoped_render_pass<'b>(
                 &'b mut self,
-                label: impl Into<String>,
+                label: impl Into<Cow<'static, str>>,
                 device: &wgpu::Device,
                 pass_descriptor: wgpu::RenderPassDescriptor<'b, '_>,
             ) -> OwningScope<'b, wgpu::RenderPass<'b>> {
-                let child_scope = self
+                let mut child_scope = self
                     .profiler
                     .begin_pass_query(label, &mut self.recorder, device)
                     .with_parent(self.scope.as_ref());
-                let render_pass = self
-                    .recorder
-                    .begin_render_pass(&wgpu::RenderPassDescriptor {
-                        timestamp_writes: child_scope.render_pass_timestamp_writes(),
-                        ..pass_descriptor
-                    });
+                let mut render_pass =
+                    self.recorder
+                        .begin_render_pass(&wgpu::RenderPassDescriptor {
+                            timestamp_writes: child_scope.render_pass_timestamp_writes(),
+                            ..pass_descriptor
+                        });
+                self.profiler.begin_pipeline_statistics_query(
+                    &mut child_scope,
+                    &mut render_pass,
+                    device,
+                );
 
                 OwningScope {
                     profiler: self.profiler,
@@ -133,23 +425,32 @@ macro_rules! impl_scope_ext {
             ///
             /// Note that in order to take measurements, this does not require the
             /// [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] feature, only [`wgpu::Features::TIMESTAMP_QUERY`].
+            ///
+            /// To profile a section of a compute pass that is owned elsewhere, use [`GpuProfiler::scope`] with a
+            /// reference to the pass instead, which yields a borrowing [`Scope`].
+            /// This requires [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] for measurements.
             #[track_caller]
             pub fn scoped_compute_pass<'b>(
                 &'b mut self,
-                label: impl Into<String>,
+                label: impl Into<Cow<'static, str>>,
                 device: &wgpu::Device,
             ) -> OwningScope<'b, wgpu::ComputePass<'b>> {
-                let child_scope = self
+                let mut child_scope = self
                     .profiler
                     .begin_pass_query(label, &mut self.recorder, device)
                     .with_parent(self.scope.as_ref());
 
-                let render_pass = self
-                    .recorder
-                    .begin_compute_pass(&wgpu::ComputePassDescriptor {
-                        label: Some(&child_scope.label),
-                        timestamp_writes: child_scope.compute_pass_timestamp_writes(),
-                    });
+                let mut render_pass =
+                    self.recorder
+                        .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                            label: Some(&child_scope.label),
+                            timestamp_writes: child_scope.compute_pass_timestamp_writes(),
+                        });
+                self.profiler.begin_pipeline_statistics_query(
+                    &mut child_scope,
+                    &mut render_pass,
+                    device,
+                );
 
                 OwningScope {
                     profiler: self.profiler,
@@ -157,6 +458,96 @@ macro_rules! impl_scope_ext {
                     scope: Some(child_scope),
                 }
             }
+
+            /// Like `scoped_render_pass`, but times the pass with the given method and fails instead of
+            /// opening a scope that can't be timed.
+            ///
+            /// The method is validated against the device's features: [`PassTimestampMethod::InsidePass`] requires
+            /// [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`].
+            /// See [`GpuProfiler::try_begin_query`] for all other failure reasons, e.g. running out of queries.
+            /// If an error is returned, neither a scope nor a pass was begun.
+            #[track_caller]
+            pub fn scoped_render_pass_with_timestamps<'b>(
+                &'b mut self,
+                label: impl Into<Cow<'static, str>>,
+                device: &wgpu::Device,
+                pass_descriptor: wgpu::RenderPassDescriptor<'b, '_>,
+                method: PassTimestampMethod,
+            ) -> Result<OwningScope<'b, wgpu::RenderPass<'b>>, BeginQueryError> {
+                let child_scope = self
+                    .profiler
+                    .try_begin_pass_query(label, method, &mut self.recorder, device)?
+                    .with_parent(self.scope.as_ref());
+                let timestamp_writes = match method {
+                    PassTimestampMethod::TimestampWrites => {
+                        child_scope.render_pass_timestamp_writes()
+                    }
+                    PassTimestampMethod::InsidePass => pass_descriptor.timestamp_writes.clone(),
+                };
+                let mut render_pass =
+                    self.recorder
+                        .begin_render_pass(&wgpu::RenderPassDescriptor {
+                            timestamp_writes,
+                            ..pass_descriptor
+                        });
+                let mut child_scope = match method {
+                    PassTimestampMethod::TimestampWrites => child_scope,
+                    PassTimestampMethod::InsidePass => self
+                        .profiler
+                        .begin_query_on_recorder(child_scope, &mut render_pass),
+                };
+                self.profiler.begin_pipeline_statistics_query(
+                    &mut child_scope,
+                    &mut render_pass,
+                    device,
+                );
+
+                Ok(OwningScope {
+                    profiler: self.profiler,
+                    recorder: render_pass,
+                    scope: Some(child_scope),
+                })
+            }
+
+            /// Like `scoped_compute_pass`, but times the pass with the given method and fails instead of
+            /// opening a scope that can't be timed.
+            ///
+            /// See `scoped_render_pass_with_timestamps`.
+            #[track_caller]
+            pub fn scoped_compute_pass_with_timestamps<'b>(
+                &'b mut self,
+                label: impl Into<Cow<'static, str>>,
+                device: &wgpu::Device,
+                method: PassTimestampMethod,
+            ) -> Result<OwningScope<'b, wgpu::ComputePass<'b>>, BeginQueryError> {
+                let child_scope = self
+                    .profiler
+                    .try_begin_pass_query(label, method, &mut self.recorder, device)?
+                    .with_parent(self.scope.as_ref());
+                let mut compute_pass =
+                    self.recorder
+                        .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                            label: Some(&child_scope.label),
+                            timestamp_writes: child_scope.compute_pass_timestamp_writes(),
+                        });
+                let mut child_scope = match method {
+                    PassTimestampMethod::TimestampWrites => child_scope,
+                    PassTimestampMethod::InsidePass => self
+                        .profiler
+                        .begin_query_on_recorder(child_scope, &mut compute_pass),
+                };
+                self.profiler.begin_pipeline_statistics_query(
+                    &mut child_scope,
+                    &mut compute_pass,
+                    device,
+                );
+
+                Ok(OwningScope {
+                    profiler: self.profiler,
+                    recorder: compute_pass,
+                    scope: Some(child_scope),
+                })
+            }
         }
 
         impl<'a, R: ProfilerCommandRecorder> std::ops::Deref for $scope<'a, R> {
@@ -0,0 +1,50 @@
+/// A statically known scope label, e.g. for an enum listing all passes of a renderer.
+///
+/// All methods opening scopes take `impl Into<Cow<'static, str>>` as label.
+/// Rust's orphan rules don't allow this crate to provide the conversion for all implementors of this trait,
+/// so use [`impl_scope_label!`](crate::impl_scope_label) to implement it for your type:
+///
+/// ```
+/// # use wgpu_profiler::ScopeLabel;
+/// enum Pass {
+///     Shadow,
+///     Lighting,
+/// }
+///
+/// impl ScopeLabel for Pass {
+///     fn label(&self) -> &'static str {
+///         match self {
+///             Pass::Shadow => "shadow",
+///             Pass::Lighting => "lighting",
+///         }
+///     }
+/// }
+///
+/// wgpu_profiler::impl_scope_label!(Pass);
+///
+/// let label: std::borrow::Cow<'static, str> = Pass::Shadow.into();
+/// assert_eq!(label, "shadow");
+/// ```
+///
+/// Since labels are static strings, opening a scope with such a label doesn't allocate.
+pub trait ScopeLabel {
+    /// Label of the scope.
+    fn label(&self) -> &'static str;
+}
+
+/// Makes a type implementing [`ScopeLabel`] usable as label for all scope & query methods.
+///
+/// Implements `From<$type> for Cow<'static, str>` by borrowing [`ScopeLabel::label`].
+#[macro_export]
+macro_rules! impl_scope_label {
+    ($($label_type:ty),+ $(,)?) => {
+        $(
+            impl ::std::convert::From<$label_type> for ::std::borrow::Cow<'static, str> {
+                #[inline]
+                fn from(label: $label_type) -> Self {
+                    ::std::borrow::Cow::Borrowed($crate::ScopeLabel::label(&label))
+                }
+            }
+        )+
+    };
+}
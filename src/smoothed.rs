@@ -0,0 +1,147 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::GpuTimerQueryResult;
+
+/// Maintains exponentially smoothed durations of scopes, e.g. for displaying them in an overlay.
+///
+/// Scopes are identified by their path, i.e. the labels of all parent scopes and the scope itself.
+/// Unlike for [`crate::GpuProfilerStatistics`], repeated scopes with the same path are smoothed individually,
+/// in the order in which they occur.
+/// Smoothed durations are kept while a scope is absent from frames, so it continues smoothly when it reappears.
+/// Scopes that haven't been present for [`SmoothedProfiler::MAX_ABSENT_FRAMES`] frames are evicted.
+pub struct SmoothedProfiler {
+    smoothing_factor: f64,
+    num_frames: u64,
+    durations: HashMap<String, SmoothedDuration>,
+    latest_frame: Vec<GpuTimerQueryResult>,
+}
+
+struct SmoothedDuration {
+    seconds: f64,
+    last_frame: u64,
+}
+
+impl SmoothedProfiler {
+    /// Number of frames after which smoothed durations of absent scopes are discarded.
+    pub const MAX_ABSENT_FRAMES: u64 = 256;
+
+    /// Creates a new smoother.
+    ///
+    /// `smoothing_factor` is the weight of the newest duration of a scope, clamped to `0.0..=1.0`.
+    /// The smaller it is, the smoother but also slower to react the durations are.
+    pub fn new(smoothing_factor: f64) -> Self {
+        Self {
+            smoothing_factor: smoothing_factor.clamp(0.0, 1.0),
+            num_frames: 0,
+            durations: HashMap::new(),
+            latest_frame: Vec::new(),
+        }
+    }
+
+    /// Weight of the newest duration of a scope.
+    pub fn smoothing_factor(&self) -> f64 {
+        self.smoothing_factor
+    }
+
+    /// Adds a frame of profiling results.
+    ///
+    /// Scopes without timing information don't affect their smoothed duration.
+    pub fn add_frame(&mut self, profile_data: &[GpuTimerQueryResult]) {
+        let frame = self.num_frames;
+        self.num_frames += 1;
+
+        self.add_results_recursive(profile_data, &mut String::new(), frame);
+        self.durations
+            .retain(|_, duration| duration.last_frame + Self::MAX_ABSENT_FRAMES > frame);
+        self.latest_frame = profile_data.to_vec();
+    }
+
+    /// Returns the most recently added frame with all durations replaced by their smoothed durations.
+    ///
+    /// Since only durations are smoothed, the time ranges are anchored at a synthetic start:
+    /// top-level scopes are laid out back to back starting at zero,
+    /// nested scopes back to back starting at the start of their parent.
+    pub fn current(&self) -> Vec<GpuTimerQueryResult> {
+        let mut results = self.latest_frame.clone();
+        self.smooth_results_recursive(&mut results, &mut String::new(), 0.0);
+        results
+    }
+
+    /// Removes all previously added frames.
+    pub fn clear(&mut self) {
+        self.durations.clear();
+        self.latest_frame.clear();
+    }
+
+    fn add_results_recursive(
+        &mut self,
+        results: &[GpuTimerQueryResult],
+        key: &mut String,
+        frame: u64,
+    ) {
+        let mut occurrences = HashMap::new();
+        for result in results {
+            let parent_key_len = key.len();
+            push_key(key, &result.label, &mut occurrences);
+
+            if let Some(time) = &result.time {
+                let seconds = time.end - time.start;
+                match self.durations.entry(key.clone()) {
+                    Entry::Occupied(mut entry) => {
+                        let duration = entry.get_mut();
+                        duration.seconds += self.smoothing_factor * (seconds - duration.seconds);
+                        duration.last_frame = frame;
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(SmoothedDuration {
+                            seconds,
+                            last_frame: frame,
+                        });
+                    }
+                }
+            }
+
+            self.add_results_recursive(&result.nested_queries, key, frame);
+            key.truncate(parent_key_len);
+        }
+    }
+
+    fn smooth_results_recursive(
+        &self,
+        results: &mut [GpuTimerQueryResult],
+        key: &mut String,
+        start: f64,
+    ) {
+        let mut occurrences = HashMap::new();
+        let mut next_start = start;
+        for result in results {
+            let parent_key_len = key.len();
+            push_key(key, &result.label, &mut occurrences);
+
+            if let Some(duration) = self.durations.get(key.as_str()) {
+                result.time = Some(next_start..(next_start + duration.seconds));
+            }
+            self.smooth_results_recursive(&mut result.nested_queries, key, next_start);
+            if let Some(time) = &result.time {
+                next_start = time.end;
+            }
+
+            key.truncate(parent_key_len);
+        }
+    }
+}
+
+/// Appends the key of a scope with the given label to the key of its parent.
+///
+/// Every repetition of a label among siblings gets a distinct key.
+fn push_key(key: &mut String, label: &str, occurrences: &mut HashMap<String, usize>) {
+    let occurrence = occurrences.entry(label.to_owned()).or_insert(0);
+    if !key.is_empty() {
+        key.push('/');
+    }
+    key.push_str(label);
+    if *occurrence > 0 {
+        key.push_str(&format!("#{occurrence}"));
+    }
+    *occurrence += 1;
+}
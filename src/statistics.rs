@@ -0,0 +1,187 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{flatten_with_paths, GpuTimerQueryResult};
+
+/// Statistics of a single scope or category over the frames in the window of a [`GpuProfilerStatistics`].
+///
+/// All durations are in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScopeStatistics {
+    /// Number of frames in the window the scope or category was present in.
+    pub num_samples: usize,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    /// 95th percentile (nearest rank).
+    pub p95_ms: f64,
+}
+
+/// Aggregates the durations of scopes over a rolling window of frames.
+///
+/// Scopes are identified by their path, i.e. the labels of all parent scopes and the scope itself,
+/// separated by `/`.
+/// If a path occurs several times in a frame, its durations are summed up.
+/// Scopes without timing information are ignored.
+/// Paths that haven't been seen for the entire window are evicted.
+///
+/// Additionally, the total duration per [`GpuTimerQueryResult::category`] in each frame is aggregated,
+/// see [`GpuProfilerStatistics::category`].
+pub struct GpuProfilerStatistics {
+    window_size: usize,
+    num_frames: u64,
+    scopes: HashMap<String, VecDeque<Sample>>,
+    categories: HashMap<String, VecDeque<Sample>>,
+}
+
+struct Sample {
+    frame: u64,
+    duration_ms: f64,
+}
+
+impl GpuProfilerStatistics {
+    /// Creates a new statistics aggregator over the last `window_size` frames.
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window_size: window_size.max(1),
+            num_frames: 0,
+            scopes: HashMap::new(),
+            categories: HashMap::new(),
+        }
+    }
+
+    /// Size of the rolling window in frames.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Adds a frame of profiling results, dropping the oldest frame from the window if necessary.
+    pub fn add_frame(&mut self, profile_data: &[GpuTimerQueryResult]) {
+        let frame = self.num_frames;
+        self.num_frames += 1;
+
+        for (path, _, scope) in flatten_with_paths(profile_data) {
+            let Some(time) = &scope.time else {
+                continue;
+            };
+            let duration_ms = (time.end - time.start) * 1000.0;
+
+            let samples = self.scopes.entry(path).or_default();
+            match samples.back_mut() {
+                Some(sample) if sample.frame == frame => sample.duration_ms += duration_ms,
+                _ => samples.push_back(Sample { frame, duration_ms }),
+            }
+        }
+
+        for (category, duration_ms) in category_durations(profile_data) {
+            // Categories are unique within a frame, so every frame adds at most one sample.
+            self.categories
+                .entry(category.to_owned())
+                .or_default()
+                .push_back(Sample { frame, duration_ms });
+        }
+
+        let window_size = self.window_size as u64;
+        let evict = |_: &String, samples: &mut VecDeque<Sample>| {
+            while samples
+                .front()
+                .is_some_and(|sample| sample.frame + window_size <= frame)
+            {
+                samples.pop_front();
+            }
+            !samples.is_empty()
+        };
+        self.scopes.retain(evict);
+        self.categories.retain(evict);
+    }
+
+    /// Returns the statistics of the scope with the given path, if it was present in the window.
+    pub fn get(&self, path: &str) -> Option<ScopeStatistics> {
+        self.scopes.get(path).map(statistics)
+    }
+
+    /// Iterates over the statistics of all scopes present in the window, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ScopeStatistics)> {
+        self.scopes
+            .iter()
+            .map(|(path, samples)| (path.as_str(), statistics(samples)))
+    }
+
+    /// Returns the statistics of the total duration of all scopes with the given category per frame,
+    /// if the category was present in the window.
+    ///
+    /// See [`category_durations`] for how the total of a frame is computed.
+    pub fn category(&self, category: &str) -> Option<ScopeStatistics> {
+        self.categories.get(category).map(statistics)
+    }
+
+    /// Iterates over the statistics of all categories present in the window, in arbitrary order.
+    pub fn iter_categories(&self) -> impl Iterator<Item = (&str, ScopeStatistics)> {
+        self.categories
+            .iter()
+            .map(|(category, samples)| (category.as_str(), statistics(samples)))
+    }
+
+    /// Removes all previously added frames.
+    pub fn clear(&mut self) {
+        self.scopes.clear();
+        self.categories.clear();
+    }
+}
+
+/// Sums up the durations of all scopes of a frame per [`GpuTimerQueryResult::category`], in milliseconds.
+///
+/// Categories are returned in the order of their first occurrence.
+/// Scopes nested in a scope of the same category are not counted again, as their time is already
+/// included in their ancestor's duration.
+/// Scopes without timing information and scopes without category are ignored,
+/// but scopes nested in them are still taken into account.
+pub fn category_durations(profile_data: &[GpuTimerQueryResult]) -> Vec<(&str, f64)> {
+    let mut durations = Vec::new();
+    add_category_durations(profile_data, &mut Vec::new(), &mut durations);
+    durations
+}
+
+fn add_category_durations<'a>(
+    scopes: &'a [GpuTimerQueryResult],
+    enclosing_categories: &mut Vec<&'a str>,
+    durations: &mut Vec<(&'a str, f64)>,
+) {
+    for scope in scopes {
+        let counted_category = match (scope.category.as_deref(), &scope.time) {
+            (Some(category), Some(time)) if !enclosing_categories.contains(&category) => {
+                let duration_ms = (time.end - time.start) * 1000.0;
+                match durations.iter_mut().find(|(c, _)| *c == category) {
+                    Some((_, total)) => *total += duration_ms,
+                    None => durations.push((category, duration_ms)),
+                }
+                enclosing_categories.push(category);
+                true
+            }
+            _ => false,
+        };
+
+        add_category_durations(&scope.nested_queries, enclosing_categories, durations);
+        if counted_category {
+            enclosing_categories.pop();
+        }
+    }
+}
+
+fn statistics(samples: &VecDeque<Sample>) -> ScopeStatistics {
+    let mut durations = samples
+        .iter()
+        .map(|sample| sample.duration_ms)
+        .collect::<Vec<_>>();
+    durations.sort_by(f64::total_cmp);
+
+    let num_samples = durations.len();
+    let p95_rank = ((num_samples as f64 * 0.95).ceil() as usize).clamp(1, num_samples);
+
+    ScopeStatistics {
+        num_samples,
+        min_ms: durations[0],
+        max_ms: durations[num_samples - 1],
+        mean_ms: durations.iter().sum::<f64>() / num_samples as f64,
+        p95_ms: durations[p95_rank - 1],
+    }
+}
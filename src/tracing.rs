@@ -0,0 +1,50 @@
+//! Reports profiling results as [tracing](https://github.com/tokio-rs/tracing) spans.
+//!
+//! Requires the `tracing` feature.
+
+use crate::GpuTimerQueryResult;
+
+/// Target of all spans & events emitted by [`emit_frame`].
+pub const TARGET: &str = "gpu";
+
+/// Reports a frame of profiling results as `tracing` spans & events with target [`TARGET`].
+///
+/// Every scope becomes a span named `gpu_scope` whose nesting mirrors the nesting of the scopes.
+/// Top level scopes become root spans, they don't inherit the span that is current while calling this.
+/// Spans carry the scope's label, its [`GpuTimerQueryResult::timeline`] and, if timed,
+/// its duration in milliseconds as `duration_ms` field.
+/// Additionally, an info event carrying label & duration is emitted within the span of every timed scope,
+/// so that subscribers which only log events (like `tracing_subscriber::fmt`) pick up the timings as well.
+///
+/// Note that the spans are only opened & closed while this is called, so any timing a subscriber
+/// measures on its own for these spans reflects the time of reporting, not gpu time.
+/// Call this once for every frame returned by [`crate::GpuProfiler::process_finished_frame`].
+pub fn emit_frame(profile_data: &[GpuTimerQueryResult]) {
+    emit_scopes_recursive(profile_data, None);
+}
+
+fn emit_scopes_recursive(scopes: &[GpuTimerQueryResult], parent: Option<&::tracing::Span>) {
+    for scope in scopes {
+        let span = ::tracing::info_span!(
+            target: TARGET,
+            parent: parent.and_then(::tracing::Span::id),
+            "gpu_scope",
+            label = %scope.label,
+            timeline = scope.timeline,
+            duration_ms = ::tracing::field::Empty,
+        );
+
+        if let Some(time) = &scope.time {
+            let duration_ms = (time.end - time.start) * 1000.0;
+            span.record("duration_ms", duration_ms);
+            ::tracing::info!(
+                target: TARGET,
+                parent: &span,
+                label = %scope.label,
+                duration_ms,
+            );
+        }
+
+        emit_scopes_recursive(&scope.nested_queries, Some(&span));
+    }
+}
@@ -1,43 +1,17 @@
+//! Tracy integration, see [`crate::GpuProfiler::new_with_tracy_client`].
+
 use crate::CreationError;
 
+/// Creates a Tracy gpu context calibrated to the current gpu timestamp.
+///
+/// Blocks until the calibration timestamp has been read back.
 pub fn create_tracy_gpu_client(
     backend: wgpu::Backend,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
 ) -> Result<tracy_client::GpuContext, CreationError> {
-    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu sync query_set"),
-        ty: wgpu::QueryType::Timestamp,
-        count: 1,
-    });
-
-    let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu resolve buffer"),
-        size: wgpu::QUERY_SIZE as _,
-        usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
-        mapped_at_creation: false,
-    });
-
-    let map_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu map buffer"),
-        size: wgpu::QUERY_SIZE as _,
-        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
-
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu sync cmd_buf"),
-    });
-    encoder.write_timestamp(&query_set, 0);
-    encoder.resolve_query_set(&query_set, 0..1, &resolve_buffer, 0);
-    encoder.copy_buffer_to_buffer(&resolve_buffer, 0, &map_buffer, 0, wgpu::QUERY_SIZE as _);
-    queue.submit(Some(encoder.finish()));
-
-    map_buffer.slice(..).map_async(wgpu::MapMode::Read, |_| ());
-    device.poll(wgpu::Maintain::Wait);
-
-    let view = map_buffer.slice(..).get_mapped_range();
-    let timestamp: i64 = i64::from_le_bytes((*view).try_into().unwrap());
+    let timestamp = crate::calibration::read_gpu_timestamp(device, queue)
+        .ok_or(CreationError::TracyCalibrationFailed)? as i64;
 
     let tracy_backend = match backend {
         wgpu::Backend::Empty | wgpu::Backend::Metal | wgpu::Backend::BrowserWebGpu => {
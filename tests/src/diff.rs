@@ -0,0 +1,62 @@
+use wgpu_profiler::{diff, ScopeChange, ScopeDelta};
+
+use crate::src::result;
+
+#[test]
+fn diff_frames() {
+    let baseline = [
+        result(
+            "frame",
+            0.0..0.010,
+            [
+                result("shadows", 0.0..0.002, []),
+                result("lighting", 0.002..0.006, []),
+                result("bloom", 0.006..0.008, []),
+            ],
+        ),
+        wgpu_profiler::GpuTimerQueryResult {
+            time: None,
+            ..result("untimed", 0.0..0.0, [])
+        },
+    ];
+    let current = [
+        result(
+            "frame",
+            0.0..0.010,
+            [
+                result("shadows", 0.0..0.004, []),
+                result("lighting", 0.004..0.0065, []),
+                result("ssao", 0.0065..0.007, []),
+                result("ssao", 0.007..0.0075, []),
+            ],
+        ),
+        wgpu_profiler::GpuTimerQueryResult {
+            time: None,
+            ..result("untimed", 0.0..0.0, [])
+        },
+    ];
+
+    let deltas = diff(&baseline, &current, 1.0);
+    let changes = deltas
+        .iter()
+        .map(|delta| (delta.path.as_str(), delta.change))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            ("frame/shadows", ScopeChange::Slower),
+            ("frame/lighting", ScopeChange::Faster),
+            ("frame/bloom", ScopeChange::Removed),
+            ("frame/ssao", ScopeChange::Added),
+        ]
+    );
+
+    // Durations of the same path are summed up.
+    let added = deltas.last().unwrap();
+    assert_eq!(added.baseline_ms, None);
+    assert!((added.current_ms.unwrap() - 1.0).abs() < 1e-9);
+    assert_eq!(added.delta_ms(), None);
+    assert!((deltas[0].delta_ms().unwrap() - 2.0).abs() < 1e-9);
+
+    assert_eq!(diff(&baseline, &baseline, 0.0), Vec::<ScopeDelta>::new());
+}
@@ -1,4 +1,4 @@
-use wgpu_profiler::GpuProfilerSettings;
+use wgpu_profiler::{EndFrameError, GpuProfilerSettings, ProcessFrameError};
 
 use super::create_device;
 
@@ -22,8 +22,10 @@ fn handle_dropped_frames_gracefully() {
         }
         profiler.resolve_queries(&mut encoder);
         profiler.end_frame().unwrap();
+        assert_eq!(profiler.num_pending_frames(), 1);
 
         // We haven't done a device poll, so there can't be a result!
+        assert!(!profiler.is_oldest_pending_frame_ready());
         assert!(profiler
             .process_finished_frame(queue.get_timestamp_period())
             .is_none());
@@ -33,10 +35,457 @@ fn handle_dropped_frames_gracefully() {
     device.poll(wgpu::Maintain::Wait);
 
     // A single (!) frame should now be available.
+    assert!(profiler.is_oldest_pending_frame_ready());
     assert!(profiler
         .process_finished_frame(queue.get_timestamp_period())
         .is_some());
+    assert_eq!(profiler.num_pending_frames(), 0);
     assert!(profiler
         .process_finished_frame(queue.get_timestamp_period())
         .is_none());
 }
+
+#[test]
+fn change_device() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // A pending frame on the first device.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("old device", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+    assert_eq!(profiler.num_pending_frames(), 1);
+
+    // A closed query of the current frame on the first device.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("old device, current frame", &mut encoder, &device));
+    drop(encoder);
+
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+    profiler.change_device(&device, &queue);
+    assert_eq!(profiler.num_pending_frames(), 0);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("new device", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler.process_finished_frame_with_queue(&queue).unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "new device");
+}
+
+#[test]
+fn on_device_lost() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert!(!profiler.needs_device());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("lost device", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    profiler.end_frame().unwrap();
+
+    profiler.on_device_lost();
+    assert!(profiler.needs_device());
+    assert!(!profiler.is_enabled());
+    assert_eq!(profiler.num_pending_frames(), 0);
+    assert_eq!(profiler.memory_report().total_bytes(), 0);
+
+    // Frames without a device don't produce any results.
+    profiler.end_frame().unwrap();
+    assert!(!profiler.is_enabled());
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .is_empty());
+
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+    profiler.change_device(&device, &queue);
+    assert!(!profiler.needs_device());
+    assert!(profiler.is_enabled());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("new device", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler.process_finished_frame_with_queue(&queue).unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "new device");
+}
+
+#[test]
+fn frame_index() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert_eq!(profiler.frame_index(), 0);
+
+    for label in ["frame 0", "frame 1"] {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(profiler.scope(label, &mut encoder, &device));
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    assert_eq!(profiler.frame_index(), 2);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    for expected_index in [0, 1] {
+        let (frame_index, frame) = profiler
+            .process_finished_frame_indexed(queue.get_timestamp_period())
+            .unwrap();
+        assert_eq!(frame_index, expected_index);
+        assert_eq!(frame[0].label, format!("frame {expected_index}"));
+    }
+    assert!(profiler
+        .process_finished_frame_indexed(queue.get_timestamp_period())
+        .is_none());
+
+    // Discarding frames doesn't reset the index.
+    profiler.clear();
+    assert_eq!(profiler.frame_index(), 2);
+}
+
+#[test]
+fn abort_frame() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(
+        GpuProfilerSettings::default().with_track_open_queries(true),
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("aborted", &mut encoder, &device));
+    let open_scope = profiler.scope("open", &mut encoder, &device);
+    assert_eq!(
+        profiler.abort_frame(),
+        Err(EndFrameError::UnclosedQueries {
+            num_open_queries: 1,
+            open_query_labels: vec!["open".to_owned()],
+        })
+    );
+    drop(open_scope);
+    assert_eq!(profiler.abort_frame(), Ok(()));
+    assert_eq!(profiler.frame_index(), 0);
+    drop(encoder);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("kept", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "kept");
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .is_none());
+}
+
+#[test]
+fn clear_with_open_query() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let stale_query = profiler.begin_query("stale", &mut encoder, &device);
+    profiler.clear();
+
+    // Closing a query of a discarded frame is ignored instead of corrupting the open query count.
+    profiler.end_query(&mut encoder, stale_query);
+    drop(profiler.scope("fresh", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "fresh");
+}
+
+#[test]
+fn clear_with_closed_query() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // The active frame's pools were never mapped, clearing must not try to unmap them.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("stale", &mut encoder, &device));
+    profiler.clear();
+    drop(encoder);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("fresh", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "fresh");
+}
+
+#[test]
+fn clear() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    for label in ["stale", "fresh"] {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(profiler.scope(label, &mut encoder, &device));
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        if label == "stale" {
+            profiler.clear();
+            assert_eq!(profiler.num_pending_frames(), 0);
+        }
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "fresh");
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .is_none());
+}
+
+#[test]
+fn lower_max_num_pending_frames() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(
+        GpuProfilerSettings::default().with_max_num_pending_frames(4),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        profiler.set_max_num_pending_frames(0),
+        Err(wgpu_profiler::SettingsError::InvalidMaxNumPendingFrames)
+    ));
+
+    for frame in 0..4 {
+        // Lowering the limit takes effect when ending the next frame.
+        if frame == 3 {
+            profiler.set_max_num_pending_frames(2).unwrap();
+            assert_eq!(profiler.num_pending_frames(), 3);
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(profiler.scope(format!("frame{frame}"), &mut encoder, &device));
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    // The oldest frame is kept since it's the closest to completion, the newer ones made room for the last frame.
+    assert_eq!(profiler.num_pending_frames(), 2);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].label, "frame0");
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].label, "frame3");
+}
+
+#[test]
+fn lower_max_num_pending_frames_with_sample_interval() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(
+        GpuProfilerSettings::default()
+            .with_max_num_pending_frames(3)
+            .with_sample_interval(2),
+    )
+    .unwrap();
+
+    for frame in 0..6 {
+        // Lowering the limit is applied even when ending a frame that isn't sampled.
+        if frame == 5 {
+            assert!(!profiler.is_enabled());
+            profiler.set_max_num_pending_frames(1).unwrap();
+            assert_eq!(profiler.num_pending_frames(), 3);
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(profiler.scope(format!("frame{frame}"), &mut encoder, &device));
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    assert_eq!(profiler.num_pending_frames(), 1);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].label, "frame0");
+}
+
+#[test]
+fn synchronous_readback() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    assert!(matches!(
+        wgpu_profiler::GpuProfiler::new(
+            GpuProfilerSettings::default().with_max_num_pending_frames(0)
+        ),
+        Err(wgpu_profiler::CreationError::InvalidSettings(
+            wgpu_profiler::SettingsError::InvalidMaxNumPendingFrames
+        ))
+    ));
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::synchronous()).unwrap();
+    assert!(profiler
+        .process_finished_frame_blocking(&device, &queue)
+        .is_none());
+
+    for frame in 0..3 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(profiler.scope(format!("frame{frame}"), &mut encoder, &device));
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        let results = profiler
+            .process_finished_frame_blocking(&device, &queue)
+            .unwrap();
+        assert_eq!(results[0].label, format!("frame{frame}"));
+        assert!(results[0].time.is_some());
+        assert_eq!(profiler.num_pending_frames(), 0);
+    }
+}
+
+#[test]
+fn try_process_finished_frame() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(
+        GpuProfilerSettings::synchronous().with_allow_device_poll(false),
+    )
+    .unwrap();
+    assert_eq!(
+        profiler
+            .try_process_finished_frame(queue.get_timestamp_period())
+            .err(),
+        Some(ProcessFrameError::NoPendingFrame)
+    );
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("frame", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    // The profiler doesn't poll on its own, so nothing is available until the application polls.
+    assert_eq!(
+        profiler
+            .try_process_finished_frame(queue.get_timestamp_period())
+            .err(),
+        Some(ProcessFrameError::NotReady)
+    );
+    assert!(profiler
+        .process_finished_frame_blocking(&device, &queue)
+        .is_none());
+    assert_eq!(profiler.num_pending_frames(), 1);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .try_process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].label, "frame");
+    assert_eq!(profiler.num_pending_frames(), 0);
+}
+
+#[test]
+fn sample_interval() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    assert!(matches!(
+        wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default().with_sample_interval(0)),
+        Err(wgpu_profiler::CreationError::InvalidSettings(
+            wgpu_profiler::SettingsError::InvalidSampleInterval
+        ))
+    ));
+
+    let mut profiler =
+        wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default().with_sample_interval(3))
+            .unwrap();
+
+    for frame in 0..6 {
+        assert_eq!(profiler.is_enabled(), frame % 3 == 0);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(profiler.scope(format!("frame{frame}"), &mut encoder, &device));
+        if frame % 3 != 0 {
+            assert_eq!(profiler.num_queries_in_active_frame(), 0);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    // Skipped frames don't become pending frames.
+    assert_eq!(profiler.num_pending_frames(), 2);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    for expected_frame in ["frame0", "frame3"] {
+        let frame = profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap();
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].label, expected_frame);
+    }
+
+    assert!(profiler.is_enabled());
+    profiler.end_frame().unwrap();
+    assert!(!profiler.is_enabled());
+
+    // Changing the interval restarts sampling with the next frame.
+    profiler.set_sample_interval(2).unwrap();
+    profiler.end_frame().unwrap();
+    assert!(profiler.is_enabled());
+    profiler.end_frame().unwrap();
+    assert!(!profiler.is_enabled());
+}
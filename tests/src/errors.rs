@@ -16,21 +16,45 @@ fn invalid_pending_frame_count() {
     ));
 }
 
+#[test]
+fn invalid_timestamp_valid_bits() {
+    for valid_bits in [0, 65] {
+        let settings = GpuProfilerSettings::default()
+            .with_timestamp_wrap_policy(wgpu_profiler::TimestampWrapPolicy::Unwrap { valid_bits });
+        assert_eq!(
+            settings.validate(),
+            Err(wgpu_profiler::SettingsError::InvalidTimestampValidBits)
+        );
+    }
+    let settings = GpuProfilerSettings::default()
+        .with_timestamp_wrap_policy(wgpu_profiler::TimestampWrapPolicy::Unwrap { valid_bits: 64 });
+    assert_eq!(settings.validate(), Ok(()));
+}
+
 #[test]
 fn end_frame_unclosed_query() {
     let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
 
-    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        track_open_queries: true,
+        ..Default::default()
+    })
+    .unwrap();
     let unclosed_query = {
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let closed_query = profiler.begin_query("closed query", &mut encoder, &device);
         let query = profiler.begin_query("open query", &mut encoder, &device);
+        profiler.end_query(&mut encoder, closed_query);
         profiler.resolve_queries(&mut encoder);
         query
     };
 
     assert_eq!(
         profiler.end_frame(),
-        Err(wgpu_profiler::EndFrameError::UnclosedQueries(1))
+        Err(wgpu_profiler::EndFrameError::UnclosedQueries {
+            num_open_queries: 1,
+            open_query_labels: vec!["open query".to_owned()],
+        })
     );
 
     // Make sure we can recover from this.
@@ -84,3 +108,162 @@ fn change_settings_while_query_open() {
 
     profiler.end_query(&mut encoder, query);
 }
+
+#[test]
+fn max_query_depth_exceeded() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler =
+        wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default().with_max_query_depth(2))
+            .unwrap();
+    {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut scope = profiler.scope("level 1", &mut encoder, &device);
+            let mut scope = scope.scope("level 2", &device);
+            let mut scope = scope.scope("level 3", &device);
+            drop(scope.scope("level 4", &device));
+        }
+        profiler.resolve_queries(&mut encoder);
+    }
+
+    // Exceeding the depth doesn't fail the frame.
+    assert_eq!(profiler.end_frame(), Ok(()));
+    assert_eq!(profiler.num_queries_exceeding_max_depth(), 2);
+    assert_eq!(profiler.first_query_exceeding_max_depth(), Some("level 4"));
+
+    assert_eq!(profiler.end_frame(), Ok(()));
+    assert_eq!(profiler.num_queries_exceeding_max_depth(), 0);
+    assert_eq!(profiler.first_query_exceeding_max_depth(), None);
+}
+
+#[test]
+fn try_begin_query() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(
+        GpuProfilerSettings::default()
+            .with_max_num_queries_per_frame(1, wgpu_profiler::QueryOverflowPolicy::Drop),
+    )
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        assert_eq!(
+            profiler
+                .try_begin_query("pass query", &mut pass, &device)
+                .err(),
+            Some(wgpu_profiler::BeginQueryError::TimestampWritesNotSupported)
+        );
+        assert_eq!(
+            profiler.try_scope("pass scope", &mut pass, &device).err(),
+            Some(wgpu_profiler::BeginQueryError::TimestampWritesNotSupported)
+        );
+    }
+
+    let query = profiler
+        .try_begin_query("query", &mut encoder, &device)
+        .unwrap();
+    assert_eq!(
+        profiler
+            .try_begin_query("query over limit", &mut encoder, &device)
+            .err(),
+        Some(wgpu_profiler::BeginQueryError::MaxNumQueriesPerFrameExceeded)
+    );
+    profiler.end_query(&mut encoder, query);
+    profiler.resolve_queries(&mut encoder);
+    assert_eq!(profiler.end_frame(), Ok(()));
+
+    profiler.set_enabled(false);
+    profiler.end_frame().unwrap();
+    assert_eq!(
+        profiler
+            .try_begin_query("disabled", &mut encoder, &device)
+            .err(),
+        Some(wgpu_profiler::BeginQueryError::ProfilerDisabled)
+    );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "Query \"inner\" was closed after its parent query \"outer\"")]
+fn query_closed_after_parent() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(
+        GpuProfilerSettings::default().with_track_open_queries(true),
+    )
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    let outer = profiler.begin_query("outer", &mut encoder, &device);
+    let inner = profiler
+        .begin_query("inner", &mut encoder, &device)
+        .with_parent(Some(&outer));
+    profiler.end_query(&mut encoder, outer);
+    profiler.end_query(&mut encoder, inner);
+}
+
+#[test]
+fn invalid_query_pool() {
+    let (_, device, _) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let query_pool = |capacity: u32, resolve_buffer_size: u64| wgpu_profiler::QueryPoolResources {
+        query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: None,
+            ty: wgpu::QueryType::Timestamp,
+            count: capacity,
+        }),
+        capacity,
+        resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: resolve_buffer_size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        }),
+        read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (capacity * wgpu::QUERY_SIZE) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        }),
+    };
+
+    assert_eq!(
+        wgpu_profiler::GpuProfiler::new_with_query_pools(
+            GpuProfilerSettings::default(),
+            [query_pool(3, 3 * wgpu::QUERY_SIZE as u64)]
+        )
+        .err(),
+        Some(wgpu_profiler::CreationError::InvalidQueryPool(
+            wgpu_profiler::QueryPoolError::InvalidCapacity { capacity: 3 }
+        ))
+    );
+    assert_eq!(
+        wgpu_profiler::GpuProfiler::new_with_query_pools(
+            GpuProfilerSettings::default(),
+            [query_pool(8, 8)]
+        )
+        .err(),
+        Some(wgpu_profiler::CreationError::InvalidQueryPool(
+            wgpu_profiler::QueryPoolError::InvalidResolveBuffer {
+                required_size: 8 * wgpu::QUERY_SIZE as u64
+            }
+        ))
+    );
+    assert_eq!(
+        wgpu_profiler::GpuProfiler::new_with_query_pools(
+            GpuProfilerSettings::default()
+                .with_max_num_queries_per_frame(8, wgpu_profiler::QueryOverflowPolicy::Drop),
+            [query_pool(8, 8 * wgpu::QUERY_SIZE as u64)]
+        )
+        .err(),
+        Some(wgpu_profiler::CreationError::InvalidQueryPool(
+            wgpu_profiler::QueryPoolError::CapacityTooSmall {
+                capacity: 8,
+                required: 16
+            }
+        ))
+    );
+}
@@ -0,0 +1,370 @@
+use crate::src::result;
+
+#[test]
+fn csv() {
+    let frame = [
+        result(
+            "outer",
+            1.0..1.5,
+            [result("inner, \"quoted\"", 1.25..1.5, [])],
+        ),
+        result("second", 2.0..2.25, []),
+        wgpu_profiler::GpuTimerQueryResult {
+            time: None,
+            ..result("untimed", 0.0..0.0, [])
+        },
+    ];
+
+    let mut csv = Vec::new();
+    wgpu_profiler::csv::write_csv(&mut csv, &frame).unwrap();
+
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "depth,label,start_ms,end_ms,duration_ms,path
+0,outer,1000,1500,500,outer
+1,\"inner, \"\"quoted\"\"\",1250,1500,250,\"outer.inner, \"\"quoted\"\"\"
+0,second,2000,2250,250,second
+0,untimed,,,,untimed
+"
+    );
+}
+
+#[test]
+fn perfetto() {
+    let frames = [
+        vec![result("outer", 1.0..1.5, [result("inner", 1.25..1.5, [])])],
+        vec![result("next frame", 2.0..2.25, [])],
+    ];
+
+    let mut trace = Vec::new();
+    wgpu_profiler::perfetto::write_trace(&mut trace, &frames).unwrap();
+
+    // Every packet is a length delimited `Trace.packet` field.
+    let mut num_packets = 0;
+    let mut remaining = trace.as_slice();
+    while !remaining.is_empty() {
+        assert_eq!(remaining[0], 0x0A);
+        let len = remaining[1] as usize;
+        assert!(len < 0x80);
+        remaining = &remaining[2 + len..];
+        num_packets += 1;
+    }
+    // Track descriptor + begin & end for every scope.
+    assert_eq!(num_packets, 1 + 3 * 2);
+
+    for label in ["outer", "inner", "next frame"] {
+        assert!(trace
+            .windows(label.len())
+            .any(|window| window == label.as_bytes()));
+    }
+}
+
+#[test]
+fn perfetto_timelines() {
+    let frames = [vec![
+        result("graphics", 1.0..1.5, []),
+        wgpu_profiler::GpuTimerQueryResult {
+            timeline: 1,
+            ..result("compute", 1.0..1.5, [])
+        },
+    ]];
+
+    let mut trace = Vec::new();
+    wgpu_profiler::perfetto::write_trace(&mut trace, &frames).unwrap();
+
+    // One track descriptor per timeline.
+    for track_name in ["GPU", "GPU timeline 1"] {
+        assert!(trace
+            .windows(track_name.len() + 1)
+            .any(|window| window[0] == track_name.len() as u8
+                && &window[1..] == track_name.as_bytes()));
+    }
+}
+
+#[test]
+fn chrometrace_builder() {
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new();
+    builder.add_frame(
+        0,
+        &[result("outer", 1.0..1.5, [result("inner", 1.25..1.5, [])])],
+    );
+    // Overlaps with the previous frame and should be moved behind it.
+    builder.add_frame(1, &[result("second frame", 1.25..1.75, [])]);
+
+    let mut trace = Vec::new();
+    builder.finish(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+
+    let events = trace
+        .lines()
+        .filter(|line| line.starts_with("{ "))
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 3);
+    assert!(events[0]
+        .contains(r#""ts":1000000, "dur":500000, "ph":"X", "name":"outer", "args":{ "frame":0 }"#));
+    assert!(events[1]
+        .contains(r#""ts":1250000, "dur":250000, "ph":"X", "name":"inner", "args":{ "frame":0 }"#));
+    assert!(events[2].contains(
+        r#""ts":1500000, "dur":500000, "ph":"X", "name":"second frame", "args":{ "frame":1 }"#
+    ));
+}
+
+#[test]
+fn chrometrace_metadata() {
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new();
+    builder.add_frame(
+        3,
+        &[wgpu_profiler::GpuTimerQueryResult {
+            metadata: vec![
+                ("draw_calls".to_owned(), "12".to_owned()),
+                ("note".to_owned(), "\"quoted\"".to_owned()),
+            ],
+            ..result("draw", 1.0..1.5, [])
+        }],
+    );
+
+    let mut trace = Vec::new();
+    builder.finish(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+
+    assert!(trace.contains(
+        r#""name":"draw", "args":{ "frame":3, "draw_calls":"12", "note":"\"quoted\"" }"#
+    ));
+}
+
+#[test]
+fn chrometrace_track_name() {
+    let named = |label: &str, range| wgpu_profiler::GpuTimerQueryResult {
+        track_name: Some("Shadows".into()),
+        ..result(label, range, [])
+    };
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new();
+    builder.add_frame(0, &[named("first", 1.0..1.5)]);
+    builder.add_frame(1, &[named("second", 1.5..2.0)]);
+
+    let mut trace = Vec::new();
+    builder.finish(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+
+    // The track is named only once.
+    assert_eq!(
+        trace.matches(r#""ph":"M", "name":"thread_name""#).count(),
+        1
+    );
+    assert!(trace.contains(r#""args":{ "name":"Shadows" }"#));
+}
+
+#[test]
+fn chrometrace_color() {
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new();
+    builder.add_frame(
+        0,
+        &[
+            wgpu_profiler::GpuTimerQueryResult {
+                color: Some(wgpu_profiler::TraceColor::RailResponse),
+                ..result("shadows", 1.0..1.5, [])
+            },
+            result("lighting", 1.5..2.0, []),
+        ],
+    );
+
+    let mut trace = Vec::new();
+    builder.finish(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+
+    assert!(trace.contains(r#""name":"shadows", "cname":"rail_response""#));
+    assert_eq!(trace.matches("cname").count(), 1);
+}
+
+#[test]
+fn streaming_chrometrace() {
+    let frames = [
+        vec![result("outer", 1.0..1.5, [result("inner", 1.25..1.5, [])])],
+        vec![result("second frame", 1.25..1.75, [])],
+    ];
+
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new();
+    let mut streaming = wgpu_profiler::chrometrace::StreamingChromeTrace::new(Vec::new()).unwrap();
+    for (frame_index, frame) in frames.iter().enumerate() {
+        builder.add_frame(frame_index as u64, frame);
+        streaming.add_frame(frame_index as u64, frame).unwrap();
+    }
+
+    // Identical to the trace of the builder.
+    let mut expected = Vec::new();
+    builder.finish(&mut expected).unwrap();
+    assert_eq!(streaming.finish().unwrap(), expected);
+
+    // Dropping an unfinished trace still closes it.
+    let mut trace = Vec::new();
+    {
+        let mut streaming =
+            wgpu_profiler::chrometrace::StreamingChromeTrace::new(&mut trace).unwrap();
+        streaming.add_frame(0, &frames[0]).unwrap();
+    }
+    assert!(String::from_utf8(trace).unwrap().ends_with("]\n}\n"));
+}
+
+#[test]
+fn chrometrace_cpu_timings() {
+    let with_cpu_time = |label: &str, range, cpu_range| wgpu_profiler::GpuTimerQueryResult {
+        cpu_time: Some(cpu_range),
+        ..result(label, range, [])
+    };
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new().with_cpu_timings(true);
+    builder.add_frame(
+        0,
+        &[
+            with_cpu_time("both", 1.0..1.5, 0.25..0.5),
+            wgpu_profiler::GpuTimerQueryResult {
+                time: None,
+                ..with_cpu_time("cpu only", 0.0..0.0, 0.5..0.75)
+            },
+            result("gpu only", 1.5..2.0, []),
+        ],
+    );
+
+    let mut trace = Vec::new();
+    builder.finish(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+
+    let events = trace
+        .lines()
+        .filter(|line| line.contains(r#""ph":"X""#))
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 4);
+    assert!(events[0].contains(r#""ts":1000000, "dur":500000, "ph":"X", "name":"both""#));
+    assert!(events[1].contains(r#""ts":250000, "dur":250000, "ph":"X", "name":"both""#));
+    assert!(events[2].contains(r#""ts":500000, "dur":250000, "ph":"X", "name":"cpu only""#));
+    assert!(events[3].contains(r#""ts":1500000, "dur":500000, "ph":"X", "name":"gpu only""#));
+
+    // Cpu events are on a separate, named track.
+    let tid = |event: &str| {
+        event
+            .split(r#""tid":"#)
+            .nth(1)
+            .unwrap()
+            .split(',')
+            .next()
+            .unwrap()
+            .to_owned()
+    };
+    assert_ne!(tid(events[0]), tid(events[1]));
+    assert_eq!(tid(events[1]), tid(events[2]));
+    assert_eq!(tid(events[0]), tid(events[3]));
+    assert_eq!(
+        trace
+            .matches(r#""ph":"M", "name":"thread_name", "args":{ "name":"cpu" }"#)
+            .count(),
+        1
+    );
+
+    // Without cpu timings, only gpu events are written.
+    let mut builder = wgpu_profiler::chrometrace::ChromeTraceBuilder::new();
+    builder.add_frame(0, &[with_cpu_time("both", 1.0..1.5, 0.25..0.5)]);
+    let mut trace = Vec::new();
+    builder.finish(&mut trace).unwrap();
+    assert_eq!(
+        String::from_utf8(trace)
+            .unwrap()
+            .matches(r#""ph":"X""#)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn folded() {
+    let frame = [
+        result(
+            "outer",
+            1.0..1.5,
+            [
+                result("inner;1", 1.25..1.375, []),
+                result("inner 2", 1.375..1.5, []),
+            ],
+        ),
+        wgpu_profiler::GpuTimerQueryResult {
+            time: None,
+            ..result("untimed", 0.0..0.0, [result("nested", 2.0..2.25, [])])
+        },
+    ];
+
+    let mut folded = Vec::new();
+    wgpu_profiler::folded::write_folded(&mut folded, &frame).unwrap();
+
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "outer 250000000
+outer;inner_1 125000000
+outer;inner 2 125000000
+untimed;nested 250000000
+"
+    );
+}
+
+#[test]
+fn capture_roundtrip() {
+    let frames = [
+        vec![
+            wgpu_profiler::GpuTimerQueryResult {
+                id: wgpu_profiler::QueryId(3),
+                pid: 42,
+                cpu_time: Some(0.5..0.75),
+                timeline: 1,
+                track_name: Some("Compute queue".into()),
+                metadata: vec![("draws".to_owned(), "12".to_owned())],
+                color: Some(wgpu_profiler::TraceColor::RailResponse),
+                category: Some("lighting".into()),
+                pipeline_stats: Some(wgpu_profiler::PipelineStats {
+                    compute_shader_invocations: Some(1024),
+                    ..Default::default()
+                }),
+                ..result("outer", 1.0..1.5, [result("inner", 1.25..1.5, [])])
+            },
+            wgpu_profiler::GpuTimerQueryResult {
+                time: None,
+                ..result("untimed", 0.0..0.0, [result("nested", 2.0..2.25, [])])
+            },
+        ],
+        vec![],
+        vec![result("outer", 3.0..3.5, [result("inner", 3.25..3.5, [])])],
+    ];
+
+    let mut writer = wgpu_profiler::capture::Writer::new(Vec::new()).unwrap();
+    for frame in &frames {
+        writer.write_frame(frame).unwrap();
+    }
+    let capture = writer.into_inner();
+    assert!(capture.starts_with(&wgpu_profiler::capture::MAGIC));
+    // Labels are only stored once.
+    assert_eq!(
+        capture
+            .windows("outer".len())
+            .filter(|window| *window == b"outer")
+            .count(),
+        1
+    );
+
+    let reader = wgpu_profiler::capture::Reader::new(capture.as_slice()).unwrap();
+    let read_frames = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(format!("{read_frames:?}"), format!("{frames:?}"));
+}
+
+#[test]
+fn capture_invalid() {
+    let error = wgpu_profiler::capture::Reader::new(b"not a capture".as_slice())
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut writer = wgpu_profiler::capture::Writer::new(Vec::new()).unwrap();
+    writer
+        .write_frame(&[result("outer", 1.0..1.5, [])])
+        .unwrap();
+    let capture = writer.into_inner();
+
+    // Truncated frames are an error rather than the end of the capture.
+    let mut reader = wgpu_profiler::capture::Reader::new(&capture[..capture.len() - 1]).unwrap();
+    assert!(reader.read_frame().is_err());
+}
@@ -74,7 +74,7 @@ fn multithreaded_scopes() {
             barrier.wait();
 
             for i in 0..NUM_SCOPES_PER_THREAD {
-                let _ = profiler.scope(&format!("e0_s{i}"), &mut encoder, &device);
+                let _ = profiler.scope(format!("e0_s{i}"), &mut encoder, &device);
             }
             encoder.finish()
         });
@@ -84,7 +84,7 @@ fn multithreaded_scopes() {
             barrier.wait();
 
             for i in 0..NUM_SCOPES_PER_THREAD {
-                let _ = profiler.scope(&format!("e1_s{i}"), &mut encoder, &device);
+                let _ = profiler.scope_fmt(format_args!("e1_s{i}"), &mut encoder, &device);
             }
             encoder.finish()
         });
@@ -121,3 +121,9 @@ fn multithreaded_scopes() {
             .collect::<Vec<_>>(),
     );
 }
+
+#[test]
+fn profiler_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<GpuProfiler>();
+}
@@ -0,0 +1,45 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings, MockCommand, MockRecorder};
+
+use super::create_device;
+
+#[test]
+fn mock_recorder_nested_scopes() {
+    let Ok((_, device, _)) = create_device(wgpu::Features::TIMESTAMP_QUERY) else {
+        println!("Skipping test because device doesn't support timer queries");
+        return;
+    };
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut recorder = MockRecorder::encoder();
+    {
+        let mut outer = profiler.scope("outer", &mut recorder, &device);
+        drop(outer.scope("inner", &device));
+    }
+
+    assert_eq!(
+        recorder.commands,
+        [
+            MockCommand::WriteTimestamp { query_index: 0 },
+            MockCommand::PushDebugGroup("outer".to_owned()),
+            MockCommand::WriteTimestamp { query_index: 2 },
+            MockCommand::PushDebugGroup("inner".to_owned()),
+            MockCommand::WriteTimestamp { query_index: 3 },
+            MockCommand::PopDebugGroup,
+            MockCommand::WriteTimestamp { query_index: 1 },
+            MockCommand::PopDebugGroup,
+        ]
+    );
+
+    // Passes require `TIMESTAMP_QUERY_INSIDE_PASSES`, scopes are opened without timestamp writes.
+    let mut pass = MockRecorder::pass();
+    drop(profiler.scope("pass", &mut pass, &device));
+    assert!(pass.timestamp_writes().is_empty());
+    assert_eq!(
+        pass.commands,
+        [
+            MockCommand::PushDebugGroup("pass".to_owned()),
+            MockCommand::PopDebugGroup,
+        ]
+    );
+}
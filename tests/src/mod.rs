@@ -1,9 +1,16 @@
 use wgpu::RequestDeviceError;
 
+mod diff;
 mod dropped_frame_handling;
 mod errors;
+#[cfg(feature = "exporters")]
+mod exporters;
 mod interleaved_command_buffer;
+#[cfg(feature = "test-util")]
+mod mock_recorder;
 mod nested_scopes;
+mod results;
+mod statistics;
 
 pub fn create_device(
     features: wgpu::Features,
@@ -34,10 +41,25 @@ pub fn create_device(
     futures_lite::future::block_on(create_default_device_async(features))
 }
 
+/// Feature requirements for a scope to be timed.
 #[derive(Debug)]
 enum Requires {
     Timestamps,
     TimestampsInPasses,
+    /// Scope is never timed, regardless of features.
+    Never,
+}
+
+impl Requires {
+    fn is_timed(&self, features: wgpu::Features) -> bool {
+        match self {
+            Requires::Timestamps => features.contains(wgpu::Features::TIMESTAMP_QUERY),
+            Requires::TimestampsInPasses => {
+                features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
+            }
+            Requires::Never => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -51,29 +73,32 @@ fn expected_scope(
     ExpectedScope(label.into(), requires, children.into())
 }
 
+fn validate_result(
+    features: wgpu::Features,
+    result: &wgpu_profiler::GpuTimerQueryResult,
+    expected: &ExpectedScope,
+) {
+    assert_eq!(result.label, expected.0);
+    assert_eq!(
+        result.time.is_some(),
+        expected.1.is_timed(features),
+        "result: {result:?}\nexpected: {expected:?}"
+    );
+    validate_results(features, &result.nested_queries, &expected.2);
+}
+
 fn validate_results(
     features: wgpu::Features,
     result: &[wgpu_profiler::GpuTimerQueryResult],
     expected: &[ExpectedScope],
 ) {
-    let expected = expected
-        .iter()
-        .filter(|expected| match expected.1 {
-            Requires::Timestamps => features.contains(wgpu::Features::TIMESTAMP_QUERY),
-            Requires::TimestampsInPasses => {
-                features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
-            }
-        })
-        .collect::<Vec<_>>();
-
     assert_eq!(
         result.len(),
         expected.len(),
         "result: {result:?}\nexpected: {expected:?}"
     );
     for (result, expected) in result.iter().zip(expected.iter()) {
-        assert_eq!(result.label, expected.0);
-        validate_results(features, &result.nested_queries, &expected.2);
+        validate_result(features, result, expected);
     }
 }
 
@@ -82,28 +107,45 @@ fn validate_results_unordered(
     result: &[wgpu_profiler::GpuTimerQueryResult],
     expected: &[ExpectedScope],
 ) {
-    let expected = expected
-        .iter()
-        .filter(|expected| match expected.1 {
-            Requires::Timestamps => features.contains(wgpu::Features::TIMESTAMP_QUERY),
-            Requires::TimestampsInPasses => {
-                features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
-            }
-        })
-        .collect::<Vec<_>>();
-
     assert_eq!(
         result.len(),
         expected.len(),
         "result: {result:?}\nexpected: {expected:?}"
     );
 
-    let mut expected_labels = std::collections::HashSet::<String>::from_iter(
-        expected.iter().map(|expected| expected.0.clone()),
+    let mut expected_by_label = std::collections::HashMap::<&str, &ExpectedScope>::from_iter(
+        expected
+            .iter()
+            .map(|expected| (expected.0.as_str(), expected)),
     );
 
-    for (result, expected) in result.iter().zip(expected.iter()) {
-        assert!(expected_labels.remove(&result.label));
-        validate_results(features, &result.nested_queries, &expected.2);
+    for result in result {
+        let expected = expected_by_label
+            .remove(&*result.label)
+            .unwrap_or_else(|| panic!("unexpected result: {result:?}"));
+        validate_result(features, result, expected);
+    }
+}
+
+/// Creates a result with the given label, time range (in seconds) and nested results.
+fn result(
+    label: &str,
+    time: std::ops::Range<f64>,
+    nested_queries: impl Into<Vec<wgpu_profiler::GpuTimerQueryResult>>,
+) -> wgpu_profiler::GpuTimerQueryResult {
+    wgpu_profiler::GpuTimerQueryResult {
+        label: label.to_owned().into(),
+        id: wgpu_profiler::QueryId::default(),
+        pid: 0,
+        tid: std::thread::current().id(),
+        time: Some(time),
+        cpu_time: None,
+        timeline: 0,
+        track_name: None,
+        metadata: Vec::new(),
+        color: None,
+        category: None,
+        pipeline_stats: None,
+        nested_queries: nested_queries.into(),
     }
 }
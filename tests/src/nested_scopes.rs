@@ -1,4 +1,7 @@
-use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+use wgpu_profiler::{
+    BeginQueryError, EndFrameError, GpuProfiler, GpuProfilerSettings, PassTimestampMethod,
+    ProfiledRenderBundle, ProfilerCapabilities, QueryOverflowPolicy, ScopeLabel, TraceColor,
+};
 
 use crate::src::{expected_scope, validate_results, Requires};
 
@@ -144,3 +147,1711 @@ fn nested_scopes_no_features() {
     let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
     nested_scopes(&device, &queue);
 }
+
+#[test]
+fn closure_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    let value = profiler.profile("e0_s0", &mut encoder, &device, |scope| {
+        scope.profile("e0_s0_s0", &device, |_| ());
+        scope.profile("e0_s0_s1", &device, |scope| {
+            scope.profile("e0_s0_s1_s0", &device, |_| 42)
+        })
+    });
+    assert_eq!(value, 42);
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::Timestamps,
+            [
+                expected_scope("e0_s0_s0", Requires::Timestamps, []),
+                expected_scope(
+                    "e0_s0_s1",
+                    Requires::Timestamps,
+                    [expected_scope("e0_s0_s1_s0", Requires::Timestamps, [])],
+                ),
+            ],
+        )],
+    );
+}
+
+#[test]
+fn measure() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    let ((value, nested_id), id) = profiler.measure("e0_s0", &mut encoder, &device, |encoder| {
+        let mut scope = profiler.scope("e0_s0_s0", encoder, &device);
+        scope.measure("e0_s0_s0_s0", &device, |_| 42)
+    });
+    assert_eq!(value, 42);
+    assert!(id.is_some());
+    assert_ne!(id, nested_id);
+
+    // Passes are handed to the closure just the same.
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        let (value, _) = profiler.measure("p0", &mut pass, &device, |pass| {
+            pass.insert_debug_marker("marker");
+            7
+        });
+        assert_eq!(value, 7);
+    }
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].label, "e0_s0");
+    assert_eq!(Some(frame[0].id), id);
+    assert_eq!(
+        Some(frame[0].nested_queries[0].nested_queries[0].id),
+        nested_id
+    );
+
+    // Scopes that don't show up in the results don't have an id.
+    let profiler = GpuProfiler::new(
+        GpuProfilerSettings::default().with_query_filter(|label| label != "filtered"),
+    )
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let (_, id) = profiler.measure("filtered", &mut encoder, &device, |_| ());
+    assert_eq!(id, None);
+}
+
+#[test]
+fn render_bundle_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut bundle_encoder =
+        device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+            label: None,
+            color_formats: &[Some(wgpu::TextureFormat::Rgba8Unorm)],
+            depth_stencil: None,
+            sample_count: 1,
+            multiview: None,
+        });
+    {
+        let mut scope = profiler.scope("b0_s0", &mut bundle_encoder, &device);
+        drop(scope.scope("b0_s0_s0", &device));
+    }
+    bundle_encoder.finish(&wgpu::RenderBundleDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    // Render bundles can't be timed, so the scopes show up without timings.
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "b0_s0",
+            Requires::Never,
+            [expected_scope("b0_s0_s0", Requires::Never, [])],
+        )],
+    );
+}
+
+#[test]
+fn profiled_render_bundles() {
+    let Ok((_, device, queue)) = create_device(GpuProfiler::ALL_WGPU_TIMER_FEATURES) else {
+        println!("Skipping test because device doesn't support timer features");
+        return;
+    };
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let bundle_encoder =
+        device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+            label: None,
+            color_formats: &[Some(wgpu::TextureFormat::Rgba8Unorm)],
+            depth_stencil: None,
+            sample_count: 1,
+            multiview: None,
+        });
+    let bundle = ProfiledRenderBundle::new(
+        "bundle",
+        bundle_encoder.finish(&wgpu::RenderBundleDescriptor::default()),
+    );
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations::default(),
+            })],
+            ..Default::default()
+        });
+        // Every execution is a separate scope.
+        profiler.execute_bundle(&mut pass, &bundle, &device);
+        profiler.execute_bundle(&mut pass, &bundle, &device);
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("bundle", Requires::TimestampsInPasses, []),
+            expected_scope("bundle", Requires::TimestampsInPasses, []),
+        ],
+    );
+}
+
+#[test]
+fn disabled_profiler() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // Disabling only takes effect on the next frame boundary.
+    profiler.set_enabled(false);
+    assert!(profiler.is_enabled());
+    profiler.end_frame().unwrap();
+    assert!(!profiler.is_enabled());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scoped_compute_pass("e0_s0_c0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    // Skip the empty frame from before disabling.
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .is_some());
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(device.features(), &frame, &[]);
+}
+
+#[test]
+fn pass_timestamp_methods() {
+    let (_, device, _) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // Timing from within the pass requires TIMESTAMP_QUERY_INSIDE_PASSES.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        assert!(matches!(
+            scope.scoped_compute_pass_with_timestamps(
+                "e0_s0_c0",
+                &device,
+                PassTimestampMethod::InsidePass
+            ),
+            Err(BeginQueryError::TimestampWritesNotSupported)
+        ));
+    }
+    drop(encoder);
+
+    let Ok((_, device, queue)) = create_device(GpuProfiler::ALL_WGPU_TIMER_FEATURES) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_PASSES");
+        return;
+    };
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        for (label, method) in [
+            ("e0_s0_c0", PassTimestampMethod::TimestampWrites),
+            ("e0_s0_c1", PassTimestampMethod::InsidePass),
+        ] {
+            drop(
+                scope
+                    .scoped_compute_pass_with_timestamps(label, &device, method)
+                    .unwrap(),
+            );
+        }
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::Timestamps,
+            [
+                expected_scope("e0_s0_c0", Requires::Timestamps, []),
+                expected_scope("e0_s0_c1", Requires::TimestampsInPasses, []),
+            ],
+        )],
+    );
+}
+
+#[test]
+fn query_overflow_drop() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(
+        GpuProfilerSettings::default().with_max_num_queries_per_frame(2, QueryOverflowPolicy::Drop),
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    drop(profiler.scope("e0_s1", &mut encoder, &device));
+    drop(profiler.scope("e0_s2", &mut encoder, &device));
+    drop(profiler.scope("e0_s3", &mut encoder, &device));
+    assert_eq!(profiler.num_queries_in_active_frame(), 4);
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+    assert_eq!(profiler.num_dropped_queries(), 2);
+    assert_eq!(profiler.num_queries_in_active_frame(), 0);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("e0_s0", Requires::Timestamps, []),
+            expected_scope("e0_s1", Requires::Timestamps, []),
+            expected_scope("e0_s2", Requires::Never, []),
+            expected_scope("e0_s3", Requires::Never, []),
+        ],
+    );
+
+    // The limit applies per frame.
+    profiler.end_frame().unwrap();
+    assert_eq!(profiler.num_dropped_queries(), 0);
+}
+
+#[test]
+fn timestamp_period_from_queue() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    assert_eq!(
+        GpuProfiler::new(GpuProfilerSettings::default())
+            .unwrap()
+            .timestamp_period(),
+        None
+    );
+
+    assert_eq!(
+        GpuProfiler::new_default(&queue).timestamp_period(),
+        Some(queue.get_timestamp_period())
+    );
+
+    let mut profiler = GpuProfiler::new_with_queue(GpuProfilerSettings::default(), &queue).unwrap();
+    assert_eq!(
+        profiler.timestamp_period(),
+        Some(queue.get_timestamp_period())
+    );
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler.process_finished_frame_with_queue(&queue).unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope("e0_s0", Requires::Timestamps, [])],
+    );
+    assert_eq!(
+        profiler.timestamp_period(),
+        Some(queue.get_timestamp_period())
+    );
+}
+
+#[test]
+fn cpu_timings() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler =
+        GpuProfiler::new(GpuProfilerSettings::default().with_cpu_timings(true)).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope("e0_s0_s0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::Timestamps,
+            [expected_scope("e0_s0_s0", Requires::Timestamps, [])],
+        )],
+    );
+
+    let outer = frame[0].cpu_time.clone().unwrap();
+    let inner = frame[0].nested_queries[0].cpu_time.clone().unwrap();
+    assert!(outer.start <= inner.start && inner.start <= inner.end && inner.end <= outer.end);
+}
+
+#[test]
+fn custom_cpu_clock() {
+    /// Advances by one second whenever it is read.
+    #[derive(Debug, Default)]
+    struct CountingClock(std::sync::atomic::AtomicU64);
+
+    impl wgpu_profiler::Clock for CountingClock {
+        fn now_nanos(&self) -> u64 {
+            (self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1) * 1_000_000_000
+        }
+    }
+
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(
+        GpuProfilerSettings::default()
+            .with_cpu_timings(true)
+            .with_cpu_clock(CountingClock::default()),
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope("e0_s0_s0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].cpu_time, Some(1.0..4.0));
+    assert_eq!(frame[0].nested_queries[0].cpu_time, Some(2.0..3.0));
+}
+
+#[test]
+fn cpu_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler =
+        GpuProfiler::new(GpuProfilerSettings::default().with_cpu_timings(true)).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.cpu_scope("e0_s0_c0"));
+    }
+    profiler.resolve_queries(&mut encoder);
+    {
+        let mut submit_scope = profiler.cpu_scope("submit");
+        drop(submit_scope.cpu_scope("submit_c0"));
+        queue.submit([encoder.finish()]);
+    }
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 2);
+
+    assert_eq!(frame[0].label, "e0_s0");
+    assert!(frame[0].time.is_some());
+    let cpu_query = &frame[0].nested_queries[0];
+    assert_eq!(cpu_query.label, "e0_s0_c0");
+    assert!(cpu_query.time.is_none());
+    assert!(cpu_query.cpu_time.is_some());
+
+    assert_eq!(frame[1].label, "submit");
+    assert!(frame[1].time.is_none());
+    let submit_time = frame[1].cpu_time.clone().unwrap();
+    let nested_time = frame[1].nested_queries[0].cpu_time.clone().unwrap();
+    assert!(submit_time.start <= nested_time.start && nested_time.end <= submit_time.end);
+}
+
+#[test]
+fn features_supported() {
+    assert!(GpuProfiler::features_supported(wgpu::Features::empty()).is_labels_only());
+    assert_eq!(
+        GpuProfiler::features_supported(wgpu::Features::TIMESTAMP_QUERY),
+        ProfilerCapabilities {
+            encoder_timer_queries: true,
+            pass_timer_queries: false,
+        }
+    );
+    assert_eq!(
+        GpuProfiler::features_supported(GpuProfiler::ALL_WGPU_TIMER_FEATURES),
+        ProfilerCapabilities {
+            encoder_timer_queries: true,
+            pass_timer_queries: true,
+        }
+    );
+}
+
+#[test]
+fn required_features() {
+    assert_eq!(
+        GpuProfiler::required_features(&GpuProfilerSettings::default()),
+        wgpu::Features::TIMESTAMP_QUERY
+    );
+    assert_eq!(
+        GpuProfiler::required_features(&GpuProfilerSettings {
+            enable_timer_queries: false,
+            ..Default::default()
+        }),
+        wgpu::Features::empty()
+    );
+    assert_eq!(
+        GpuProfiler::required_features(
+            &GpuProfilerSettings::default()
+                .with_pipeline_statistics(wgpu::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS)
+        ),
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::PIPELINE_STATISTICS_QUERY
+    );
+    assert!(
+        GpuProfiler::ALL_WGPU_TIMER_FEATURES.contains(GpuProfiler::required_features(
+            &GpuProfilerSettings::default()
+        ))
+    );
+}
+
+#[test]
+fn manual_owning_scope_siblings() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let (encoder, profiler_ref) = profiler
+        .manual_owning_scope("e0_s0", encoder, &device)
+        .end_query_keep_profiler();
+    let mut encoder = profiler_ref
+        .manual_owning_scope("e0_s1", encoder, &device)
+        .end_query();
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("e0_s0", Requires::Timestamps, []),
+            expected_scope("e0_s1", Requires::Timestamps, []),
+        ],
+    );
+}
+
+#[test]
+fn borrowed_pass_scopes() {
+    let Ok((_, device, queue)) = create_device(GpuProfiler::ALL_WGPU_TIMER_FEATURES) else {
+        println!("Skipping test because device doesn't support timer features");
+        return;
+    };
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        drop(profiler.scope("p0_s0", &mut pass, &device));
+        let mut scope = profiler.scope("p0_s1", &mut pass, &device);
+        drop(scope.scope("p0_s1_s0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("p0_s0", Requires::TimestampsInPasses, []),
+            expected_scope(
+                "p0_s1",
+                Requires::TimestampsInPasses,
+                [expected_scope("p0_s1_s0", Requires::TimestampsInPasses, [])],
+            ),
+        ],
+    );
+}
+
+#[test]
+fn raw_results() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        let mut inner_scope = scope.scope("e0_s0_s0", &device);
+        drop(inner_scope.scope("e0_s0_s0_s0", &device));
+    }
+    drop(profiler.scope("e0_s1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler.process_finished_frame_raw().unwrap();
+    assert_eq!(
+        frame
+            .iter()
+            .map(|result| (&*result.label, result.depth))
+            .collect::<Vec<_>>(),
+        [
+            ("e0_s0", 0),
+            ("e0_s0_s0", 1),
+            ("e0_s0_s0_s0", 2),
+            ("e0_s1", 0)
+        ]
+    );
+    assert!(frame.iter().all(|result| result.timestamps.is_some()));
+}
+
+#[test]
+fn process_finished_frame_with() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // Nothing submitted yet, so the callback must not be called.
+    assert!(!profiler.process_finished_frame_with(queue.get_timestamp_period(), |_, _| panic!()));
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope("e0_s0_s0", &device));
+    }
+    drop(profiler.scope("e0_s1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut results = Vec::new();
+    assert!(
+        profiler.process_finished_frame_with(queue.get_timestamp_period(), |depth, result| {
+            assert!(result.nested_queries.is_empty());
+            assert!(result.time.is_some());
+            results.push((result.label, depth));
+        })
+    );
+    assert_eq!(
+        results
+            .iter()
+            .map(|(label, depth)| (&**label, *depth))
+            .collect::<Vec<_>>(),
+        [("e0_s0", 0), ("e0_s0_s0", 1), ("e0_s1", 0)]
+    );
+    assert!(profiler.last_frame_total().is_some());
+}
+
+#[test]
+fn frame_guard() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    {
+        let mut frame = profiler.begin_frame();
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        drop(frame.scope("e0_s0", &mut encoder, &device));
+        frame.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+    }
+    assert_eq!(profiler.num_pending_frames(), 1);
+
+    // Errors are returned by `finish`.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let frame = profiler.begin_frame();
+    let query = frame.begin_query("open", &mut encoder, &device);
+    assert!(matches!(
+        frame.finish(),
+        Err(EndFrameError::UnclosedQueries { .. })
+    ));
+    profiler.end_query(&mut encoder, query);
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.begin_frame().finish().unwrap();
+    assert_eq!(profiler.num_pending_frames(), 2);
+}
+
+#[test]
+fn query_filter() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(
+        GpuProfilerSettings::default().with_query_filter(|label| !label.ends_with("skipped")),
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        let mut skipped_scope = scope.scope("e0_s0_skipped", &device);
+        drop(skipped_scope.scope("e0_s0_s0", &device));
+    }
+    {
+        let mut skipped_scope = profiler.scope("e0_skipped", &mut encoder, &device);
+        drop(skipped_scope.scope("e0_s1", &device));
+    }
+    assert!(matches!(
+        profiler.try_begin_query("skipped", &mut encoder, &device),
+        Err(BeginQueryError::FilteredOut)
+    ));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope(
+                "e0_s0",
+                Requires::Timestamps,
+                [expected_scope("e0_s0_s0", Requires::Timestamps, [])],
+            ),
+            expected_scope("e0_s1", Requires::Timestamps, []),
+        ],
+    );
+}
+
+#[test]
+fn registered_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(
+        GpuProfilerSettings::default().with_query_filter(|label| !label.ends_with("skipped")),
+    )
+    .unwrap();
+    let outer = profiler.register_scope("outer", 0);
+    let inner = profiler.register_scope(String::from("inner"), 1);
+    let skipped = profiler.register_scope("skipped", 1);
+    assert_eq!(profiler.registered_scope_label(inner), "inner");
+
+    for _ in 0..2 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut scope = profiler.registered_scope(outer, &mut encoder, &device);
+            drop(scope.registered_scope(inner, &device));
+            drop(scope.registered_scope(skipped, &device));
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+
+    for _ in 0..2 {
+        let frame = profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap();
+        validate_results(
+            device.features(),
+            &frame,
+            &[expected_scope(
+                "outer",
+                Requires::Timestamps,
+                [expected_scope("inner", Requires::Timestamps, [])],
+            )],
+        );
+    }
+}
+
+#[test]
+fn process_finished_frame_into() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut results = Vec::new();
+    assert!(!profiler.process_finished_frame_into(queue.get_timestamp_period(), &mut results));
+
+    for frame in 0..2 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut scope = profiler.scope(format!("f{frame}_s0"), &mut encoder, &device);
+            drop(scope.scope(format!("f{frame}_s0_s0"), &device));
+        }
+        if frame == 0 {
+            drop(profiler.scope("f0_s1", &mut encoder, &device));
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        device.poll(wgpu::Maintain::Wait);
+
+        assert!(profiler.process_finished_frame_into(queue.get_timestamp_period(), &mut results));
+    }
+
+    // Contents of the first frame are entirely replaced by the second one.
+    validate_results(
+        device.features(),
+        &results,
+        &[expected_scope(
+            "f1_s0",
+            Requires::Timestamps,
+            [expected_scope("f1_s0_s0", Requires::Timestamps, [])],
+        )],
+    );
+}
+
+#[test]
+fn recycle_result_buffers() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler =
+        GpuProfiler::new(GpuProfilerSettings::default().with_recycle_result_buffers(true)).unwrap();
+
+    for frame in 0..3 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut scope = profiler.scope(format!("f{frame}_s0"), &mut encoder, &device);
+            let mut inner_scope = scope.scope(format!("f{frame}_s0_s0"), &device);
+            drop(inner_scope.scope(format!("f{frame}_s0_s0_s0"), &device));
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        device.poll(wgpu::Maintain::Wait);
+
+        // Recycled vectors of the previous frame must not leak any of its results.
+        let results = profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap();
+        validate_results(
+            device.features(),
+            &results,
+            &[expected_scope(
+                format!("f{frame}_s0"),
+                Requires::Timestamps,
+                [expected_scope(
+                    format!("f{frame}_s0_s0"),
+                    Requires::Timestamps,
+                    [expected_scope(
+                        format!("f{frame}_s0_s0_s0"),
+                        Requires::Timestamps,
+                        [],
+                    )],
+                )],
+            )],
+        );
+        profiler.recycle_results(results);
+    }
+}
+
+#[test]
+fn open_query_labels() {
+    let (_, device, _) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler =
+        GpuProfiler::new(GpuProfilerSettings::default().with_track_open_queries(true)).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("shadows", &mut encoder, &device);
+        let inner_scope = scope.scope("cascade 2", &device);
+        assert_eq!(profiler.open_query_labels(), ["shadows", "cascade 2"]);
+        drop(inner_scope);
+        assert_eq!(profiler.open_query_labels(), ["shadows"]);
+    }
+    assert!(profiler.open_query_labels().is_empty());
+}
+
+#[test]
+fn lazy_labels() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    // Labels aren't created if they aren't needed.
+    let unused_profiler = GpuProfiler::new(
+        GpuProfilerSettings::default()
+            .with_timer_queries(false)
+            .with_debug_groups(false),
+    )
+    .unwrap();
+    drop(unused_profiler.scope_lazy(|| -> String { panic!() }, &mut encoder, &device));
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    {
+        let mut scope = profiler.scope_lazy(|| format!("e0_s{}", 0), &mut encoder, &device);
+        drop(scope.scope_lazy(|| "e0_s0_s0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::Timestamps,
+            [expected_scope("e0_s0_s0", Requires::Timestamps, [])],
+        )],
+    );
+}
+
+#[test]
+fn scope_labels() {
+    enum Pass {
+        Shadow,
+        Lighting,
+    }
+
+    impl ScopeLabel for Pass {
+        fn label(&self) -> &'static str {
+            match self {
+                Pass::Shadow => "shadow",
+                Pass::Lighting => "lighting",
+            }
+        }
+    }
+
+    wgpu_profiler::impl_scope_label!(Pass);
+
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope(Pass::Shadow, &mut encoder, &device);
+        drop(scope.scope(Pass::Lighting, &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "shadow",
+            Requires::Timestamps,
+            [expected_scope("lighting", Requires::Timestamps, [])],
+        )],
+    );
+}
+
+#[test]
+fn application_query_pools() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let capacity = 4;
+    let mut profiler = GpuProfiler::new_with_query_pools(
+        GpuProfilerSettings::default(),
+        [wgpu_profiler::QueryPoolResources {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("application query set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: capacity,
+            }),
+            capacity,
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: (capacity * wgpu::QUERY_SIZE) as u64,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: (capacity * wgpu::QUERY_SIZE) as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        }],
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    drop(profiler.scope("e0_s1", &mut encoder, &device));
+    // The application pool is used first.
+    assert_eq!(
+        profiler
+            .active_query_sets()
+            .map(|(_, num_queries)| num_queries)
+            .collect::<Vec<_>>(),
+        [4]
+    );
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("e0_s0", Requires::Timestamps, []),
+            expected_scope("e0_s1", Requires::Timestamps, []),
+        ],
+    );
+}
+
+#[test]
+fn clock_calibration() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert!(profiler.clock_calibration().is_none());
+
+    let first = profiler.calibrate(&device, &queue).unwrap();
+    assert_eq!(first.drift, 0.0);
+    assert!(first.uncertainty_seconds >= 0.0);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let calibration = profiler.calibrate(&device, &queue).unwrap();
+    assert_eq!(profiler.clock_calibration(), Some(calibration));
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    if let Some(time) = &frame[0].time {
+        // The scope was recorded just now, generous bounds account for slow test machines.
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        assert!((calibration.to_unix_seconds(time.start) - now).abs() < 60.0);
+    }
+}
+
+#[test]
+fn query_ids() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let (outer_id, inner_id) = {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        let inner_scope = scope.scope("e0_s0_s0", &device);
+        (scope.id().unwrap(), inner_scope.id().unwrap())
+    };
+    let query = profiler.begin_query("e0_s1", &mut encoder, &device);
+    let query_id = query.id();
+    profiler.end_query(&mut encoder, query);
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    assert_ne!(outer_id, inner_id);
+    assert_ne!(outer_id, query_id);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].id, outer_id);
+    assert_eq!(frame[0].nested_queries[0].id, inner_id);
+    assert_eq!(frame[1].id, query_id);
+}
+
+#[test]
+fn debug_output() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let scope = profiler.scope("e0_s0", &mut encoder, &device);
+        let scope_debug = format!("{scope:?}");
+        assert!(scope_debug.contains("e0_s0"));
+
+        let profiler_debug = format!("{profiler:?}");
+        assert!(profiler_debug.contains("enabled: true"));
+        assert!(profiler_debug.contains("num_open_queries: 1"));
+        assert!(profiler_debug.contains("num_queries_in_active_frame: 1"));
+    }
+    assert!(format!("{profiler:?}").contains("num_open_queries: 0"));
+}
+
+#[test]
+fn process_finished_frame_async() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert!(futures_lite::future::block_on(
+        profiler.process_finished_frame_async(queue.get_timestamp_period())
+    )
+    .is_none());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    let timestamp_period = queue.get_timestamp_period();
+    let frame = std::thread::scope(|s| {
+        let frame = s.spawn(|| {
+            futures_lite::future::block_on(profiler.process_finished_frame_async(timestamp_period))
+        });
+        // Mappings only complete while the device is polled.
+        while !frame.is_finished() {
+            device.poll(wgpu::Maintain::Poll);
+            std::thread::yield_now();
+        }
+        frame.join().unwrap()
+    })
+    .unwrap();
+
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "e0_s0");
+    assert_eq!(profiler.num_pending_frames(), 0);
+}
+
+#[test]
+fn owning_scope_into_inner() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let mut scope = profiler.owning_scope("outer", encoder, &device);
+    drop(scope.scope("inner", &device));
+    // Ends the scope before handing out the encoder.
+    let mut encoder = scope.into_inner();
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "outer");
+    assert!(frame[0].time.is_some());
+    assert_eq!(frame[0].nested_queries[0].label, "inner");
+}
+
+#[test]
+fn namespaces() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert!(!profiler.pop_namespace());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.push_namespace("physics");
+    {
+        let mut scope = profiler.scope("draw", &mut encoder, &device);
+        profiler.push_namespace("broadphase");
+        drop(scope.scope("draw", &device));
+        assert!(profiler.pop_namespace());
+    }
+    assert!(profiler.pop_namespace());
+    drop(profiler.scope("draw", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame[0].label, "physics/draw");
+    assert_eq!(frame[0].nested_queries[0].label, "physics/broadphase/draw");
+    assert_eq!(frame[1].label, "draw");
+
+    // The filter of `try_begin_query` sees the namespaced label, just like the one of `begin_query`.
+    profiler
+        .change_settings(
+            GpuProfilerSettings::default().with_query_filter(|label| label.starts_with("physics/")),
+        )
+        .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    assert!(matches!(
+        profiler.try_begin_query("draw", &mut encoder, &device),
+        Err(BeginQueryError::FilteredOut)
+    ));
+    profiler.push_namespace("physics");
+    let query = profiler
+        .try_begin_query("draw", &mut encoder, &device)
+        .unwrap();
+    profiler.end_query(&mut encoder, query);
+    assert!(profiler.pop_namespace());
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "physics/draw");
+    assert!(frame[0].time.is_some());
+}
+
+#[test]
+fn pipeline_statistics() {
+    let Ok((_, device, queue)) =
+        create_device(wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::PIPELINE_STATISTICS_QUERY)
+    else {
+        println!("Skipping test because device doesn't support pipeline statistics queries");
+        return;
+    };
+
+    let mut profiler = GpuProfiler::new(
+        GpuProfilerSettings::default()
+            .with_pipeline_statistics(wgpu::PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS),
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scoped_compute_pass("e0_s0_c0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    // Only passes collect statistics.
+    assert_eq!(frame[0].pipeline_stats, None);
+    let pass_stats = frame[0].nested_queries[0].pipeline_stats.unwrap();
+    assert_eq!(pass_stats.compute_shader_invocations, Some(0));
+    assert_eq!(pass_stats.vertex_shader_invocations, None);
+}
+
+#[test]
+fn prune_empty_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler =
+        GpuProfiler::new(GpuProfilerSettings::default().with_prune_empty_scopes(true)).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        for _ in 0..4 {
+            drop(scope.scope("e0_s0_s0", &device));
+        }
+    }
+    drop(profiler.cpu_scope("untimed"));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    // Whether the empty scopes end up with zero width depends on the gpu,
+    // but none of the remaining childless scopes may be zero-width.
+    for (_, result) in wgpu_profiler::flatten(&frame) {
+        if let (Some(time), true) = (&result.time, result.nested_queries.is_empty()) {
+            assert!(time.end - time.start >= 1e-9, "{result:?}");
+        }
+    }
+    // Untimed scopes are always kept.
+    assert!(frame.iter().any(|result| result.label == "untimed"));
+}
+
+#[test]
+fn no_timestamp_anomalies() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope("e0_s0_s0", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert!(profiler.timestamp_anomalies().is_empty());
+    assert!(frame[0].time.is_some());
+    assert!(frame[0].nested_queries[0].time.is_some());
+}
+
+#[test]
+fn no_overlapping_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler =
+        GpuProfiler::new(GpuProfilerSettings::default().with_detect_overlapping_scopes(true))
+            .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope("e0_s0_s0", &device));
+        drop(scope.scope("e0_s0_s1", &device));
+    }
+    drop(profiler.scope("e0_s1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 2);
+    assert!(profiler.overlapping_scopes().is_empty());
+}
+
+#[test]
+fn last_frame_total() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert_eq!(profiler.last_frame_total(), None);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    drop(profiler.scope("e0_s1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    let first = frame[0].time.clone().unwrap();
+    let last = frame[1].time.clone().unwrap();
+    assert_eq!(profiler.last_frame_total(), Some(last.end - first.start));
+}
+
+#[test]
+fn resolve_into_external_buffer() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope("e0_s0_s0", &device));
+    }
+
+    let required_size = profiler.required_resolve_buffer_size();
+    let offset = wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT;
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: offset + required_size,
+        usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let written = profiler.resolve_queries_into(&mut encoder, &buffer, offset);
+    assert_eq!(written, offset..offset + required_size);
+    assert_eq!(profiler.required_resolve_buffer_size(), 0);
+
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::Timestamps,
+            [expected_scope("e0_s0_s0", Requires::Timestamps, [])],
+        )],
+    );
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn gpu_profile_attribute() {
+    #[wgpu_profiler::gpu_profile]
+    fn draw(profiler: &GpuProfiler, encoder: &mut wgpu::CommandEncoder, device: &wgpu::Device) {
+        drop(encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default()));
+    }
+
+    #[wgpu_profiler::gpu_profile("custom label", profiler = p, recorder = e, device = d)]
+    fn draw_custom(p: &GpuProfiler, e: &mut wgpu::CommandEncoder, d: &wgpu::Device) {}
+
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    draw(&profiler, &mut encoder, &device);
+    draw_custom(&profiler, &mut encoder, &device);
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(
+        frame
+            .iter()
+            .map(|result| &*result.label)
+            .collect::<Vec<_>>(),
+        ["draw", "custom label"]
+    );
+}
+
+#[test]
+fn query_metadata() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let query = profiler.begin_query_with_args(
+        "draw",
+        &[("instances", 42.to_string())],
+        &mut encoder,
+        &device,
+    );
+    profiler.end_query(&mut encoder, query);
+    let query = profiler.begin_query("plain", &mut encoder, &device);
+    profiler.end_query(&mut encoder, query);
+    let query = profiler.begin_query_colored("colored", TraceColor::Good, &mut encoder, &device);
+    profiler.end_query(&mut encoder, query);
+    {
+        let mut scope = profiler.scope_with_category("shadows", "lighting", &mut encoder, &device);
+        drop(scope.scope_with_category("cascade", "lighting", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(
+        frame[0].metadata,
+        [("instances".to_owned(), "42".to_owned())]
+    );
+    assert!(frame[1].metadata.is_empty());
+    assert_eq!(frame[1].color, None);
+    assert_eq!(frame[2].color, Some(TraceColor::Good));
+    assert_eq!(frame[2].category, None);
+    assert_eq!(frame[3].category.as_deref(), Some("lighting"));
+    assert_eq!(
+        frame[3].nested_queries[0].category.as_deref(),
+        Some("lighting")
+    );
+}
+
+#[test]
+fn top_level_scoped_passes() {
+    // Pass timestamp writes don't require `TIMESTAMP_QUERY_INSIDE_PASSES`.
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scoped_compute_pass("compute0", &mut encoder, &device));
+    drop(profiler.scoped_compute_pass("compute1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(frame.len(), 2);
+    assert_eq!(frame[0].label, "compute0");
+    assert_eq!(frame[1].label, "compute1");
+    assert!(frame.iter().all(|result| result.time.is_some()));
+}
+
+#[test]
+fn active_query_sets() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert_eq!(profiler.active_query_sets().count(), 0);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let query0 = profiler.begin_query("q0", &mut encoder, &device);
+    let query1 = profiler.begin_query("q1", &mut encoder, &device);
+    assert_eq!(query0.timestamp_query_index().unwrap().1, 0);
+    assert_eq!(query1.timestamp_query_index().unwrap().1, 2);
+    profiler.end_query(&mut encoder, query1);
+    profiler.end_query(&mut encoder, query0);
+
+    assert_eq!(
+        profiler
+            .active_query_sets()
+            .map(|(_, num_used_queries)| num_used_queries)
+            .collect::<Vec<_>>(),
+        [4]
+    );
+}
+
+#[test]
+fn memory_report() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert_eq!(
+        profiler.memory_report(),
+        wgpu_profiler::MemoryReport::default()
+    );
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("e0_s0", &mut encoder, &device));
+    let report = profiler.memory_report();
+    assert_eq!(report.num_query_pools, 1);
+    assert!(report.query_set_bytes > 0);
+    assert!(report.resolve_buffer_bytes >= report.query_set_bytes);
+    assert!(report.read_buffer_bytes >= report.query_set_bytes);
+    assert_eq!(
+        report.total_bytes(),
+        report.query_set_bytes + report.resolve_buffer_bytes + report.read_buffer_bytes
+    );
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    // The pool moves on to the pending frame and is then kept around for reuse.
+    assert_eq!(profiler.memory_report(), report);
+    device.poll(wgpu::Maintain::Wait);
+    profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    assert_eq!(profiler.memory_report(), report);
+}
+
+#[test]
+fn last_frame_raw_timestamps() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert!(profiler.last_frame_raw_timestamps().is_none());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let query0 = profiler.begin_query("q0", &mut encoder, &device);
+    let query1 = profiler.begin_query("q1", &mut encoder, &device);
+    let start_index1 = query1.timestamp_query_index().unwrap().1 as usize;
+    profiler.end_query(&mut encoder, query1);
+    profiler.end_query(&mut encoder, query0);
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let raw_results = profiler.process_finished_frame_raw().unwrap();
+    let timestamps = profiler.last_frame_raw_timestamps().unwrap();
+    assert_eq!(timestamps.len(), 4);
+    let raw_result1 = raw_results.iter().find(|raw| raw.label == "q1").unwrap();
+    assert_eq!(
+        raw_result1.timestamps,
+        Some(timestamps[start_index1]..timestamps[start_index1 + 1])
+    );
+}
+
+#[test]
+fn transfer_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let source = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 1024,
+        usage: wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let destination = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 1024,
+        usage: wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("upload", &mut encoder, &device);
+        scope.copy_buffer_to_buffer(&source, 0, &destination, 0, 512);
+        let mut nested_scope = scope.scope("upload_second_half", &device);
+        nested_scope.copy_buffer_to_buffer(&source, 512, &destination, 512, 512);
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap();
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "upload",
+            Requires::Timestamps,
+            [expected_scope(
+                "upload_second_half",
+                Requires::Timestamps,
+                [],
+            )],
+        )],
+    );
+}
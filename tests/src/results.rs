@@ -0,0 +1,336 @@
+use crate::src::result;
+
+#[test]
+fn self_time() {
+    let scope = result(
+        "outer",
+        1.0..2.0,
+        [
+            result("inner", 1.0..1.25, []),
+            wgpu_profiler::GpuTimerQueryResult {
+                time: None,
+                ..result("untimed", 0.0..0.0, [])
+            },
+            result("inner", 1.5..1.75, []),
+        ],
+    );
+    assert_eq!(scope.self_time(), Some(0.5));
+    assert_eq!(scope.nested_queries[0].self_time(), Some(0.25));
+    assert_eq!(scope.nested_queries[1].self_time(), None);
+
+    // Overlapping nested scopes are clamped.
+    let overlapping = result(
+        "outer",
+        1.0..2.0,
+        [result("a", 1.0..2.0, []), result("b", 1.0..2.0, [])],
+    );
+    assert_eq!(overlapping.self_time(), Some(0.0));
+}
+
+#[test]
+fn finished_frame() {
+    let frame = wgpu_profiler::FinishedFrame::from(vec![
+        result("a", 1.0..2.0, [result("a_0", 1.25..1.5, [])]),
+        wgpu_profiler::GpuTimerQueryResult {
+            time: None,
+            ..result("untimed", 0.0..0.0, [])
+        },
+        result("b", 2.0..3.5, []),
+    ]);
+
+    assert_eq!(frame.len(), 3);
+    assert_eq!(frame.total_duration(), Some(2.5));
+    assert_eq!(frame.find("a_0").unwrap().time, Some(1.25..1.5));
+    assert!(frame.find("c").is_none());
+    assert_eq!(
+        frame
+            .iter_recursive()
+            .map(|(depth, result)| (depth, &*result.label))
+            .collect::<Vec<_>>(),
+        [(0, "a"), (1, "a_0"), (0, "untimed"), (0, "b")]
+    );
+    assert_eq!(
+        (&frame)
+            .into_iter()
+            .map(|result| &*result.label)
+            .collect::<Vec<_>>(),
+        ["a", "untimed", "b"]
+    );
+
+    let results: Vec<_> = frame.into();
+    assert_eq!(results.len(), 3);
+    assert!(wgpu_profiler::FinishedFrame::default()
+        .total_duration()
+        .is_none());
+}
+
+/// Creates a raw result with the given label, timestamps and depth.
+fn raw_result(
+    label: &str,
+    timestamps: Option<std::ops::Range<u64>>,
+    depth: usize,
+) -> wgpu_profiler::GpuTimerQueryRawResult {
+    wgpu_profiler::GpuTimerQueryRawResult {
+        label: label.to_owned().into(),
+        id: wgpu_profiler::QueryId::default(),
+        pid: 0,
+        tid: std::thread::current().id(),
+        timestamps,
+        cpu_time: None,
+        depth,
+        timeline: 0,
+        track_name: None,
+        metadata: Vec::new(),
+        color: None,
+        category: None,
+        pipeline_stats: None,
+    }
+}
+
+#[test]
+fn results_from_raw() {
+    let results = wgpu_profiler::results_from_raw(
+        vec![
+            raw_result("outer", Some(1000..3000), 0),
+            raw_result("inner", Some(1500..2000), 1),
+            raw_result("inner_inner", Some(1500..1750), 2),
+            raw_result("untimed", None, 1),
+            raw_result("zero", Some(0..0), 0),
+            raw_result("decreasing", Some(2000..1000), 0),
+        ],
+        2.0,
+    );
+
+    let labels = wgpu_profiler::flatten(&results)
+        .map(|(depth, result)| (depth, &*result.label))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        labels,
+        [
+            (0, "outer"),
+            (1, "inner"),
+            (2, "inner_inner"),
+            (1, "untimed"),
+            (0, "zero"),
+            (0, "decreasing"),
+        ]
+    );
+
+    // 2ns per tick.
+    let assert_time = |result: &wgpu_profiler::GpuTimerQueryResult,
+                       expected: std::ops::Range<f64>| {
+        let time = result.time.clone().unwrap();
+        assert!((time.start - expected.start).abs() < 1.0e-12, "{time:?}");
+        assert!((time.end - expected.end).abs() < 1.0e-12, "{time:?}");
+    };
+    assert_time(&results[0], 0.000002..0.000006);
+    assert_time(&results[0].nested_queries[0], 0.000003..0.000004);
+    assert_eq!(results[0].nested_queries[1].time, None);
+    assert_eq!(results[1].time, None);
+    assert_eq!(results[2].time, None);
+}
+
+#[test]
+fn results_from_raw_with_wrap_policy() {
+    let raw_results = || {
+        vec![
+            raw_result("wrapped", Some(0xffff_ff00..0x100), 0),
+            raw_result("decreasing", Some(2000..1000), 0),
+            raw_result("zero", Some(0..0), 0),
+        ]
+    };
+
+    let results = wgpu_profiler::results_from_raw_with_wrap_policy(
+        raw_results(),
+        1.0,
+        wgpu_profiler::TimestampWrapPolicy::Unwrap { valid_bits: 32 },
+    );
+    let timestamp_to_sec = 1.0 / 1000.0 / 1000.0 / 1000.0;
+    let start = 0xffff_ff00_u64 as f64 * timestamp_to_sec;
+    assert_eq!(
+        results[0].time,
+        Some(start..start + 512.0 * timestamp_to_sec)
+    );
+    // Small decreases can't be explained by a wrap.
+    assert_eq!(results[1].time, None);
+    assert_eq!(results[2].time, None);
+
+    // Timestamps beyond the valid bits can't be the result of a wrap either.
+    let results = wgpu_profiler::results_from_raw_with_wrap_policy(
+        raw_results(),
+        1.0,
+        wgpu_profiler::TimestampWrapPolicy::Unwrap { valid_bits: 16 },
+    );
+    assert_eq!(results[0].time, None);
+
+    let results = wgpu_profiler::results_from_raw(raw_results(), 1.0);
+    assert!(results.iter().all(|result| result.time.is_none()));
+}
+
+#[test]
+fn build_query_tree() {
+    let resolved = [100_u64, 400, 200, 300, 0, 0]
+        .iter()
+        .flat_map(|timestamp| timestamp.to_le_bytes())
+        .collect::<Vec<_>>();
+    let raw_timestamps = wgpu_profiler::decode_timestamps(&resolved).collect::<Vec<_>>();
+    assert_eq!(raw_timestamps, [100, 400, 200, 300, 0, 0]);
+
+    let record = |label: &str, timestamp_index, depth| wgpu_profiler::GpuTimerQueryRecord {
+        label: label.to_owned().into(),
+        id: wgpu_profiler::QueryId::default(),
+        pid: 0,
+        tid: std::thread::current().id(),
+        timestamp_index,
+        depth,
+        timeline: 0,
+        track_name: None,
+    };
+    let results = wgpu_profiler::build_query_tree(
+        &raw_timestamps,
+        &[
+            record("outer", Some(0), 0),
+            record("inner", Some(2), 1),
+            record("untimed", None, 1),
+            record("out_of_range", Some(5), 0),
+        ],
+        1000.0,
+    );
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].label, "outer");
+    assert_eq!(results[0].nested_queries.len(), 2);
+    assert_eq!(results[0].nested_queries[0].label, "inner");
+    assert_eq!(results[0].nested_queries[1].time, None);
+    assert_eq!(results[1].time, None);
+
+    // 1µs per tick.
+    let duration = |result: &wgpu_profiler::GpuTimerQueryResult| {
+        let time = result.time.clone().unwrap();
+        time.end - time.start
+    };
+    assert!((duration(&results[0]) - 0.0003).abs() < 1.0e-12);
+    assert!((duration(&results[0].nested_queries[0]) - 0.0001).abs() < 1.0e-12);
+}
+
+#[test]
+fn merge() {
+    let untimed = wgpu_profiler::GpuTimerQueryResult {
+        time: None,
+        ..result("untimed", 0.0..0.0, [])
+    };
+    let merged = wgpu_profiler::merge(vec![
+        vec![
+            result("main", 2.0..3.0, [result("main_nested", 2.0..2.5, [])]),
+            untimed,
+        ],
+        vec![result("shadows", 1.0..2.0, [])],
+    ])
+    .unwrap();
+    assert_eq!(
+        merged
+            .iter()
+            .map(|result| &*result.label)
+            .collect::<Vec<_>>(),
+        ["shadows", "main", "untimed"]
+    );
+    assert_eq!(merged[1].nested_queries.len(), 1);
+
+    assert_eq!(
+        wgpu_profiler::merge(vec![
+            vec![result("graphics", 1.0..2.0, [])],
+            vec![wgpu_profiler::GpuTimerQueryResult {
+                timeline: 1,
+                ..result("compute", 1.0..2.0, [])
+            }],
+        ])
+        .unwrap_err(),
+        wgpu_profiler::MergeError::DifferentTimelines {
+            first: 0,
+            second: 1
+        }
+    );
+}
+
+#[cfg(feature = "exporters")]
+#[test]
+fn print_tree() {
+    let frame = [
+        result(
+            "frame",
+            1.0..1.0045,
+            [
+                result("shadow", 1.0..1.00123, []),
+                result("main", 1.00123..1.00425, []),
+            ],
+        ),
+        wgpu_profiler::GpuTimerQueryResult {
+            time: None,
+            ..result("untimed", 0.0..0.0, [result("nested", 2.0..2.0125, [])])
+        },
+    ];
+
+    let mut text = Vec::new();
+    wgpu_profiler::print_tree(&mut text, &frame).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "frame:     4.50 ms (0.25 ms self)
+  shadow:  1.23 ms (1.23 ms self)
+  main:    3.02 ms (3.02 ms self)
+untimed:
+  nested: 12.50 ms (12.50 ms self)
+"
+    );
+
+    let mut text = Vec::new();
+    wgpu_profiler::print_tree_with_indent(&mut text, &frame[1..], 4).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "untimed:
+    nested: 12.50 ms (12.50 ms self)
+"
+    );
+}
+
+#[test]
+fn find_overlapping_scopes() {
+    let mut other_timeline = result("other_timeline", 1.5..2.5, []);
+    other_timeline.timeline = 1;
+    let results = [
+        result(
+            "first",
+            0.0..2.0,
+            [
+                result("nested_0", 0.0..1.0, []),
+                result("nested_1", 1.0..2.0, []),
+            ],
+        ),
+        result("second", 1.5..3.0, [result("nested_2", 1.5..2.0, [])]),
+        other_timeline,
+        result("third", 3.0..4.0, []),
+        result("fourth", 2.5..3.5, []),
+    ];
+
+    let overlaps = wgpu_profiler::find_overlapping_scopes(&results);
+    assert_eq!(
+        overlaps,
+        [
+            wgpu_profiler::ScopeOverlap {
+                first_label: "first".into(),
+                first_time: 0.0..2.0,
+                second_label: "second".into(),
+                second_time: 1.5..3.0,
+                depth: 0,
+                timeline: 0,
+            },
+            wgpu_profiler::ScopeOverlap {
+                first_label: "third".into(),
+                first_time: 3.0..4.0,
+                second_label: "fourth".into(),
+                second_time: 2.5..3.5,
+                depth: 0,
+                timeline: 0,
+            },
+        ]
+    );
+}
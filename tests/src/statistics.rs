@@ -0,0 +1,150 @@
+use wgpu_profiler::{category_durations, GpuProfilerStatistics};
+
+use crate::src::result;
+
+#[test]
+fn rolling_window() {
+    let mut statistics = GpuProfilerStatistics::new(2);
+
+    statistics.add_frame(&[result(
+        "outer",
+        0.0..0.004,
+        [
+            result("inner", 0.0..0.001, []),
+            result("inner", 0.002..0.003, []),
+        ],
+    )]);
+    statistics.add_frame(&[result("outer", 0.0..0.002, [])]);
+
+    let outer = statistics.get("outer").unwrap();
+    assert_eq!(outer.num_samples, 2);
+    assert_eq!(outer.min_ms, 2.0);
+    assert_eq!(outer.max_ms, 4.0);
+    assert_eq!(outer.mean_ms, 3.0);
+    assert_eq!(outer.p95_ms, 4.0);
+
+    // Both occurrences of inner in the first frame are summed up.
+    let inner = statistics.get("outer/inner").unwrap();
+    assert_eq!(inner.num_samples, 1);
+
+    // Inner leaves the window, outer remains.
+    statistics.add_frame(&[result("outer", 0.0..0.002, [])]);
+    assert!(statistics.get("outer/inner").is_none());
+    assert_eq!(statistics.get("outer").unwrap().num_samples, 2);
+}
+
+#[test]
+fn category_statistics() {
+    let categorized =
+        |label: &str, category: &'static str, range, nested| wgpu_profiler::GpuTimerQueryResult {
+            category: Some(category.into()),
+            ..result(label, range, nested)
+        };
+
+    let frame = [
+        categorized(
+            "shadows",
+            "lighting",
+            0.0..0.5,
+            // Already included in the parent's duration.
+            vec![categorized("cascade", "lighting", 0.0..0.25, vec![])],
+        ),
+        result(
+            "post",
+            0.5..1.0,
+            [categorized("bloom", "blur", 0.5..0.75, vec![])],
+        ),
+        categorized("sky", "lighting", 1.0..1.25, vec![]),
+    ];
+    assert_eq!(
+        category_durations(&frame),
+        [("lighting", 750.0), ("blur", 250.0)]
+    );
+
+    let mut statistics = GpuProfilerStatistics::new(2);
+    statistics.add_frame(&frame);
+    statistics.add_frame(&[categorized("sky", "lighting", 0.0..0.25, vec![])]);
+
+    let lighting = statistics.category("lighting").unwrap();
+    assert_eq!(lighting.num_samples, 2);
+    assert_eq!(lighting.mean_ms, 500.0);
+    assert_eq!(statistics.category("blur").unwrap().num_samples, 1);
+
+    // Blur leaves the window.
+    statistics.add_frame(&[]);
+    assert!(statistics.category("blur").is_none());
+    assert_eq!(statistics.iter_categories().count(), 1);
+}
+
+#[test]
+fn smoothed_durations() {
+    let mut smoothed = wgpu_profiler::SmoothedProfiler::new(0.5);
+
+    smoothed.add_frame(&[result(
+        "outer",
+        0.0..4.0,
+        [result("inner", 0.0..1.0, []), result("inner", 1.0..2.0, [])],
+    )]);
+    smoothed.add_frame(&[result("outer", 10.0..12.0, [])]);
+
+    let current = smoothed.current();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0].time, Some(0.0..3.0));
+    assert!(current[0].nested_queries.is_empty());
+
+    // Inner reappears and continues from its previous smoothed duration,
+    // the second inner is smoothed separately from the first.
+    smoothed.add_frame(&[result(
+        "outer",
+        20.0..22.0,
+        [
+            result("inner", 20.0..23.0, []),
+            result("inner", 23.0..24.0, []),
+        ],
+    )]);
+
+    let current = smoothed.current();
+    assert_eq!(current[0].time, Some(0.0..2.5));
+    assert_eq!(current[0].nested_queries[0].time, Some(0.0..2.0));
+    assert_eq!(current[0].nested_queries[1].time, Some(2.0..3.0));
+}
+
+#[test]
+fn duration_histogram() {
+    let mut histogram = wgpu_profiler::DurationHistogram::new("outer/inner", 1.0, 3);
+
+    // Durations of 0.5ms, 1.5ms (summed up), 2.5ms and 10ms, all well within their buckets.
+    histogram.add_frame(&[result(
+        "outer",
+        0.0..1.0,
+        [result("inner", 0.0..0.0005, [])],
+    )]);
+    histogram.add_frame(&[result(
+        "outer",
+        0.0..1.0,
+        [
+            result("inner", 0.0..0.001, []),
+            result("inner", 0.001..0.0015, []),
+        ],
+    )]);
+    histogram.add_frame(&[result(
+        "outer",
+        0.0..1.0,
+        [result("inner", 0.0..0.0025, [])],
+    )]);
+    histogram.add_frame(&[result("outer", 0.0..1.0, [result("inner", 0.0..0.01, [])])]);
+    // Absent scope doesn't add a sample.
+    histogram.add_frame(&[result("outer", 0.0..1.0, [])]);
+
+    assert_eq!(histogram.bucket_counts(), [1, 1, 2]);
+    assert_eq!(histogram.num_samples(), 4);
+    assert_eq!(histogram.bucket_range_ms(1), 1.0..2.0);
+    assert_eq!(histogram.bucket_range_ms(2), 2.0..f64::INFINITY);
+
+    let text = histogram.to_ascii(4);
+    assert_eq!(text.lines().count(), 4);
+    assert!(text.lines().last().unwrap().ends_with("| #### 2"));
+
+    histogram.reset();
+    assert_eq!(histogram.num_samples(), 0);
+}
@@ -0,0 +1,145 @@
+//! Attribute macros for `wgpu-profiler`.
+//!
+//! Use these via the `macros` feature of `wgpu-profiler` rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    FnArg, Ident, ItemFn, LitStr, Pat, Token,
+};
+
+/// Arguments of [`macro@gpu_profile`].
+struct GpuProfileArgs {
+    label: Option<LitStr>,
+    profiler: Ident,
+    recorder: Ident,
+    device: Ident,
+}
+
+/// A single `key = ident` argument.
+struct NamedArg {
+    key: Ident,
+    value: Ident,
+}
+
+impl Parse for NamedArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(Self { key, value })
+    }
+}
+
+impl Parse for GpuProfileArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Self {
+            label: None,
+            profiler: Ident::new("profiler", Span::call_site()),
+            recorder: Ident::new("encoder", Span::call_site()),
+            device: Ident::new("device", Span::call_site()),
+        };
+
+        if input.peek(LitStr) {
+            args.label = Some(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        for named in Punctuated::<NamedArg, Token![,]>::parse_terminated(input)? {
+            match named.key.to_string().as_str() {
+                "profiler" => args.profiler = named.value,
+                "recorder" => args.recorder = named.value,
+                "device" => args.device = named.value,
+                key => {
+                    return Err(syn::Error::new(
+                        named.key.span(),
+                        format!(
+                            "unknown argument `{key}`, expected `profiler`, `recorder` or `device`"
+                        ),
+                    ))
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Wraps the body of a function in a profiler scope.
+///
+/// The function needs arguments for the profiler (`&GpuProfiler` or `&mut GpuProfiler`),
+/// the recorder (`&mut` to any `ProfilerCommandRecorder`) and the device (`&wgpu::Device`).
+/// By default these are expected to be called `profiler`, `encoder` and `device`.
+/// The scope's label defaults to the name of the function.
+///
+/// Within the function body, the recorder argument refers to the recorder of the scope,
+/// so everything recorded on it is part of the scope.
+/// Like all scopes created directly on the profiler, the scope has no parent:
+/// calling another profiled function from within the body produces a top-level scope as well.
+///
+/// ```ignore
+/// use wgpu_profiler::{gpu_profile, GpuProfiler};
+///
+/// #[gpu_profile]
+/// fn draw_shadows(profiler: &GpuProfiler, encoder: &mut wgpu::CommandEncoder, device: &wgpu::Device) {
+///     // ...
+/// }
+///
+/// #[gpu_profile("gbuffer", profiler = gpu_profiler, recorder = pass)]
+/// fn draw_gbuffer(gpu_profiler: &GpuProfiler, pass: &mut wgpu::RenderPass, device: &wgpu::Device) {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn gpu_profile(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as GpuProfileArgs);
+    let function = parse_macro_input!(item as ItemFn);
+
+    for expected in [&args.profiler, &args.recorder, &args.device] {
+        let has_argument = function.sig.inputs.iter().any(|input| match input {
+            FnArg::Typed(typed) => matches!(&*typed.pat, Pat::Ident(pat) if pat.ident == *expected),
+            FnArg::Receiver(_) => false,
+        });
+        if !has_argument {
+            return syn::Error::new(
+                function.sig.ident.span(),
+                format!("function has no argument named `{expected}`"),
+            )
+            .into_compile_error()
+            .into();
+        }
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+    let label = args
+        .label
+        .unwrap_or_else(|| LitStr::new(&sig.ident.to_string(), sig.ident.span()));
+    let GpuProfileArgs {
+        profiler,
+        recorder,
+        device,
+        ..
+    } = args;
+
+    quote! {
+        #(#attrs)*
+        #vis #sig {
+            let mut __wgpu_profiler_scope = #profiler.scope(#label, &mut *#recorder, #device);
+            #[allow(unused_variables)]
+            let #recorder = &mut *__wgpu_profiler_scope;
+            #block
+        }
+    }
+    .into()
+}